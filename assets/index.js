@@ -4,11 +4,16 @@
  * @property {string} name
  * @property {number} mtime
  * @property {number} size
+ * @property {string} [human_size]
+ * @property {boolean} [is_recent]
+ * @property {string} [symlink_target]
+ * @property {string} [snippet]
  */
 
 /**
  * @typedef {object} DATA
  * @property {string} href
+ * @property {string|null} parent_href
  * @property {string} uri_prefix
  * @property {"Index" | "Edit" | "View"} kind
  * @property {PathItem[]} paths
@@ -16,9 +21,18 @@
  * @property {boolean} allow_delete
  * @property {boolean} allow_search
  * @property {boolean} allow_archive
+ * @property {boolean} allow_symlink
  * @property {boolean} auth
  * @property {string} user
+ * @property {string} lang
  * @property {boolean} dir_exists
+ * @property {string} sort
+ * @property {string} order
+ * @property {number} page
+ * @property {number} per_page
+ * @property {number} total_count
+ * @property {number} total_size
+ * @property {boolean} truncated
  * @property {string} editable
  */
 
@@ -38,11 +52,195 @@ var DIR_EMPTY_NOTE;
  * @type {PARAMS}
  * @typedef {object} PARAMS
  * @property {string} q
+ * @property {string} grep
  * @property {string} sort
  * @property {string} order
+ * @property {string} expires
+ * @property {string} token
  */
 const PARAMS = Object.fromEntries(new URLSearchParams(window.location.search).entries());
 
+/**
+ * The signature of a share link, carried over to every link of the page
+ */
+const SHARE_QS = PARAMS.expires && PARAMS.token ? new URLSearchParams({ expires: PARAMS.expires, token: PARAMS.token }).toString() : "";
+
+/**
+ * Labels of the UI keyed by their English text, the languages must match `UI_LANGS` in utils.rs
+ */
+const I18N = {
+  de: {
+    "Name": "Name",
+    "Last Modified": "Zuletzt geändert",
+    "Size": "Größe",
+    "Actions": "Aktionen",
+    "Progress": "Fortschritt",
+    "Upload files": "Dateien hochladen",
+    "Upload folder": "Ordner hochladen",
+    "New folder": "Neuer Ordner",
+    "New File": "Neue Datei",
+    "Delete": "Löschen",
+    "Move to new path": "Verschieben",
+    "Download file": "Datei herunterladen",
+    "Download folder as a .zip file": "Ordner als .zip herunterladen",
+    "Edit file": "Datei bearbeiten",
+    "View file": "Datei ansehen",
+    "Save file": "Datei speichern",
+    "Login": "Anmelden",
+    "Logout": "Abmelden",
+    "Searching for folders or files": "Nach Ordnern oder Dateien suchen",
+    "Root": "Stammverzeichnis",
+    "Parent directory": "Übergeordneter Ordner",
+    "Empty folder": "Leerer Ordner",
+    "No results": "Keine Ergebnisse",
+    "Folder will be created when a file is uploaded": "Der Ordner wird beim Hochladen einer Datei erstellt",
+    "Enter folder name": "Ordnername eingeben",
+    "Enter file name": "Dateiname eingeben",
+    "Enter new path": "Neuen Pfad eingeben",
+    "Retry": "Erneut versuchen",
+    "Previous page": "Vorherige Seite",
+    "Next page": "Nächste Seite",
+    "items": "Einträge",
+    "The listing was truncated": "Die Liste wurde gekürzt",
+  },
+  es: {
+    "Name": "Nombre",
+    "Last Modified": "Última modificación",
+    "Size": "Tamaño",
+    "Actions": "Acciones",
+    "Progress": "Progreso",
+    "Upload files": "Subir archivos",
+    "Upload folder": "Subir carpeta",
+    "New folder": "Nueva carpeta",
+    "New File": "Nuevo archivo",
+    "Delete": "Eliminar",
+    "Move to new path": "Mover a otra ruta",
+    "Download file": "Descargar archivo",
+    "Download folder as a .zip file": "Descargar carpeta como .zip",
+    "Edit file": "Editar archivo",
+    "View file": "Ver archivo",
+    "Save file": "Guardar archivo",
+    "Login": "Iniciar sesión",
+    "Logout": "Cerrar sesión",
+    "Searching for folders or files": "Buscar carpetas o archivos",
+    "Root": "Raíz",
+    "Parent directory": "Carpeta superior",
+    "Empty folder": "Carpeta vacía",
+    "No results": "Sin resultados",
+    "Folder will be created when a file is uploaded": "La carpeta se creará al subir un archivo",
+    "Enter folder name": "Introduce el nombre de la carpeta",
+    "Enter file name": "Introduce el nombre del archivo",
+    "Enter new path": "Introduce la nueva ruta",
+    "Retry": "Reintentar",
+    "Previous page": "Página anterior",
+    "Next page": "Página siguiente",
+    "items": "elementos",
+    "The listing was truncated": "La lista está truncada",
+  },
+  fr: {
+    "Name": "Nom",
+    "Last Modified": "Dernière modification",
+    "Size": "Taille",
+    "Actions": "Actions",
+    "Progress": "Progression",
+    "Upload files": "Téléverser des fichiers",
+    "Upload folder": "Téléverser un dossier",
+    "New folder": "Nouveau dossier",
+    "New File": "Nouveau fichier",
+    "Delete": "Supprimer",
+    "Move to new path": "Déplacer",
+    "Download file": "Télécharger le fichier",
+    "Download folder as a .zip file": "Télécharger le dossier en .zip",
+    "Edit file": "Modifier le fichier",
+    "View file": "Afficher le fichier",
+    "Save file": "Enregistrer le fichier",
+    "Login": "Se connecter",
+    "Logout": "Se déconnecter",
+    "Searching for folders or files": "Rechercher des dossiers ou des fichiers",
+    "Root": "Racine",
+    "Parent directory": "Dossier parent",
+    "Empty folder": "Dossier vide",
+    "No results": "Aucun résultat",
+    "Folder will be created when a file is uploaded": "Le dossier sera créé lors du téléversement d'un fichier",
+    "Enter folder name": "Saisissez le nom du dossier",
+    "Enter file name": "Saisissez le nom du fichier",
+    "Enter new path": "Saisissez le nouveau chemin",
+    "Retry": "Réessayer",
+    "Previous page": "Page précédente",
+    "Next page": "Page suivante",
+    "items": "éléments",
+    "The listing was truncated": "La liste est tronquée",
+  },
+  ja: {
+    "Name": "名前",
+    "Last Modified": "更新日時",
+    "Size": "サイズ",
+    "Actions": "操作",
+    "Progress": "進捗",
+    "Upload files": "ファイルをアップロード",
+    "Upload folder": "フォルダをアップロード",
+    "New folder": "新しいフォルダ",
+    "New File": "新しいファイル",
+    "Delete": "削除",
+    "Move to new path": "移動",
+    "Download file": "ファイルをダウンロード",
+    "Download folder as a .zip file": "フォルダを .zip でダウンロード",
+    "Edit file": "ファイルを編集",
+    "View file": "ファイルを表示",
+    "Save file": "ファイルを保存",
+    "Login": "ログイン",
+    "Logout": "ログアウト",
+    "Searching for folders or files": "フォルダやファイルを検索",
+    "Root": "ルート",
+    "Parent directory": "親フォルダ",
+    "Empty folder": "空のフォルダ",
+    "No results": "結果なし",
+    "Folder will be created when a file is uploaded": "ファイルをアップロードするとフォルダが作成されます",
+    "Enter folder name": "フォルダ名を入力",
+    "Enter file name": "ファイル名を入力",
+    "Enter new path": "新しいパスを入力",
+    "Retry": "再試行",
+    "Previous page": "前のページ",
+    "Next page": "次のページ",
+    "items": "件",
+    "The listing was truncated": "一覧は途中までです",
+  },
+  zh: {
+    "Name": "名称",
+    "Last Modified": "修改时间",
+    "Size": "大小",
+    "Actions": "操作",
+    "Progress": "进度",
+    "Upload files": "上传文件",
+    "Upload folder": "上传文件夹",
+    "New folder": "新建文件夹",
+    "New File": "新建文件",
+    "Delete": "删除",
+    "Move to new path": "移动到新路径",
+    "Download file": "下载文件",
+    "Download folder as a .zip file": "将文件夹下载为 .zip",
+    "Edit file": "编辑文件",
+    "View file": "查看文件",
+    "Save file": "保存文件",
+    "Login": "登录",
+    "Logout": "退出登录",
+    "Searching for folders or files": "搜索文件夹或文件",
+    "Root": "根目录",
+    "Parent directory": "上级目录",
+    "Empty folder": "空文件夹",
+    "No results": "无结果",
+    "Folder will be created when a file is uploaded": "上传文件时将创建该文件夹",
+    "Enter folder name": "输入文件夹名称",
+    "Enter file name": "输入文件名",
+    "Enter new path": "输入新路径",
+    "Retry": "重试",
+    "Previous page": "上一页",
+    "Next page": "下一页",
+    "items": "项",
+    "The listing was truncated": "列表已截断",
+  },
+};
+
 const IFRAME_FORMATS = [
   ".pdf",
   ".jpg", ".jpeg", ".png", ".gif", ".bmp", ".svg",
@@ -113,7 +311,7 @@ window.addEventListener("DOMContentLoaded", async () => {
   }
 
   DATA = JSON.parse(decodeBase64($indexData.innerHTML));
-  DIR_EMPTY_NOTE = PARAMS.q ? 'No results' : DATA.dir_exists ? 'Empty folder' : 'Folder will be created when a file is uploaded';
+  DIR_EMPTY_NOTE = t(PARAMS.q || PARAMS.grep ? 'No results' : DATA.dir_exists ? 'Empty folder' : 'Folder will be created when a file is uploaded');
 
   await ready();
 });
@@ -129,6 +327,7 @@ async function ready() {
   $logoutBtn = document.querySelector(".logout-btn");
   $userName = document.querySelector(".user-name");
 
+  localize();
   addBreadcrumb(DATA.href, DATA.uri_prefix);
 
   if (DATA.kind === "Index") {
@@ -268,7 +467,7 @@ class Uploader {
   }
 
   fail(reason = "") {
-    this.$uploadStatus.innerHTML = `<span style="width: 20px;" title="${reason}">✗</span><span class="retry-btn" id="retry${this.idx}" title="Retry">↻</span>`;
+    this.$uploadStatus.innerHTML = `<span style="width: 20px;" title="${reason}">✗</span><span class="retry-btn" id="retry${this.idx}" title="${t("Retry")}">↻</span>`;
     failUploaders.set(this.idx, this);
     Uploader.runnings--;
     Uploader.runQueue();
@@ -328,11 +527,11 @@ function addBreadcrumb(href, uri_prefix) {
     }
     const encodedName = encodedStr(name);
     if (i === 0) {
-      $breadcrumb.insertAdjacentHTML("beforeend", `<a href="${path}" title="Root"><svg width="16" height="16" viewBox="0 0 16 16"><path d="M6.5 14.5v-3.505c0-.245.25-.495.5-.495h2c.25 0 .5.25.5.5v3.5a.5.5 0 0 0 .5.5h4a.5.5 0 0 0 .5-.5v-7a.5.5 0 0 0-.146-.354L13 5.793V2.5a.5.5 0 0 0-.5-.5h-1a.5.5 0 0 0-.5.5v1.293L8.354 1.146a.5.5 0 0 0-.708 0l-6 6A.5.5 0 0 0 1.5 7.5v7a.5.5 0 0 0 .5.5h4a.5.5 0 0 0 .5-.5z"/></svg></a>`);
+      $breadcrumb.insertAdjacentHTML("beforeend", `<a href="${shareUrl(path)}" title="${t("Root")}"><svg width="16" height="16" viewBox="0 0 16 16"><path d="M6.5 14.5v-3.505c0-.245.25-.495.5-.495h2c.25 0 .5.25.5.5v3.5a.5.5 0 0 0 .5.5h4a.5.5 0 0 0 .5-.5v-7a.5.5 0 0 0-.146-.354L13 5.793V2.5a.5.5 0 0 0-.5-.5h-1a.5.5 0 0 0-.5.5v1.293L8.354 1.146a.5.5 0 0 0-.708 0l-6 6A.5.5 0 0 0 1.5 7.5v7a.5.5 0 0 0 .5.5h4a.5.5 0 0 0 .5-.5z"/></svg></a>`);
     } else if (i === len - 1) {
       $breadcrumb.insertAdjacentHTML("beforeend", `<b>${encodedName}</b>`);
     } else {
-      $breadcrumb.insertAdjacentHTML("beforeend", `<a href="${path}">${encodedName}</a>`);
+      $breadcrumb.insertAdjacentHTML("beforeend", `<a href="${shareUrl(path)}">${encodedName}</a>`);
     }
     if (i !== len - 1) {
       $breadcrumb.insertAdjacentHTML("beforeend", `<span class="separator">/</span>`);
@@ -343,7 +542,7 @@ function addBreadcrumb(href, uri_prefix) {
 async function setupIndexPage() {
   if (DATA.allow_archive) {
     const $download = document.querySelector(".download");
-    $download.href = baseUrl() + "?zip";
+    $download.href = shareUrl(baseUrl() + "?zip");
     $download.title = "Download folder as a .zip file";
     $download.classList.remove("hidden");
   }
@@ -375,17 +574,17 @@ function renderPathsTableHead() {
     {
       name: "name",
       props: `colspan="2"`,
-      text: "Name",
+      text: t("Name"),
     },
     {
       name: "mtime",
       props: ``,
-      text: "Last Modified",
+      text: t("Last Modified"),
     },
     {
       name: "size",
       props: ``,
-      text: "Size",
+      text: t("Size"),
     }
   ];
   $pathsTableHead.insertAdjacentHTML("beforeend", `
@@ -393,8 +592,8 @@ function renderPathsTableHead() {
       ${headerItems.map(item => {
     let svg = `<svg width="12" height="12" viewBox="0 0 16 16"><path fill-rule="evenodd" d="M11.5 15a.5.5 0 0 0 .5-.5V2.707l3.146 3.147a.5.5 0 0 0 .708-.708l-4-4a.5.5 0 0 0-.708 0l-4 4a.5.5 0 1 0 .708.708L11 2.707V14.5a.5.5 0 0 0 .5.5zm-7-14a.5.5 0 0 1 .5.5v11.793l3.146-3.147a.5.5 0 0 1 .708.708l-4 4a.5.5 0 0 1-.708 0l-4-4a.5.5 0 0 1 .708-.708L4 13.293V1.5a.5.5 0 0 1 .5-.5z"/></svg>`;
     let order = "desc";
-    if (PARAMS.sort === item.name) {
-      if (PARAMS.order === "desc") {
+    if (DATA.sort === item.name) {
+      if (DATA.order === "desc") {
         order = "asc";
         svg = `<svg width="12" height="12" viewBox="0 0 16 16"><path fill-rule="evenodd" d="M8 1a.5.5 0 0 1 .5.5v11.793l3.146-3.147a.5.5 0 0 1 .708.708l-4 4a.5.5 0 0 1-.708 0l-4-4a.5.5 0 0 1 .708-.708L7.5 13.293V1.5A.5.5 0 0 1 8 1z"/></svg>`
       } else {
@@ -405,7 +604,7 @@ function renderPathsTableHead() {
     const icon = `<span>${svg}</span>`
     return `<th class="cell-${item.name}" ${item.props}><a href="?${qs}">${item.text}${icon}</a></th>`
   }).join("\n")}
-      <th class="cell-actions">Actions</th>
+      <th class="cell-actions">${t("Actions")}</th>
     </tr>
   `);
 }
@@ -414,6 +613,21 @@ function renderPathsTableHead() {
  * Render path table tbody
  */
 function renderPathsTableBody() {
+  if (DATA.parent_href) {
+    $pathsTable.classList.remove("hidden");
+    $pathsTableBody.insertAdjacentHTML("beforeend", `
+<tr class="parent-path">
+  <td class="path cell-icon">
+    ${ICONS.dir}
+  </td>
+  <td class="path cell-name">
+    <a href="${shareUrl(DATA.parent_href)}" title="${t("Parent directory")}">..</a>
+  </td>
+  <td class="cell-mtime"></td>
+  <td class="cell-size"></td>
+  <td class="cell-actions"></td>
+</tr>`);
+  }
   if (DATA.paths && DATA.paths.length > 0) {
     const len = DATA.paths.length;
     if (len > 0) {
@@ -426,6 +640,39 @@ function renderPathsTableBody() {
     $emptyFolder.textContent = DIR_EMPTY_NOTE;
     $emptyFolder.classList.remove("hidden");
   }
+  renderSummary();
+  renderPager();
+}
+
+/**
+ * Render the number of entries and the size of the files in the folder
+ */
+function renderSummary() {
+  if (!DATA.total_count) return;
+  const $summary = document.querySelector(".summary");
+  $summary.textContent = `${DATA.total_count.toLocaleString()} ${t("items")}, ${formatSize(DATA.total_size).join(" ")}`;
+  if (DATA.truncated) {
+    $summary.textContent += ` (${t("The listing was truncated")})`;
+  }
+  $summary.classList.remove("hidden");
+}
+
+/**
+ * Render links to the neighbour pages of a paginated listing
+ */
+function renderPager() {
+  const pages = Math.ceil(DATA.total_count / DATA.per_page);
+  if (pages <= 1) return;
+  const link = (page, text) => {
+    const qs = new URLSearchParams({ ...PARAMS, page }).toString();
+    return `<a href="?${qs}">${t(text)}</a>`;
+  };
+  const $pager = document.querySelector(".pager");
+  $pager.insertAdjacentHTML("beforeend", `
+${DATA.page > 1 ? link(DATA.page - 1, "Previous page") : ""}
+<span>${DATA.page} / ${pages}</span>
+${DATA.page < pages ? link(DATA.page + 1, "Next page") : ""}`);
+  $pager.classList.remove("hidden");
 }
 
 /**
@@ -447,27 +694,27 @@ function addPath(file, index) {
     if (DATA.allow_archive) {
       actionDownload = `
       <div class="action-btn">
-        <a href="${url}?zip" title="Download folder as a .zip file">${ICONS.download}</a>
+        <a href="${shareUrl(url + "?zip")}" title="${t("Download folder as a .zip file")}">${ICONS.download}</a>
       </div>`;
     }
   } else {
     actionDownload = `
     <div class="action-btn" >
-      <a href="${url}" title="Download file" download>${ICONS.download}</a>
+      <a href="${shareUrl(url)}" title="${t("Download file")}" download>${ICONS.download}</a>
     </div>`;
   }
   if (DATA.allow_delete) {
     if (DATA.allow_upload) {
-      actionMove = `<div onclick="movePath(${index})" class="action-btn" id="moveBtn${index}" title="Move to new path">${ICONS.move}</div>`;
+      actionMove = `<div onclick="movePath(${index})" class="action-btn" id="moveBtn${index}" title="${t("Move to new path")}">${ICONS.move}</div>`;
       if (!isDir) {
-        actionEdit = `<a class="action-btn" title="Edit file" target="_blank" href="${url}?edit">${ICONS.edit}</a>`;
+        actionEdit = `<a class="action-btn" title="${t("Edit file")}" target="_blank" href="${url}?edit">${ICONS.edit}</a>`;
       }
     }
     actionDelete = `
-    <div onclick="deletePath(${index})" class="action-btn" id="deleteBtn${index}" title="Delete">${ICONS.delete}</div>`;
+    <div onclick="deletePath(${index})" class="action-btn" id="deleteBtn${index}" title="${t("Delete")}">${ICONS.delete}</div>`;
   }
   if (!actionEdit && !isDir) {
-    actionView = `<a class="action-btn" title="View file" target="_blank" href="${url}?view">${ICONS.view}</a>`;
+    actionView = `<a class="action-btn" title="${t("View file")}" target="_blank" href="${shareUrl(url + "?view")}">${ICONS.view}</a>`;
   }
   let actionCell = `
   <td class="cell-actions">
@@ -478,15 +725,22 @@ function addPath(file, index) {
     ${actionEdit}
   </td>`;
   
-  let sizeDisplay = isDir ? `${file.size} ${file.size === 1 ? "item" : "items"}` : formatSize(file.size).join(" ");
+  let sizeDisplay = isDir ? `${file.size} ${file.size === 1 ? "item" : "items"}` : (file.human_size || formatSize(file.size).join(" "));
+
+  let isSymlink = file.path_type.startsWith("Symlink");
+  let symlinkBadge = isSymlink ? `<span class="symlink-badge" title="Symbolic link">link</span>` : "";
+  let symlinkTarget = file.symlink_target ? `<span class="symlink-target"> → ${encodedStr(file.symlink_target)}</span>` : "";
+  let snippet = file.snippet ? `<div class="snippet">${encodedStr(file.snippet)}</div>` : "";
+  let recentBadge = file.is_recent ? `<span class="recent-badge" title="Recently modified">new</span>` : "";
+  let rowClasses = [isSymlink ? "symlink" : "", file.is_recent ? "recent" : ""].filter(v => v).join(" ");
 
   $pathsTableBody.insertAdjacentHTML("beforeend", `
-<tr id="addPath${index}">
+<tr id="addPath${index}"${rowClasses ? ` class="${rowClasses}"` : ""}>
   <td class="path cell-icon">
     ${getPathSvg(file.path_type)}
   </td>
   <td class="path cell-name">
-    <a href="${url}" ${isDir ? "" : `target="_blank"`}>${encodedName}</a>
+    <a href="${shareUrl(url)}" ${isDir ? "" : `target="_blank"`}>${encodedName}</a>${recentBadge}${symlinkBadge}${symlinkTarget}${snippet}
   </td>
   <td class="cell-mtime">${formatMtime(file.mtime)}</td>
   <td class="cell-size">${sizeDisplay}</td>
@@ -560,13 +814,23 @@ function setupUploadFile() {
       new Uploader(file, []).upload();
     }
   });
+  if (!("webkitdirectory" in document.createElement("input"))) return;
+  document.querySelector(".upload-folder").classList.remove("hidden");
+  document.getElementById("folder").addEventListener("change", async e => {
+    const files = e.target.files;
+    for (let file of files) {
+      // webkitRelativePath is `folder/sub/name`, keep its directories
+      const dirs = file.webkitRelativePath.split("/").slice(0, -1);
+      new Uploader(file, dirs).upload();
+    }
+  });
 }
 
 function setupNewFolder() {
   const $newFolder = document.querySelector(".new-folder");
   $newFolder.classList.remove("hidden");
   $newFolder.addEventListener("click", () => {
-    const name = prompt("Enter folder name");
+    const name = prompt(t("Enter folder name"));
     if (name) createFolder(name);
   });
 }
@@ -575,7 +839,7 @@ function setupNewFile() {
   const $newFile = document.querySelector(".new-file");
   $newFile.classList.remove("hidden");
   $newFile.addEventListener("click", () => {
-    const name = prompt("Enter file name");
+    const name = prompt(t("Enter file name"));
     if (name) createFile(name);
   });
 }
@@ -703,7 +967,7 @@ async function doMovePath(fileUrl) {
 
   const filePath = decodeURIComponent(fileUrlObj.pathname.slice(prefix.length));
 
-  let newPath = prompt("Enter new path", filePath);
+  let newPath = prompt(t("Enter new path"), filePath);
   if (!newPath) return;
   if (!newPath.startsWith("/")) newPath = "/" + newPath;
   if (filePath === newPath) return;
@@ -832,6 +1096,15 @@ function newUrl(name) {
   return url;
 }
 
+/**
+ * Append the share link signature, if the page was opened through one
+ * @param {string} url
+ */
+function shareUrl(url) {
+  if (!SHARE_QS) return url;
+  return url + (url.includes("?") ? "&" : "?") + SHARE_QS;
+}
+
 function baseUrl() {
   return location.href.split(/[?#]/)[0];
 }
@@ -906,6 +1179,28 @@ function formatPercent(percent) {
   }
 }
 
+/**
+ * Translate a UI label into `DATA.lang`, falling back to English
+ * @param {string} text
+ */
+function t(text) {
+  const labels = I18N[DATA.lang] || {};
+  return labels[text] || text;
+}
+
+/**
+ * Translate the labels and tooltips written into index.html
+ */
+function localize() {
+  document.documentElement.lang = DATA.lang || "en";
+  document.querySelectorAll("[title]").forEach($el => {
+    $el.title = t($el.title);
+  });
+  document.querySelectorAll(".uploaders-table th").forEach($el => {
+    $el.textContent = t($el.textContent);
+  });
+}
+
 function encodedStr(rawStr) {
   return rawStr.replace(/[\u00A0-\u9999<>\&]/g, function (i) {
     return '&#' + i.charCodeAt(0) + ';';
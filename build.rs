@@ -0,0 +1,21 @@
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    if Path::new(".git").exists() {
+        println!("cargo:rerun-if-changed=.git/HEAD");
+        println!("cargo:rerun-if-changed=.git/refs");
+    } else {
+        // tarball and crates.io builds have no repository to watch
+        println!("cargo:rerun-if-changed=build.rs");
+    }
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|v| v.status.success())
+        .and_then(|v| String::from_utf8(v.stdout).ok());
+    if let Some(hash) = hash {
+        println!("cargo:rustc-env=DUFS_GIT_HASH={}", hash.trim());
+    }
+}
@@ -1,17 +1,20 @@
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use async_zip::Compression;
 use clap::builder::{PossibleValue, PossibleValuesParser};
 use clap::{value_parser, Arg, ArgAction, ArgMatches, Command, ValueEnum};
 use clap_complete::{generate, Generator, Shell};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use hyper::header::{HeaderMap, HeaderName, HeaderValue};
+use hyper::Method;
 use serde::{Deserialize, Deserializer};
 use smart_default::SmartDefault;
 use std::env;
 use std::net::IpAddr;
 use std::path::{Path, PathBuf};
 
-use crate::auth::AccessControl;
+use crate::auth::{AccessControl, DIR_AUTH_FILE};
 use crate::http_logger::HttpLogger;
-use crate::utils::encode_uri;
+use crate::utils::{encode_uri, UI_LANGS};
 
 pub fn build_cli() -> Command {
     let app = Command::new(env!("CARGO_CRATE_NAME"))
@@ -68,6 +71,78 @@ pub fn build_cli() -> Command {
                 .value_name("path")
                 .help("Specify a path prefix"),
         )
+        .arg(
+            Arg::new("max-path-length")
+                .env("DUFS_MAX_PATH_LENGTH")
+				.hide_env(true)
+                .long("max-path-length")
+                .value_parser(value_parser!(usize))
+                .help("Set the maximum length of a decoded request path [default: 4096]")
+                .value_name("length"),
+        )
+        .arg(
+            Arg::new("max-path-depth")
+                .env("DUFS_MAX_PATH_DEPTH")
+				.hide_env(true)
+                .long("max-path-depth")
+                .value_parser(value_parser!(usize))
+                .help("Set the maximum number of components in a request path [default: 128]")
+                .value_name("depth"),
+        )
+        .arg(
+            Arg::new("max-concurrent-uploads")
+                .env("DUFS_MAX_CONCURRENT_UPLOADS")
+				.hide_env(true)
+                .long("max-concurrent-uploads")
+                .value_parser(value_parser!(usize))
+                .help("Limit the number of uploads a single client may run at once")
+                .value_name("num"),
+        )
+        .arg(
+            Arg::new("max-connections")
+                .env("DUFS_MAX_CONNECTIONS")
+				.hide_env(true)
+                .long("max-connections")
+                .value_parser(value_parser!(u64).range(1..))
+                .help("Answer 503 once this many requests are being served at once")
+                .value_name("num"),
+        )
+        .arg(
+            Arg::new("max-upload-size")
+                .env("DUFS_MAX_UPLOAD_SIZE")
+				.hide_env(true)
+                .long("max-upload-size")
+                .value_parser(value_parser!(u64).range(1..))
+                .help("Reject uploads, or appends and ranged writes, growing a file past this many bytes with 413")
+                .value_name("bytes"),
+        )
+        .arg(
+            Arg::new("max-list-entries")
+                .env("DUFS_MAX_LIST_ENTRIES")
+				.hide_env(true)
+                .long("max-list-entries")
+                .value_parser(value_parser!(u64).range(1..))
+                .help("Stop listing or searching a folder after this many entries")
+                .value_name("num"),
+        )
+        .arg(
+            Arg::new("idle-timeout")
+                .env("DUFS_IDLE_TIMEOUT")
+				.hide_env(true)
+                .long("idle-timeout")
+                .value_parser(value_parser!(u64).range(1..))
+                .help("Close connections, including unix socket ones, idle for this many seconds")
+                .value_name("secs"),
+        )
+        .arg(
+            Arg::new("stream-buffer")
+                .env("DUFS_STREAM_BUFFER")
+				.hide_env(true)
+                .long("stream-buffer")
+                .value_parser(value_parser!(u64).range(4096..=65536))
+                .help("Cap the bytes of a file read ahead per response when a client reads slower than the disk")
+                .value_name("bytes"),
+        )
         .arg(
             Arg::new("hidden")
                 .env("DUFS_HIDDEN")
@@ -75,9 +150,36 @@ pub fn build_cli() -> Command {
                 .long("hidden")
                 .action(ArgAction::Append)
                 .value_delimiter(',')
-                .help("Hide paths from directory listings, e.g. tmp,*.log,*.lock")
+                .help("Hide paths from directory listings, e.g. tmp,*.log,node_modules/,src/**/*.log")
                 .value_name("value"),
         )
+        .arg(
+            Arg::new("alias")
+                .env("DUFS_ALIAS")
+				.hide_env(true)
+                .long("alias")
+                .action(ArgAction::Append)
+                .help("Serve a path under another url path, e.g. /latest=/releases/v2.3.1")
+                .value_name("from=to"),
+        )
+        .arg(
+            Arg::new("static-response")
+                .env("DUFS_STATIC_RESPONSE")
+				.hide_env(true)
+                .long("static-response")
+                .action(ArgAction::Append)
+                .help("Answer a url path with a file or inline text, e.g. /robots.txt=@robots.txt")
+                .value_name("path[:type]=@file|text"),
+        )
+        .arg(
+            Arg::new("header")
+                .env("DUFS_HEADER")
+				.hide_env(true)
+                .long("header")
+                .action(ArgAction::Append)
+                .help("Add a header to every response, replacing the one dufs sets, e.g. 'X-Frame-Options: DENY'")
+                .value_name("name: value"),
+        )
         .arg(
             Arg::new("auth")
                 .env("DUFS_AUTH")
@@ -99,6 +201,30 @@ pub fn build_cli() -> Command {
                 .default_value("digest")
                 .value_name("value"),
         )
+        .arg(
+            Arg::new("dir-auth")
+                .env("DUFS_DIR_AUTH")
+				.hide_env(true)
+                .long("dir-auth")
+                .action(ArgAction::SetTrue)
+                .help("Protect directories containing a .dufs-auth file with the accounts listed in it"),
+        )
+        .arg(
+            Arg::new("share-secret")
+                .env("DUFS_SHARE_SECRET")
+				.hide_env(true)
+                .long("share-secret")
+                .value_name("secret")
+                .help("Accept read-only share links signed with this key, see --share"),
+        )
+        .arg(
+            Arg::new("trust-localhost")
+                .env("DUFS_TRUST_LOCALHOST")
+				.hide_env(true)
+                .long("trust-localhost")
+                .action(ArgAction::SetTrue)
+                .help("Skip auth for requests from loopback addresses, unsafe behind a local reverse proxy"),
+        )
         .arg(
             Arg::new("allow-all")
                 .env("DUFS_ALLOW_ALL")
@@ -140,6 +266,14 @@ pub fn build_cli() -> Command {
                 .action(ArgAction::SetTrue)
                 .help("Allow symlink to files/folders outside root directory"),
         )
+        .arg(
+            Arg::new("show-symlink-target")
+                .env("DUFS_SHOW_SYMLINK_TARGET")
+				.hide_env(true)
+                .long("show-symlink-target")
+                .action(ArgAction::SetTrue)
+                .help("Show the target path of symlinks in directory listings"),
+        )
         .arg(
             Arg::new("allow-archive")
                 .env("DUFS_ALLOW_ARCHIVE")
@@ -148,6 +282,15 @@ pub fn build_cli() -> Command {
                 .action(ArgAction::SetTrue)
                 .help("Allow download folders as archive file"),
         )
+        .arg(
+            Arg::new("dav-infinite-depth")
+                .env("DUFS_DAV_INFINITE_DEPTH")
+				.hide_env(true)
+                .long("dav-infinite-depth")
+                .value_name("policy")
+                .value_parser(parse_dav_infinite_depth)
+                .help("Answer PROPFIND with `Depth: infinity`: allow, deny or limit=N levels [default: deny]"),
+        )
         .arg(
             Arg::new("enable-cors")
                 .env("DUFS_ENABLE_CORS")
@@ -156,6 +299,38 @@ pub fn build_cli() -> Command {
                 .action(ArgAction::SetTrue)
                 .help("Enable CORS, sets `Access-Control-Allow-Origin: *`"),
         )
+        .arg(
+            Arg::new("cors-origin")
+                .env("DUFS_CORS_ORIGIN")
+				.hide_env(true)
+                .long("cors-origin")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .requires("enable-cors")
+                .help("Only allow these CORS origins, echoed back with credentials, e.g. https://a.com,https://b.com")
+                .value_name("origins"),
+        )
+        .arg(
+            Arg::new("cors-methods")
+                .env("DUFS_CORS_METHODS")
+				.hide_env(true)
+                .long("cors-methods")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .requires("enable-cors")
+                .help("Set the methods allowed by CORS, e.g. GET,HEAD,PUT")
+                .value_name("methods"),
+        )
+        .arg(
+            Arg::new("cors-max-age")
+                .env("DUFS_CORS_MAX_AGE")
+				.hide_env(true)
+                .long("cors-max-age")
+                .value_parser(value_parser!(u64))
+                .requires("enable-cors")
+                .help("Let browsers cache CORS preflight responses for N seconds")
+                .value_name("secs"),
+        )
         .arg(
             Arg::new("render-index")
                 .env("DUFS_RENDER_INDEX")
@@ -180,6 +355,122 @@ pub fn build_cli() -> Command {
                 .action(ArgAction::SetTrue)
                 .help("Serve SPA(Single Page Application)"),
         )
+        .arg(
+            Arg::new("try-files")
+                .env("DUFS_TRY_FILES")
+				.hide_env(true)
+                .long("try-files")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .help("Try these files for a missing path before 404, e.g. $uri.html,/404.html")
+                .value_name("template"),
+        )
+        .arg(
+            Arg::new("index-redirect")
+                .env("DUFS_INDEX_REDIRECT")
+				.hide_env(true)
+                .long("index-redirect")
+                .value_name("path")
+                .help("Redirect requests for the root to a landing path, e.g. /app/"),
+        )
+        .arg(
+            Arg::new("sitemap")
+                .env("DUFS_SITEMAP")
+				.hide_env(true)
+                .long("sitemap")
+                .action(ArgAction::SetTrue)
+                .help("Serve /sitemap.xml listing the files anonymous users can read"),
+        )
+        .arg(
+            Arg::new("sort-case-sensitive")
+                .env("DUFS_SORT_CASE_SENSITIVE")
+				.hide_env(true)
+                .long("sort-case-sensitive")
+                .action(ArgAction::SetTrue)
+                .help("Sort listings by name case-sensitively"),
+        )
+        .arg(
+            Arg::new("group-dirs")
+                .env("DUFS_GROUP_DIRS")
+				.hide_env(true)
+                .long("group-dirs")
+                .value_parser(clap::builder::EnumValueParser::<GroupDirs>::new())
+                .value_name("order")
+                .help("Place directories before or after files in listings, or mix them [default: first]"),
+        )
+        .arg(
+            Arg::new("size-units")
+                .env("DUFS_SIZE_UNITS")
+				.hide_env(true)
+                .long("size-units")
+                .value_parser(clap::builder::EnumValueParser::<SizeUnits>::new())
+                .value_name("units")
+                .help("Add human-readable file sizes to listings, in iec (KiB) or si (kB) units"),
+        )
+        .arg(
+            Arg::new("recent-window")
+                .env("DUFS_RECENT_WINDOW")
+				.hide_env(true)
+                .long("recent-window")
+                .value_parser(value_parser!(u64).range(1..))
+                .value_name("secs")
+                .help("Flag entries modified within this many seconds as new in listings, e.g. 86400"),
+        )
+        .arg(
+            Arg::new("inline-extensions")
+                .env("DUFS_INLINE_EXTENSIONS")
+				.hide_env(true)
+                .long("inline-extensions")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .help("Serve files with these extensions inline, overriding --attachment-extensions, e.g. pdf,png")
+                .value_name("exts"),
+        )
+        .arg(
+            Arg::new("attachment-extensions")
+                .env("DUFS_ATTACHMENT_EXTENSIONS")
+				.hide_env(true)
+                .long("attachment-extensions")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .help("Serve files with these extensions as downloads, `*` for all, e.g. exe,zip,iso")
+                .value_name("exts"),
+        )
+        .arg(
+            Arg::new("weak-etags")
+                .env("DUFS_WEAK_ETAGS")
+				.hide_env(true)
+                .long("weak-etags")
+                .action(ArgAction::SetTrue)
+                .help("Send weak ETags (W/\"...\") for files, e.g. behind proxies that rewrite bodies"),
+        )
+        .arg(
+            Arg::new("etag")
+                .env("DUFS_ETAG")
+				.hide_env(true)
+                .long("etag")
+                .value_parser(clap::builder::EnumValueParser::<EtagMode>::new())
+                .value_name("mode")
+                .help("Build file ETags from mtime and size, or from a hash of the content [default: mtime]"),
+        )
+        .arg(
+            Arg::new("cache-control")
+                .env("DUFS_CACHE_CONTROL")
+				.hide_env(true)
+                .long("cache-control")
+                .value_parser(value_parser!(u64))
+                .help("Let clients cache files for this many seconds instead of revalidating each time")
+                .value_name("secs"),
+        )
+        .arg(
+            Arg::new("cache-immutable")
+                .env("DUFS_CACHE_IMMUTABLE")
+				.hide_env(true)
+                .long("cache-immutable")
+                .action(ArgAction::SetTrue)
+                .requires("cache-control")
+                .help("Mark cached files immutable, for fingerprinted assets"),
+        )
         .arg(
             Arg::new("assets")
                 .env("DUFS_ASSETS")
@@ -189,6 +480,15 @@ pub fn build_cli() -> Command {
                 .value_parser(value_parser!(PathBuf))
                 .value_name("path")
         )
+        .arg(
+            Arg::new("lang")
+                .env("DUFS_LANG")
+				.hide_env(true)
+                .long("lang")
+                .help("Set the web UI language instead of following Accept-Language")
+                .value_parser(PossibleValuesParser::new(UI_LANGS))
+                .value_name("lang"),
+        )
         .arg(
             Arg::new("log-format")
                 .env("DUFS_LOG_FORMAT")
@@ -206,6 +506,39 @@ pub fn build_cli() -> Command {
                 .value_parser(value_parser!(PathBuf))
                 .help("Specify the file to save logs to, other than stdout/stderr"),
         )
+        .arg(
+            Arg::new("metrics")
+                .env("DUFS_METRICS")
+				.hide_env(true)
+                .long("metrics")
+                .action(ArgAction::SetTrue)
+                .help("Serve request metrics in the Prometheus format at /__dufs__/metrics"),
+        )
+        .arg(
+            Arg::new("no-health")
+                .env("DUFS_NO_HEALTH")
+				.hide_env(true)
+                .long("no-health")
+                .action(ArgAction::SetTrue)
+                .help("Disable the /__dufs__/health endpoint"),
+        )
+        .arg(
+            Arg::new("quiet")
+                .env("DUFS_QUIET")
+				.hide_env(true)
+                .short('q')
+                .long("quiet")
+                .action(ArgAction::SetTrue)
+                .help("Do not print the effective configuration on startup"),
+        )
+        .arg(
+            Arg::new("open")
+                .env("DUFS_OPEN")
+				.hide_env(true)
+                .long("open")
+                .action(ArgAction::SetTrue)
+                .help("Open the served url in the default browser once listening"),
+        )
         .arg(
             Arg::new("compress")
                 .env("DUFS_COMPRESS")
@@ -215,12 +548,77 @@ pub fn build_cli() -> Command {
                 .value_name("level")
                 .help("Set zip compress level [default: low]")
         )
+        .arg(
+            Arg::new("http-compress")
+                .env("DUFS_HTTP_COMPRESS")
+                .hide_env(true)
+                .value_parser(clap::builder::EnumValueParser::<Compress>::new())
+                .long("http-compress")
+                .value_name("level")
+                .help("Compress text files with brotli or gzip for clients that accept it [default: none]"),
+        )
+        .arg(
+            Arg::new("zip-cache")
+                .env("DUFS_ZIP_CACHE")
+                .hide_env(true)
+                .long("zip-cache")
+                .value_parser(value_parser!(u64).range(1..))
+                .value_name("secs")
+                .help("Keep built zip archives for this many seconds and serve them with range support"),
+        )
+        .arg(
+            Arg::new("share")
+                .long("share")
+                .value_name("path")
+                .help("Print a link sharing a file or folder for --share-ttl seconds and exit"),
+        )
+        .arg(
+            Arg::new("share-ttl")
+                .long("share-ttl")
+                .value_parser(value_parser!(u64).range(1..))
+                .default_value("86400")
+                .value_name("secs")
+                .help("Set how long a link printed by --share stays valid"),
+        )
+        .arg(
+            Arg::new("check")
+                .long("check")
+                .action(ArgAction::SetTrue)
+                .help("Validate the configuration, print a summary and exit without serving"),
+        )
         .arg(
             Arg::new("completions")
                 .long("completions")
                 .value_name("shell")
                 .value_parser(value_parser!(Shell))
                 .help("Print shell completion script for <shell>"),
+        )
+        .arg(
+            Arg::new("man")
+                .long("man")
+                .action(ArgAction::SetTrue)
+                .help("Print the man page in roff format"),
+        );
+
+    #[cfg(unix)]
+    let app = app
+        .arg(
+            Arg::new("file-mode")
+                .env("DUFS_FILE_MODE")
+                .hide_env(true)
+                .long("file-mode")
+                .value_name("mode")
+                .value_parser(parse_mode)
+                .help("Set the permission mode (octal) of uploaded files, e.g. 644"),
+        )
+        .arg(
+            Arg::new("dir-mode")
+                .env("DUFS_DIR_MODE")
+                .hide_env(true)
+                .long("dir-mode")
+                .value_name("mode")
+                .value_parser(parse_mode)
+                .help("Set the permission mode (octal) of created directories, e.g. 755"),
         );
 
     #[cfg(feature = "tls")]
@@ -244,6 +642,16 @@ pub fn build_cli() -> Command {
                 .help("Path to the SSL/TLS certificate's private key"),
         );
 
+    #[cfg(feature = "http3")]
+    let app = app.arg(
+        Arg::new("http3")
+            .env("DUFS_HTTP3")
+            .hide_env(true)
+            .long("http3")
+            .action(ArgAction::SetTrue)
+            .help("Also serve HTTP/3 over QUIC on the same port (experimental, requires TLS)"),
+    );
+
     app
 }
 
@@ -251,6 +659,11 @@ pub fn print_completions<G: Generator>(gen: G, cmd: &mut Command) {
     generate(gen, cmd, cmd.get_name().to_string(), &mut std::io::stdout());
 }
 
+pub fn print_man(cmd: Command) -> Result<()> {
+    clap_mangen::Man::new(cmd).render(&mut std::io::stdout())?;
+    Ok(())
+}
+
 #[derive(Debug, Deserialize, SmartDefault, PartialEq)]
 #[serde(default)]
 #[serde(rename_all = "kebab-case")]
@@ -271,28 +684,86 @@ pub struct Args {
     pub path_prefix: String,
     #[serde(skip)]
     pub uri_prefix: String,
+    #[default(4096)]
+    pub max_path_length: usize,
+    #[default(128)]
+    pub max_path_depth: usize,
+    pub max_concurrent_uploads: Option<usize>,
+    pub max_connections: Option<usize>,
+    pub max_upload_size: Option<u64>,
+    pub max_list_entries: Option<usize>,
+    pub idle_timeout: Option<u64>,
+    pub stream_buffer: Option<u64>,
     #[serde(deserialize_with = "deserialize_string_or_vec")]
     pub hidden: Vec<String>,
+    #[serde(skip)]
+    pub hidden_matcher: HiddenMatcher,
+    #[serde(deserialize_with = "deserialize_path_aliases")]
+    pub alias: Vec<PathAlias>,
+    #[serde(deserialize_with = "deserialize_static_responses")]
+    pub static_response: Vec<StaticResponse>,
+    #[serde(deserialize_with = "deserialize_headers")]
+    pub header: HeaderMap,
     #[serde(deserialize_with = "deserialize_access_control")]
     pub auth: AccessControl,
+    pub dir_auth: bool,
+    pub share_secret: Option<String>,
+    pub trust_localhost: bool,
     pub allow_all: bool,
     pub allow_upload: bool,
     pub allow_delete: bool,
     pub allow_search: bool,
     pub allow_symlink: bool,
+    pub show_symlink_target: bool,
     pub allow_archive: bool,
+    #[serde(deserialize_with = "deserialize_dav_infinite_depth")]
+    pub dav_infinite_depth: DavInfiniteDepth,
     pub render_index: bool,
     pub render_spa: bool,
     pub render_try_index: bool,
+    #[serde(deserialize_with = "deserialize_string_or_vec")]
+    pub try_files: Vec<String>,
+    pub index_redirect: Option<String>,
+    pub sitemap: bool,
+    pub sort_case_sensitive: bool,
+    pub group_dirs: GroupDirs,
+    pub size_units: Option<SizeUnits>,
+    pub recent_window: Option<u64>,
     pub enable_cors: bool,
+    #[serde(deserialize_with = "deserialize_string_or_vec")]
+    pub cors_origin: Vec<String>,
+    #[serde(deserialize_with = "deserialize_string_or_vec")]
+    pub cors_methods: Vec<String>,
+    pub cors_max_age: Option<u64>,
+    #[serde(deserialize_with = "deserialize_string_or_vec")]
+    pub inline_extensions: Vec<String>,
+    #[serde(deserialize_with = "deserialize_string_or_vec")]
+    pub attachment_extensions: Vec<String>,
+    pub weak_etags: bool,
+    pub etag: EtagMode,
+    pub cache_control: Option<u64>,
+    pub cache_immutable: bool,
     pub assets: Option<PathBuf>,
+    pub lang: Option<String>,
     #[serde(deserialize_with = "deserialize_log_http")]
     #[serde(rename = "log-format")]
     pub http_logger: HttpLogger,
     pub log_file: Option<PathBuf>,
+    pub metrics: bool,
+    pub no_health: bool,
+    pub quiet: bool,
+    pub open: bool,
     pub compress: Compress,
+    #[default(Compress::None)]
+    pub http_compress: Compress,
+    pub zip_cache: Option<u64>,
+    #[serde(deserialize_with = "deserialize_mode")]
+    pub file_mode: Option<u32>,
+    #[serde(deserialize_with = "deserialize_mode")]
+    pub dir_mode: Option<u32>,
     pub tls_cert: Option<PathBuf>,
     pub tls_key: Option<PathBuf>,
+    pub http3: bool,
 }
 
 impl Args {
@@ -337,6 +808,38 @@ impl Args {
             format!("/{}/", &encode_uri(&args.path_prefix))
         };
 
+        if let Some(max_path_length) = matches.get_one::<usize>("max-path-length") {
+            args.max_path_length = *max_path_length;
+        }
+
+        if let Some(max_path_depth) = matches.get_one::<usize>("max-path-depth") {
+            args.max_path_depth = *max_path_depth;
+        }
+
+        if let Some(max_concurrent_uploads) = matches.get_one::<usize>("max-concurrent-uploads") {
+            args.max_concurrent_uploads = Some(*max_concurrent_uploads);
+        }
+
+        if let Some(max_connections) = matches.get_one::<u64>("max-connections") {
+            args.max_connections = Some(*max_connections as usize);
+        }
+
+        if let Some(max_upload_size) = matches.get_one::<u64>("max-upload-size") {
+            args.max_upload_size = Some(*max_upload_size);
+        }
+
+        if let Some(max_list_entries) = matches.get_one::<u64>("max-list-entries") {
+            args.max_list_entries = Some(*max_list_entries as usize);
+        }
+
+        if let Some(idle_timeout) = matches.get_one::<u64>("idle-timeout") {
+            args.idle_timeout = Some(*idle_timeout);
+        }
+
+        if let Some(stream_buffer) = matches.get_one::<u64>("stream-buffer") {
+            args.stream_buffer = Some(*stream_buffer);
+        }
+
         if let Some(hidden) = matches.get_many::<String>("hidden") {
             args.hidden = hidden.cloned().collect();
         } else {
@@ -352,11 +855,60 @@ impl Args {
             args.enable_cors = matches.get_flag("enable-cors");
         }
 
+        if let Some(origins) = matches.get_many::<String>("cors-origin") {
+            args.cors_origin = origins.cloned().collect();
+        }
+        args.cors_origin = split_list(&args.cors_origin);
+
+        if let Some(methods) = matches.get_many::<String>("cors-methods") {
+            args.cors_methods = methods.cloned().collect();
+        }
+        args.cors_methods = split_list(&args.cors_methods);
+        for method in &args.cors_methods {
+            if method.parse::<Method>().is_err() {
+                bail!("Invalid cors method `{method}`");
+            }
+        }
+
+        if let Some(max_age) = matches.get_one::<u64>("cors-max-age") {
+            args.cors_max_age = Some(*max_age);
+        }
+
+        if let Some(aliases) = matches.get_many::<String>("alias") {
+            let aliases: Vec<_> = aliases.map(|v| v.as_str()).collect();
+            args.alias = PathAlias::parse_aliases(&aliases)?;
+        }
+
+        if let Some(values) = matches.get_many::<String>("static-response") {
+            let values: Vec<_> = values.map(|v| v.as_str()).collect();
+            args.static_response = StaticResponse::parse_all(&values)?;
+        }
+
+        if let Some(values) = matches.get_many::<String>("header") {
+            let values: Vec<_> = values.map(|v| v.as_str()).collect();
+            args.header = parse_headers(&values)?;
+        }
+
         if let Some(rules) = matches.get_many::<String>("auth") {
             let rules: Vec<_> = rules.map(|v| v.as_str()).collect();
             args.auth = AccessControl::new(&rules)?;
         }
 
+        if !args.dir_auth {
+            args.dir_auth = matches.get_flag("dir-auth");
+        }
+
+        if let Some(share_secret) = matches.get_one::<String>("share-secret") {
+            args.share_secret = Some(share_secret.clone());
+        }
+        if args.share_secret.as_ref().is_some_and(|v| v.is_empty()) {
+            bail!("--share-secret must not be empty");
+        }
+
+        if !args.trust_localhost {
+            args.trust_localhost = matches.get_flag("trust-localhost");
+        }
+
         if !args.allow_all {
             args.allow_all = matches.get_flag("allow-all");
         }
@@ -378,6 +930,12 @@ impl Args {
         if !args.allow_archive {
             args.allow_archive = allow_all || matches.get_flag("allow-archive");
         }
+        if let Some(policy) = matches.get_one::<DavInfiniteDepth>("dav-infinite-depth") {
+            args.dav_infinite_depth = *policy;
+        }
+        if !args.show_symlink_target {
+            args.show_symlink_target = matches.get_flag("show-symlink-target");
+        }
         if !args.render_index {
             args.render_index = matches.get_flag("render-index");
         }
@@ -390,6 +948,72 @@ impl Args {
             args.render_spa = matches.get_flag("render-spa");
         }
 
+        if let Some(try_files) = matches.get_many::<String>("try-files") {
+            args.try_files = try_files.cloned().collect();
+        } else {
+            args.try_files = args
+                .try_files
+                .iter()
+                .flat_map(|v| v.split(',').map(|v| v.to_string()))
+                .collect();
+        }
+        for template in &args.try_files {
+            if template.is_empty() || template.split('/').any(|v| v == "..") {
+                bail!("Invalid try-files template `{template}`");
+            }
+        }
+
+        if let Some(index_redirect) = matches.get_one::<String>("index-redirect") {
+            args.index_redirect = Some(index_redirect.clone());
+        }
+        if let Some(index_redirect) = &args.index_redirect {
+            args.index_redirect = Some(Self::parse_index_redirect(index_redirect)?);
+        }
+
+        if !args.sitemap {
+            args.sitemap = matches.get_flag("sitemap");
+        }
+
+        if !args.sort_case_sensitive {
+            args.sort_case_sensitive = matches.get_flag("sort-case-sensitive");
+        }
+
+        if let Some(group_dirs) = matches.get_one::<GroupDirs>("group-dirs") {
+            args.group_dirs = *group_dirs;
+        }
+
+        if let Some(size_units) = matches.get_one::<SizeUnits>("size-units") {
+            args.size_units = Some(*size_units);
+        }
+
+        if let Some(recent_window) = matches.get_one::<u64>("recent-window") {
+            args.recent_window = Some(*recent_window);
+        }
+
+        args.inline_extensions = Self::parse_extensions(
+            matches.get_many::<String>("inline-extensions"),
+            &args.inline_extensions,
+        );
+        args.attachment_extensions = Self::parse_extensions(
+            matches.get_many::<String>("attachment-extensions"),
+            &args.attachment_extensions,
+        );
+        if !args.weak_etags {
+            args.weak_etags = matches.get_flag("weak-etags");
+        }
+
+        if let Some(etag) = matches.get_one::<EtagMode>("etag") {
+            args.etag = *etag;
+        }
+
+        if let Some(cache_control) = matches.get_one::<u64>("cache-control") {
+            args.cache_control = Some(*cache_control);
+        }
+
+        if !args.cache_immutable {
+            args.cache_immutable = matches.get_flag("cache-immutable");
+        }
+
         if let Some(assets_path) = matches.get_one::<PathBuf>("assets") {
             args.assets = Some(assets_path.clone());
         }
@@ -398,6 +1022,18 @@ impl Args {
             args.assets = Some(Args::sanitize_assets_path(assets_path)?);
         }
 
+        if let Some(lang) = matches.get_one::<String>("lang") {
+            args.lang = Some(lang.clone());
+        }
+        if let Some(lang) = args.lang.as_deref() {
+            if !UI_LANGS.contains(&lang) {
+                bail!(
+                    "Unsupported lang `{lang}`, expected one of {}",
+                    UI_LANGS.join(", ")
+                );
+            }
+        }
+
         if let Some(log_format) = matches.get_one::<String>("log-format") {
             args.http_logger = log_format.parse()?;
         }
@@ -406,9 +1042,43 @@ impl Args {
             args.log_file = Some(log_file.clone());
         }
 
+        if !args.metrics {
+            args.metrics = matches.get_flag("metrics");
+        }
+
+        if !args.no_health {
+            args.no_health = matches.get_flag("no-health");
+        }
+
+        if !args.quiet {
+            args.quiet = matches.get_flag("quiet");
+        }
+
+        if !args.open {
+            args.open = matches.get_flag("open");
+        }
+
         if let Some(compress) = matches.get_one::<Compress>("compress") {
             args.compress = *compress;
         }
+        if let Some(http_compress) = matches.get_one::<Compress>("http-compress") {
+            args.http_compress = *http_compress;
+        }
+
+        if let Some(zip_cache) = matches.get_one::<u64>("zip-cache") {
+            args.zip_cache = Some(*zip_cache);
+        }
+
+        #[cfg(unix)]
+        {
+            if let Some(file_mode) = matches.get_one::<u32>("file-mode") {
+                args.file_mode = Some(*file_mode);
+            }
+
+            if let Some(dir_mode) = matches.get_one::<u32>("dir-mode") {
+                args.dir_mode = Some(*dir_mode);
+            }
+        }
 
         #[cfg(feature = "tls")]
         {
@@ -433,9 +1103,40 @@ impl Args {
             args.tls_key = None;
         }
 
+        #[cfg(feature = "http3")]
+        {
+            if !args.http3 {
+                args.http3 = matches.get_flag("http3");
+            }
+            if args.http3 && args.tls_cert.is_none() {
+                bail!("HTTP/3 requires tls-cert and tls-key");
+            }
+        }
+        #[cfg(not(feature = "http3"))]
+        {
+            args.http3 = false;
+        }
+
+        args.hidden_matcher = HiddenMatcher::new(&args.serve_path, &args.hidden, args.dir_auth)?;
+
         Ok(args)
     }
 
+    /// The path below the serve root that a request path, already below `--path-prefix`,
+    /// resolves to through the most specific alias.
+    pub fn apply_alias(&self, path: String) -> String {
+        for alias in &self.alias {
+            if let Some(rest) = path.strip_prefix(&alias.from) {
+                if rest.is_empty() || rest.starts_with('/') {
+                    return format!("{}{rest}", alias.to)
+                        .trim_start_matches('/')
+                        .to_string();
+                }
+            }
+        }
+        path
+    }
+
     fn sanitize_path<P: AsRef<Path>>(path: P) -> Result<PathBuf> {
         let path = path.as_ref();
         if !path.exists() {
@@ -457,6 +1158,34 @@ impl Args {
         }
         Ok(path)
     }
+
+    /// Lowercased extensions without the leading dot, from the cli or else comma separated config values.
+    fn parse_extensions<'a>(
+        values: Option<impl Iterator<Item = &'a String>>,
+        config: &[String],
+    ) -> Vec<String> {
+        let values: Vec<&str> = match values {
+            Some(values) => values.map(|v| v.as_str()).collect(),
+            None => config.iter().flat_map(|v| v.split(',')).collect(),
+        };
+        values
+            .into_iter()
+            .map(|v| v.trim().trim_start_matches('.').to_lowercase())
+            .filter(|v| !v.is_empty())
+            .collect()
+    }
+
+    fn parse_index_redirect(value: &str) -> Result<String> {
+        let path = match normalize_alias_path(value) {
+            Some(v) if !v.is_empty() => v,
+            _ => bail!("Invalid index redirect `{value}`, expected a path below the root"),
+        };
+        if value.ends_with('/') {
+            Ok(format!("{path}/"))
+        } else {
+            Ok(path)
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -492,21 +1221,194 @@ impl BindAddr {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathAlias {
+    pub from: String,
+    pub to: String,
+}
+
+impl PathAlias {
+    /// Parse `from=to` pairs, longest `from` first so the most specific alias wins.
+    fn parse_aliases(values: &[&str]) -> Result<Vec<Self>> {
+        let mut aliases = vec![];
+        for value in values {
+            let (from, to) = value
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid alias `{value}`, expected from=to"))?;
+            let (Some(from), Some(to)) = (normalize_alias_path(from), normalize_alias_path(to))
+            else {
+                bail!("Invalid alias `{value}`, paths must stay within the serve path");
+            };
+            if from.is_empty() {
+                bail!("Invalid alias `{value}`, cannot alias the root path");
+            }
+            aliases.push(Self { from, to });
+        }
+        aliases.sort_by_key(|v| std::cmp::Reverse(v.from.len()));
+        Ok(aliases)
+    }
+}
+
+/// The `--hidden` patterns compiled once, matched against paths relative to the serve path.
+///
+/// A pattern without a `/` names an entry at any depth, one with a `/` is anchored at the serve
+/// path, and a trailing `/` (or `/**`) limits it to directories.
+#[derive(Debug, Clone, Default)]
+pub struct HiddenMatcher {
+    root: PathBuf,
+    patterns: Vec<String>,
+    any: GlobSet,
+    dirs: GlobSet,
+}
+
+impl PartialEq for HiddenMatcher {
+    fn eq(&self, other: &Self) -> bool {
+        self.root == other.root && self.patterns == other.patterns
+    }
+}
+
+impl HiddenMatcher {
+    fn new(root: &Path, patterns: &[String], dir_auth: bool) -> Result<Self> {
+        let mut any = GlobSetBuilder::new();
+        let mut dirs = GlobSetBuilder::new();
+        for pattern in patterns {
+            let (glob, dir_only) = match pattern
+                .strip_suffix("/**")
+                .or_else(|| pattern.strip_suffix('/'))
+            {
+                Some(v) => (v, true),
+                None => (pattern.as_str(), false),
+            };
+            let glob = glob.trim_start_matches('/');
+            if glob.is_empty() {
+                continue;
+            }
+            let glob = match glob.contains('/') {
+                true => glob.to_string(),
+                false => format!("**/{glob}"),
+            };
+            let glob = GlobBuilder::new(&glob)
+                .literal_separator(true)
+                .build()
+                .map_err(|err| anyhow!("Invalid hidden pattern `{pattern}`, {err}"))?;
+            match dir_only {
+                true => dirs.add(glob),
+                false => any.add(glob),
+            };
+        }
+        if dir_auth {
+            // matched like every other check on it, without regard to case
+            any.add(
+                GlobBuilder::new(&format!("**/{DIR_AUTH_FILE}"))
+                    .case_insensitive(true)
+                    .build()?,
+            );
+        }
+        Ok(Self {
+            root: root.to_path_buf(),
+            patterns: patterns.to_vec(),
+            any: any.build()?,
+            dirs: dirs.build()?,
+        })
+    }
+
+    /// Paths outside the serve path, such as those behind an alias, are matched by their name.
+    pub fn is_match(&self, path: &Path, is_dir: bool) -> bool {
+        let path = match path.strip_prefix(&self.root) {
+            Ok(v) => v,
+            Err(_) => match path.file_name() {
+                Some(v) => Path::new(v),
+                None => return false,
+            },
+        };
+        self.any.is_match(path) || (is_dir && self.dirs.is_match(path))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaticResponse {
+    pub path: String,
+    pub content_type: String,
+    pub body: StaticBody,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StaticBody {
+    File(PathBuf),
+    Inline(String),
+}
+
+impl StaticResponse {
+    /// Parse `path[:type]=@file` or `path[:type]=text` mappings.
+    fn parse_all(values: &[&str]) -> Result<Vec<Self>> {
+        let mut responses = vec![];
+        for value in values {
+            let (key, source) = value.split_once('=').ok_or_else(|| {
+                anyhow!("Invalid static response `{value}`, expected path=@file or path=text")
+            })?;
+            let (path, content_type) = match key.split_once(':') {
+                Some((path, content_type)) => {
+                    let content_type = content_type.trim();
+                    if content_type.parse::<mime_guess::Mime>().is_err() {
+                        bail!("Invalid static response `{value}`, invalid content type");
+                    }
+                    (path, Some(content_type.to_string()))
+                }
+                None => (key, None),
+            };
+            let path = match normalize_alias_path(path) {
+                Some(v) if !v.is_empty() => v,
+                _ => bail!("Invalid static response `{value}`, invalid path"),
+            };
+            let (body, content_type) = match source.strip_prefix('@') {
+                Some(file) => {
+                    let file = PathBuf::from(file);
+                    if !file.is_file() {
+                        bail!("Invalid static response `{value}`, file not found");
+                    }
+                    let content_type = content_type.unwrap_or_else(|| {
+                        mime_guess::from_path(&file)
+                            .first_or_octet_stream()
+                            .to_string()
+                    });
+                    (StaticBody::File(file), content_type)
+                }
+                None => (
+                    StaticBody::Inline(source.to_string()),
+                    content_type.unwrap_or_else(|| "text/plain; charset=utf-8".to_string()),
+                ),
+            };
+            responses.push(Self {
+                path,
+                content_type,
+                body,
+            });
+        }
+        Ok(responses)
+    }
+}
+
+fn normalize_alias_path(path: &str) -> Option<String> {
+    let mut parts = vec![];
+    for comp in Path::new(path.trim_matches('/')).components() {
+        match comp {
+            std::path::Component::Normal(v) => parts.push(v.to_str()?),
+            _ => return None,
+        }
+    }
+    Some(parts.join("/"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum Compress {
     None,
+    #[default]
     Low,
     Medium,
     High,
 }
 
-impl Default for Compress {
-    fn default() -> Self {
-        Self::Low
-    }
-}
-
 impl ValueEnum for Compress {
     fn value_variants<'a>() -> &'a [Self] {
         &[Self::None, Self::Low, Self::Medium, Self::High]
@@ -531,6 +1433,137 @@ impl Compress {
             Compress::High => Compression::Xz,
         }
     }
+
+    /// Quality for `br` and `gzip` response bodies, `None` to send them uncompressed.
+    pub fn to_http_quality(self) -> Option<i32> {
+        match self {
+            Compress::None => None,
+            Compress::Low => Some(1),
+            Compress::Medium => Some(5),
+            Compress::High => Some(9),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupDirs {
+    #[default]
+    First,
+    Last,
+    None,
+}
+
+impl ValueEnum for GroupDirs {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::First, Self::Last, Self::None]
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        Some(match self {
+            GroupDirs::First => PossibleValue::new("first"),
+            GroupDirs::Last => PossibleValue::new("last"),
+            GroupDirs::None => PossibleValue::new("none"),
+        })
+    }
+}
+
+/// What file ETags are built from.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EtagMode {
+    #[default]
+    Mtime,
+    Hash,
+}
+
+impl ValueEnum for EtagMode {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Mtime, Self::Hash]
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        Some(match self {
+            EtagMode::Mtime => PossibleValue::new("mtime"),
+            EtagMode::Hash => PossibleValue::new("hash"),
+        })
+    }
+}
+
+/// How PROPFIND answers `Depth: infinity`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DavInfiniteDepth {
+    Allow,
+    #[default]
+    Deny,
+    Limit(usize),
+}
+
+fn deserialize_dav_infinite_depth<'de, D>(deserializer: D) -> Result<DavInfiniteDepth, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    parse_dav_infinite_depth(&value).map_err(serde::de::Error::custom)
+}
+
+fn parse_dav_infinite_depth(value: &str) -> Result<DavInfiniteDepth, String> {
+    match value {
+        "allow" => Ok(DavInfiniteDepth::Allow),
+        "deny" => Ok(DavInfiniteDepth::Deny),
+        _ => value
+            .strip_prefix("limit=")
+            .and_then(|v| v.parse().ok())
+            .filter(|v| *v > 0)
+            .map(DavInfiniteDepth::Limit)
+            .ok_or_else(|| {
+                format!("Invalid depth policy `{value}`, expected allow, deny or limit=N")
+            }),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeUnits {
+    Iec,
+    Si,
+}
+
+impl ValueEnum for SizeUnits {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Iec, Self::Si]
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        Some(match self {
+            SizeUnits::Iec => PossibleValue::new("iec"),
+            SizeUnits::Si => PossibleValue::new("si"),
+        })
+    }
+}
+
+impl SizeUnits {
+    /// Format bytes like `1.2 GiB` (iec) or `1.3 GB` (si).
+    pub fn format(self, size: u64) -> String {
+        let (base, units) = match self {
+            SizeUnits::Iec => (1024.0, ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"]),
+            SizeUnits::Si => (1000.0, ["kB", "MB", "GB", "TB", "PB", "EB"]),
+        };
+        let mut value = size as f64;
+        if value < base {
+            return format!("{size} B");
+        }
+        let mut unit = units[0];
+        for v in units {
+            value /= base;
+            unit = v;
+            // compare after rounding so 1023.96 KiB shows as 1.0 MiB
+            if (value * 10.0).round() < base * 10.0 {
+                break;
+            }
+        }
+        format!("{value:.1} {unit}")
+    }
 }
 
 fn deserialize_bind_addrs<'de, D>(deserializer: D) -> Result<Vec<BindAddr>, D::Error>
@@ -566,6 +1599,16 @@ where
     deserializer.deserialize_any(StringOrVec)
 }
 
+/// Splits comma separated entries, as config files may list them in one string.
+fn split_list(values: &[String]) -> Vec<String> {
+    values
+        .iter()
+        .flat_map(|v| v.split(','))
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
 fn deserialize_string_or_vec<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
 where
     D: Deserializer<'de>,
@@ -597,6 +1640,49 @@ where
     deserializer.deserialize_any(StringOrVec)
 }
 
+fn deserialize_path_aliases<'de, D>(deserializer: D) -> Result<Vec<PathAlias>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let values = deserialize_string_or_vec(deserializer)?;
+    let values: Vec<&str> = values.iter().map(|v| v.as_str()).collect();
+    PathAlias::parse_aliases(&values).map_err(serde::de::Error::custom)
+}
+
+fn deserialize_headers<'de, D>(deserializer: D) -> Result<HeaderMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let values = deserialize_string_or_vec(deserializer)?;
+    let values: Vec<&str> = values.iter().map(|v| v.as_str()).collect();
+    parse_headers(&values).map_err(serde::de::Error::custom)
+}
+
+/// Parse `Name: Value` pairs, a later value for the same name replaces the earlier one.
+fn parse_headers(values: &[&str]) -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    for value in values {
+        let (name, header_value) = value
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Invalid header `{value}`, expected 'name: value'"))?;
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|_| anyhow!("Invalid header `{value}`, bad header name"))?;
+        let header_value = HeaderValue::from_str(header_value.trim())
+            .map_err(|_| anyhow!("Invalid header `{value}`, bad header value"))?;
+        headers.insert(name, header_value);
+    }
+    Ok(headers)
+}
+
+fn deserialize_static_responses<'de, D>(deserializer: D) -> Result<Vec<StaticResponse>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let values = deserialize_string_or_vec(deserializer)?;
+    let values: Vec<&str> = values.iter().map(|v| v.as_str()).collect();
+    StaticResponse::parse_all(&values).map_err(serde::de::Error::custom)
+}
+
 fn deserialize_access_control<'de, D>(deserializer: D) -> Result<AccessControl, D::Error>
 where
     D: Deserializer<'de>,
@@ -613,6 +1699,32 @@ where
     value.parse().map_err(serde::de::Error::custom)
 }
 
+fn deserialize_mode<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Mode {
+        Num(u32),
+        Str(String),
+    }
+    let value = match Mode::deserialize(deserializer)? {
+        Mode::Num(v) => v.to_string(),
+        Mode::Str(v) => v,
+    };
+    parse_mode(&value)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+fn parse_mode(value: &str) -> Result<u32, String> {
+    u32::from_str_radix(value.trim_start_matches("0o"), 8)
+        .ok()
+        .filter(|v| *v <= 0o7777)
+        .ok_or_else(|| format!("Invalid octal mode `{value}`"))
+}
+
 fn default_serve_path() -> PathBuf {
     PathBuf::from(".")
 }
@@ -631,6 +1743,42 @@ mod tests {
 
     use assert_fs::prelude::*;
 
+    #[test]
+    fn test_hidden_matcher() {
+        let root = Path::new("/srv");
+        let is_hidden = |pattern: &str, path: &str, is_dir: bool| {
+            HiddenMatcher::new(root, &[pattern.to_string()], false)
+                .unwrap()
+                .is_match(&root.join(path), is_dir)
+        };
+        assert!(is_hidden(".*", ".git", true));
+        assert!(is_hidden(".*", "a/.git", true));
+        assert!(is_hidden("abc", "abc", false));
+        assert!(is_hidden("a*c", "x/abbc", false));
+        assert!(is_hidden("a?c", "abc", false));
+        assert!(!is_hidden("abc", "adc", false));
+        assert!(!is_hidden("abc", "abcd", false));
+        assert!(!is_hidden("a?c", "abbc", false));
+        assert!(!is_hidden("*.log", "log", false));
+        assert!(is_hidden("*.abc-cba", "123.xyz.abc-cba", false));
+        assert!(is_hidden("*.log", ".log", false));
+        assert!(is_hidden("*/", "abc", true));
+        assert!(!is_hidden("*/", "abc", false));
+        assert!(is_hidden("node_modules/", "a/b/node_modules", true));
+        assert!(is_hidden("tmp/**", "tmp", true));
+        assert!(is_hidden("**/*.log", "a/b.log", false));
+        assert!(is_hidden("src/**/*.log", "src/a/b/c.log", false));
+        assert!(is_hidden("src/**/*.log", "src/c.log", false));
+        assert!(!is_hidden("src/**/*.log", "lib/c.log", false));
+        assert!(!is_hidden("src/*.log", "src/a/c.log", false));
+        assert!(is_hidden("/dir1/file", "dir1/file", false));
+        assert!(!is_hidden("dir1/file", "dir2/dir1/file", false));
+        assert!(is_hidden("*.log", "/elsewhere/a.log", false));
+        assert!(HiddenMatcher::new(root, &["a[".to_string()], false).is_err());
+        let matcher = HiddenMatcher::new(root, &[], true).unwrap();
+        assert!(matcher.is_match(&root.join("dir1/.DUFS-Auth"), false));
+    }
+
     #[test]
     fn test_default() {
         let cli = build_cli();
@@ -699,6 +1847,8 @@ bind: 0.0.0.0
 port: 3000
 allow-upload: true
 hidden: tmp,*.log,*.lock
+file-mode: 0640
+dir-mode: "750"
 "#,
             tmpdir.display()
         );
@@ -717,6 +1867,8 @@ hidden: tmp,*.log,*.lock
         assert_eq!(args.hidden, ["tmp", "*.log", "*.lock"]);
         assert_eq!(args.port, 3000);
         assert!(args.allow_upload);
+        assert_eq!(args.file_mode, Some(0o640));
+        assert_eq!(args.dir_mode, Some(0o750));
     }
 
     #[test]
@@ -748,4 +1900,56 @@ hidden:
         );
         assert_eq!(args.hidden, ["tmp", "*.log", "*.lock"]);
     }
+
+    #[test]
+    fn test_size_units_format() {
+        assert_eq!(SizeUnits::Iec.format(0), "0 B");
+        assert_eq!(SizeUnits::Iec.format(1023), "1023 B");
+        assert_eq!(SizeUnits::Iec.format(1024), "1.0 KiB");
+        assert_eq!(SizeUnits::Iec.format(1048524), "1023.9 KiB");
+        assert_eq!(SizeUnits::Iec.format(1048550), "1.0 MiB");
+        assert_eq!(SizeUnits::Iec.format(1288490189), "1.2 GiB");
+        assert_eq!(SizeUnits::Si.format(999), "999 B");
+        assert_eq!(SizeUnits::Si.format(1000), "1.0 kB");
+        assert_eq!(SizeUnits::Si.format(1288490189), "1.3 GB");
+        assert_eq!(SizeUnits::Si.format(u64::MAX), "18.4 EB");
+    }
+
+    #[test]
+    fn test_parse_index_redirect() {
+        assert_eq!(Args::parse_index_redirect("/app/").unwrap(), "app/");
+        assert_eq!(
+            Args::parse_index_redirect("app/index.html").unwrap(),
+            "app/index.html"
+        );
+        assert!(Args::parse_index_redirect("/").is_err());
+        assert!(Args::parse_index_redirect("../app").is_err());
+    }
+
+    #[test]
+    fn test_parse_dav_infinite_depth() {
+        assert_eq!(
+            parse_dav_infinite_depth("allow"),
+            Ok(DavInfiniteDepth::Allow)
+        );
+        assert_eq!(parse_dav_infinite_depth("deny"), Ok(DavInfiniteDepth::Deny));
+        assert_eq!(
+            parse_dav_infinite_depth("limit=3"),
+            Ok(DavInfiniteDepth::Limit(3))
+        );
+        assert!(parse_dav_infinite_depth("limit=0").is_err());
+        assert!(parse_dav_infinite_depth("limit").is_err());
+    }
+
+    #[test]
+    fn test_parse_headers() {
+        let headers =
+            parse_headers(&["X-Frame-Options: DENY", "server:files", "Server: dufs"]).unwrap();
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers["x-frame-options"], "DENY");
+        assert_eq!(headers["server"], "dufs");
+        assert!(parse_headers(&["X-Frame-Options"]).is_err());
+        assert!(parse_headers(&["Bad Name: x"]).is_err());
+        assert!(parse_headers(&["X-Test: a\nb"]).is_err());
+    }
 }
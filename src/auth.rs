@@ -1,18 +1,24 @@
 use crate::{args::Args, server::Response, utils::unix_now};
 
 use anyhow::{anyhow, bail, Result};
-use base64::{engine::general_purpose::STANDARD, Engine as _};
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine as _,
+};
 use headers::HeaderValue;
+use hmac::{Hmac, Mac};
 use hyper::{header::WWW_AUTHENTICATE, Method};
 use indexmap::IndexMap;
 use lazy_static::lazy_static;
 use md5::Context;
+use sha2::Sha256;
 use std::{
     collections::HashMap,
     path::{Path, PathBuf},
 };
 use uuid::Uuid;
 
+pub const DIR_AUTH_FILE: &str = ".dufs-auth";
 const REALM: &str = "DUFS";
 const DIGEST_AUTH_TIMEOUT: u32 = 604800; // 7 days
 
@@ -100,6 +106,25 @@ impl AccessControl {
         !self.users.is_empty()
     }
 
+    /// Describe the auth setup without exposing any password.
+    pub fn summary(&self) -> String {
+        if self.users.is_empty() {
+            return "off".into();
+        }
+        let method = if self.use_hashed_password {
+            "basic"
+        } else {
+            "digest,basic"
+        };
+        let users = self.users.keys().cloned().collect::<Vec<_>>().join(",");
+        let anonymous = if self.anonymous.is_some() {
+            "allowed"
+        } else {
+            "denied"
+        };
+        format!("{method} (users: {users}; anonymous: {anonymous})")
+    }
+
     pub fn guard(
         &self,
         path: &str,
@@ -135,6 +160,84 @@ impl AccessControl {
 
         (None, None)
     }
+
+    /// The path rule of `user`, or of anonymous access, that governs `path`, e.g. `/dir1:rw`.
+    pub fn matched_rule(&self, path: &str, user: Option<&str>) -> Option<String> {
+        let access_paths = match user {
+            Some(user) => &self.users.get(user)?.1,
+            None => self.anonymous.as_ref()?,
+        };
+        access_paths.matched_rule(path)
+    }
+}
+
+/// Accounts from a `.dufs-auth` file, one `user:pass` per line.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DirAuth {
+    users: IndexMap<String, String>,
+}
+
+impl DirAuth {
+    pub fn new(content: &str) -> Self {
+        let mut users = IndexMap::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once(':') {
+                Some((user, pass)) if !user.is_empty() && !pass.is_empty() => {
+                    users.insert(user.to_string(), pass.to_string());
+                }
+                _ => warn!("Ignore invalid account in {DIR_AUTH_FILE}"),
+            }
+        }
+        Self { users }
+    }
+
+    /// Returns the authenticated user, an empty file lets nobody in.
+    pub fn guard(&self, method: &Method, authorization: Option<&HeaderValue>) -> Option<String> {
+        let authorization = authorization?;
+        let user = get_auth_user(authorization)?;
+        let pass = self.users.get(&user)?;
+        check_auth(authorization, method.as_str(), &user, pass)?;
+        Some(user)
+    }
+}
+
+/// Signs a read-only share of `path`, relative to the serve root, valid until `expires` (unix seconds).
+pub fn sign_share(secret: &str, path: &str, expires: u64) -> String {
+    URL_SAFE_NO_PAD.encode(share_mac(secret, path, expires).finalize().into_bytes())
+}
+
+/// Whether `token` shares `path`, or a directory containing it, and has not expired at `now`.
+pub fn verify_share(secret: &str, path: &str, expires: u64, token: &str, now: u64) -> bool {
+    if expires < now {
+        return false;
+    }
+    let Ok(token) = URL_SAFE_NO_PAD.decode(token) else {
+        return false;
+    };
+    let mut scope = path;
+    loop {
+        if share_mac(secret, scope, expires)
+            .verify_slice(&token)
+            .is_ok()
+        {
+            return true;
+        }
+        if scope.is_empty() {
+            return false;
+        }
+        scope = scope.rsplit_once('/').map(|(v, _)| v).unwrap_or_default();
+    }
+}
+
+fn share_mac(secret: &str, path: &str, expires: u64) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(format!("{expires}:{path}").as_bytes());
+    mac
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -252,6 +355,29 @@ impl AccessPaths {
         child.find_impl(&parts[1..], perm)
     }
 
+    /// The most specific rule granting access to `path`, `None` if it is only indexed.
+    pub fn matched_rule(&self, path: &str) -> Option<String> {
+        let parts: Vec<&str> = path
+            .trim_matches('/')
+            .split('/')
+            .filter(|v| !v.is_empty())
+            .collect();
+        let mut node = self;
+        let mut matched = (!node.perm.indexonly()).then_some((0, node.perm));
+        for (i, part) in parts.iter().enumerate() {
+            match node.children.get(*part) {
+                Some(child) => node = child,
+                None => break,
+            }
+            if !node.perm.indexonly() {
+                matched = Some((i + 1, node.perm));
+            }
+        }
+        let (depth, perm) = matched?;
+        let perm = if perm.readwrite() { "rw" } else { "ro" };
+        Some(format!("/{}:{perm}", parts[..depth].join("/")))
+    }
+
     pub fn child_names(&self) -> Vec<&String> {
         self.children.keys().collect()
     }
@@ -559,6 +685,21 @@ fn split_rules(rules: &[&str]) -> Vec<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_share_token() {
+        let token = sign_share("secret", "dir1", 100);
+        assert!(verify_share("secret", "dir1", 100, &token, 100));
+        assert!(verify_share("secret", "dir1/sub/file", 100, &token, 50));
+        assert!(!verify_share("secret", "dir1", 100, &token, 101));
+        assert!(!verify_share("secret", "dir1", 101, &token, 50));
+        assert!(!verify_share("secret", "dir10", 100, &token, 50));
+        assert!(!verify_share("secret", "", 100, &token, 50));
+        assert!(!verify_share("other", "dir1", 100, &token, 50));
+        assert!(!verify_share("secret", "dir1", 100, "!", 50));
+        let token = sign_share("secret", "", 100);
+        assert!(verify_share("secret", "dir1/file", 100, &token, 50));
+    }
+
     #[test]
     fn test_split_account_paths() {
         assert_eq!(
@@ -591,6 +732,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_access_control_summary() {
+        let ac = AccessControl::new(&["admin:secret@/:rw", "@/"]).unwrap();
+        let summary = ac.summary();
+        assert_eq!(summary, "digest,basic (users: admin; anonymous: allowed)");
+        assert!(!summary.contains("secret"));
+        assert_eq!(AccessControl::default().summary(), "off");
+    }
+
     #[test]
     fn test_access_paths() {
         let mut paths = AccessPaths::default();
@@ -648,6 +798,15 @@ mod tests {
             Some(AccessPaths::new(AccessPerm::ReadWrite))
         );
         assert_eq!(paths.find("dir2/dir23/file"), None);
+        assert_eq!(
+            paths.matched_rule("dir2/dir21/dir211/file").as_deref(),
+            Some("/dir2/dir21:rw")
+        );
+        assert_eq!(
+            paths.matched_rule("dir2/dir22/file").as_deref(),
+            Some("/dir2/dir22:ro")
+        );
+        assert_eq!(paths.matched_rule("dir2"), None);
         assert_eq!(
             paths.find("dir2/dir23//dir231/file"),
             Some(AccessPaths::new(AccessPerm::ReadWrite))
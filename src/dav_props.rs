@@ -0,0 +1,442 @@
+use anyhow::Result;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use tokio::{fs, sync::OwnedMutexGuard};
+use uuid::Uuid;
+use xml::{
+    escape::{escape_str_attribute, escape_str_pcdata},
+    reader::{EventReader, XmlEvent},
+};
+
+/// Sidecar holding the WebDAV dead properties of a directory's entries, keyed by file name.
+pub const DAV_PROPS_FILE: &str = ".dufs-props.json";
+pub const DAV_NS: &str = "DAV:";
+
+type DirProps = BTreeMap<String, Vec<DeadProp>>;
+
+lazy_static! {
+    /// One lock per directory, so concurrent updates of a sidecar never lose each other's writes.
+    static ref DIR_LOCKS: Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>> = Default::default();
+}
+
+/// A property set by PROPPATCH that dufs stores but doesn't interpret, RFC 4918 section 4.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct DeadProp {
+    pub ns: String,
+    pub name: String,
+    #[serde(default)]
+    pub value: String,
+}
+
+impl DeadProp {
+    pub fn to_xml(&self) -> String {
+        let value = escape_str_pcdata(&self.value);
+        if self.ns.is_empty() {
+            format!(r#"<{0} xmlns="">{value}</{0}>"#, self.name)
+        } else {
+            format!(
+                r#"<x:{0} xmlns:x="{1}">{value}</x:{0}>"#,
+                self.name,
+                escape_str_attribute(&self.ns)
+            )
+        }
+    }
+
+    /// The empty element naming the property, as listed in a PROPPATCH response.
+    pub fn to_name_xml(&self) -> String {
+        PropName::new(&self.ns, &self.name).to_xml()
+    }
+
+    fn same_name(&self, other: &Self) -> bool {
+        self.ns == other.ns && self.name == other.name
+    }
+}
+
+/// A property name, `ns` is empty for a property outside any namespace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropName {
+    pub ns: String,
+    pub name: String,
+}
+
+impl PropName {
+    pub fn new(ns: &str, name: &str) -> Self {
+        Self {
+            ns: ns.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    /// The empty element naming the property.
+    pub fn to_xml(&self) -> String {
+        if self.ns == DAV_NS {
+            format!("<D:{}/>", self.name)
+        } else if self.ns.is_empty() {
+            format!(r#"<{} xmlns=""/>"#, self.name)
+        } else {
+            format!(
+                r#"<x:{} xmlns:x="{}"/>"#,
+                self.name,
+                escape_str_attribute(&self.ns)
+            )
+        }
+    }
+}
+
+/// What a PROPFIND body asks for, RFC 4918 section 9.1.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PropFind {
+    #[default]
+    AllProp,
+    PropName,
+    Prop(Vec<PropName>),
+}
+
+impl PropFind {
+    /// The `propstat` blocks answering this request from `props`, each a name and its element.
+    ///
+    /// Named properties the resource doesn't have are listed under `404 Not Found`.
+    pub fn propstats(&self, props: &[(PropName, String)]) -> String {
+        let (found, missing) = match self {
+            PropFind::AllProp => (props.iter().map(|(_, xml)| xml.clone()).collect(), vec![]),
+            PropFind::PropName => (
+                props.iter().map(|(name, _)| name.to_xml()).collect(),
+                vec![],
+            ),
+            PropFind::Prop(names) => {
+                let mut found = vec![];
+                let mut missing = vec![];
+                for name in names {
+                    match props.iter().find(|(v, _)| v == name) {
+                        Some((_, xml)) => found.push(xml.clone()),
+                        None => missing.push(name.to_xml()),
+                    }
+                }
+                (found, missing)
+            }
+        };
+        propstat(&found, "200 OK") + &propstat(&missing, "404 Not Found")
+    }
+}
+
+/// A `propstat` block holding `props` under `status`, empty when there are no props.
+pub fn propstat(props: &[String], status: &str) -> String {
+    if props.is_empty() {
+        return String::new();
+    }
+    format!(
+        r#"
+<D:propstat>
+<D:prop>
+{}
+</D:prop>
+<D:status>HTTP/1.1 {status}</D:status>
+</D:propstat>"#,
+        props.join("\n")
+    )
+}
+
+/// Parses a PROPFIND body, `None` when it isn't well-formed XML.
+///
+/// An empty body asks for all properties.
+pub fn parse_propfind(body: &[u8]) -> Option<PropFind> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Some(PropFind::AllProp);
+    }
+    let mut propfind = PropFind::AllProp;
+    let mut depth = 0;
+    let mut in_prop = false;
+    for event in EventReader::new(body) {
+        match event.ok()? {
+            XmlEvent::StartElement { name, .. } => {
+                depth += 1;
+                let is_dav = name.namespace.as_deref() == Some(DAV_NS);
+                match (depth, name.local_name.as_str()) {
+                    (2, "propname") if is_dav => propfind = PropFind::PropName,
+                    (2, "prop") if is_dav => {
+                        propfind = PropFind::Prop(vec![]);
+                        in_prop = true;
+                    }
+                    (3, _) if in_prop => {
+                        if let PropFind::Prop(names) = &mut propfind {
+                            names.push(PropName {
+                                ns: name.namespace.unwrap_or_default(),
+                                name: name.local_name,
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            XmlEvent::EndElement { .. } => {
+                if depth == 2 {
+                    in_prop = false;
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+    }
+    Some(propfind)
+}
+
+/// One `set` or `remove` instruction of a `propertyupdate` body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropUpdate {
+    pub remove: bool,
+    pub prop: DeadProp,
+}
+
+/// Parses a PROPPATCH body, `None` when it isn't well-formed XML.
+///
+/// Property values keep only their text, nested markup is flattened.
+pub fn parse_propertyupdate(body: &[u8]) -> Option<Vec<PropUpdate>> {
+    let mut updates = vec![];
+    let mut remove = None;
+    let mut in_prop = false;
+    // the property being read and how deep we are inside its value
+    let mut current: Option<(DeadProp, usize)> = None;
+    for event in EventReader::new(body) {
+        match event.ok()? {
+            XmlEvent::StartElement { name, .. } => {
+                if let Some((_, depth)) = current.as_mut() {
+                    *depth += 1;
+                    continue;
+                }
+                let is_dav = name.namespace.as_deref() == Some(DAV_NS);
+                match name.local_name.as_str() {
+                    _ if in_prop => {
+                        let prop = DeadProp {
+                            ns: name.namespace.unwrap_or_default(),
+                            name: name.local_name,
+                            value: String::new(),
+                        };
+                        current = Some((prop, 0));
+                    }
+                    "set" if is_dav => remove = Some(false),
+                    "remove" if is_dav => remove = Some(true),
+                    "prop" if is_dav && remove.is_some() => in_prop = true,
+                    _ => {}
+                }
+            }
+            XmlEvent::Characters(text) | XmlEvent::CData(text) => {
+                if let Some((prop, _)) = current.as_mut() {
+                    prop.value.push_str(&text);
+                }
+            }
+            XmlEvent::EndElement { .. } => match current.take() {
+                Some((prop, 0)) => updates.push(PropUpdate {
+                    remove: remove.unwrap_or_default(),
+                    prop,
+                }),
+                Some((prop, depth)) => current = Some((prop, depth - 1)),
+                None if in_prop => in_prop = false,
+                None => remove = None,
+            },
+            _ => {}
+        }
+    }
+    Some(updates)
+}
+
+/// The dead properties of every entry in `dir`, empty when there is no readable sidecar.
+pub async fn load(dir: &Path) -> DirProps {
+    match fs::read(dir.join(DAV_PROPS_FILE)).await {
+        Ok(data) => serde_json::from_slice(&data).unwrap_or_else(|err| {
+            warn!(
+                "Ignore invalid {DAV_PROPS_FILE} in {}, {err}",
+                dir.display()
+            );
+            Default::default()
+        }),
+        Err(_) => Default::default(),
+    }
+}
+
+/// The dead properties of `path`.
+pub async fn load_for(path: &Path) -> Vec<DeadProp> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return vec![];
+    };
+    load(dir)
+        .await
+        .remove(name.to_string_lossy().as_ref())
+        .unwrap_or_default()
+}
+
+async fn lock_dir(dir: &Path) -> OwnedMutexGuard<()> {
+    let lock = {
+        let mut locks = DIR_LOCKS.lock().unwrap();
+        // only the map holds idle locks, dropping them keeps it as small as the updates in flight
+        locks.retain(|_, v| Arc::strong_count(v) > 1);
+        locks.entry(dir.to_path_buf()).or_default().clone()
+    };
+    lock.lock_owned().await
+}
+
+/// Replaces the sidecar through a rename, so a crash mid-write never leaves it half written.
+async fn save(dir: &Path, props: &DirProps) -> Result<()> {
+    let file = dir.join(DAV_PROPS_FILE);
+    if props.is_empty() {
+        match fs::remove_file(&file).await {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+            _ => return Ok(()),
+        }
+    }
+    let temp_file = dir.join(format!("{DAV_PROPS_FILE}.{}", Uuid::new_v4().simple()));
+    fs::write(&temp_file, serde_json::to_vec_pretty(props)?).await?;
+    if let Err(err) = fs::rename(&temp_file, &file).await {
+        let _ = fs::remove_file(&temp_file).await;
+        return Err(err.into());
+    }
+    Ok(())
+}
+
+/// Applies validated updates to the stored properties of `path`.
+pub async fn apply(path: &Path, updates: &[PropUpdate]) -> Result<()> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Ok(());
+    };
+    let name = name.to_string_lossy().to_string();
+    let _lock = lock_dir(dir).await;
+    let mut props = load(dir).await;
+    let entry = props.entry(name.clone()).or_default();
+    for update in updates {
+        entry.retain(|v| !v.same_name(&update.prop));
+        if !update.remove {
+            entry.push(update.prop.clone());
+        }
+    }
+    if entry.is_empty() {
+        props.remove(&name);
+    }
+    save(dir, &props).await
+}
+
+/// Gives `dest` the properties of `src`, then drops those of `src` unless it is kept.
+///
+/// `dest` is `None` when `src` was deleted.
+pub async fn transfer(src: &Path, dest: Option<&Path>, keep_src: bool) -> Result<()> {
+    let (Some(src_dir), Some(src_name)) = (src.parent(), src.file_name()) else {
+        return Ok(());
+    };
+    let src_name = src_name.to_string_lossy().to_string();
+    let moved = {
+        let _lock = lock_dir(src_dir).await;
+        let mut src_props = load(src_dir).await;
+        let moved = if keep_src {
+            src_props.get(&src_name).cloned()
+        } else {
+            src_props.remove(&src_name)
+        };
+        if moved.is_some() && !keep_src {
+            save(src_dir, &src_props).await?;
+        }
+        moved
+    };
+    let Some((dest_dir, dest_name)) = dest.and_then(|v| Some((v.parent()?, v.file_name()?))) else {
+        return Ok(());
+    };
+    let dest_name = dest_name.to_string_lossy().to_string();
+    let _lock = lock_dir(dest_dir).await;
+    let mut dest_props = load(dest_dir).await;
+    // a replaced destination loses whatever it had
+    let changed = match moved {
+        Some(moved) => {
+            dest_props.insert(dest_name, moved);
+            true
+        }
+        None => dest_props.remove(&dest_name).is_some(),
+    };
+    if changed {
+        save(dest_dir, &dest_props).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_propertyupdate() {
+        let body = r#"<?xml version="1.0" encoding="utf-8" ?>
+<D:propertyupdate xmlns:D="DAV:" xmlns:Z="urn:schemas-microsoft-com:">
+  <D:set>
+    <D:prop>
+      <Z:Win32CreationTime>Mon, 01 Jan 2024 00:00:00 GMT</Z:Win32CreationTime>
+      <Z:Win32FileAttributes>00000020</Z:Win32FileAttributes>
+    </D:prop>
+  </D:set>
+  <D:remove>
+    <D:prop><author xmlns="http://example.com/ns"><b>x</b></author></D:prop>
+  </D:remove>
+</D:propertyupdate>"#;
+        let updates = parse_propertyupdate(body.as_bytes()).unwrap();
+        let ms = "urn:schemas-microsoft-com:".to_string();
+        assert_eq!(
+            updates,
+            vec![
+                PropUpdate {
+                    remove: false,
+                    prop: DeadProp {
+                        ns: ms.clone(),
+                        name: "Win32CreationTime".into(),
+                        value: "Mon, 01 Jan 2024 00:00:00 GMT".into(),
+                    },
+                },
+                PropUpdate {
+                    remove: false,
+                    prop: DeadProp {
+                        ns: ms,
+                        name: "Win32FileAttributes".into(),
+                        value: "00000020".into(),
+                    },
+                },
+                PropUpdate {
+                    remove: true,
+                    prop: DeadProp {
+                        ns: "http://example.com/ns".into(),
+                        name: "author".into(),
+                        value: "x".into(),
+                    },
+                },
+            ]
+        );
+        assert!(parse_propertyupdate(b"<D:propertyupdate xmlns:D=\"DAV:\">").is_none());
+    }
+
+    #[test]
+    fn test_parse_propfind() {
+        assert_eq!(parse_propfind(b""), Some(PropFind::AllProp));
+        assert_eq!(
+            parse_propfind(br#"<D:propfind xmlns:D="DAV:"><D:allprop/></D:propfind>"#),
+            Some(PropFind::AllProp)
+        );
+        assert_eq!(
+            parse_propfind(br#"<D:propfind xmlns:D="DAV:"><D:propname/></D:propfind>"#),
+            Some(PropFind::PropName)
+        );
+        let body = r#"<?xml version="1.0" encoding="utf-8" ?>
+<D:propfind xmlns:D="DAV:">
+  <D:prop xmlns:Z="urn:example">
+    <D:getcontentlength/>
+    <Z:color><Z:ignored/></Z:color>
+  </D:prop>
+</D:propfind>"#;
+        assert_eq!(
+            parse_propfind(body.as_bytes()),
+            Some(PropFind::Prop(vec![
+                PropName::new(DAV_NS, "getcontentlength"),
+                PropName::new("urn:example", "color"),
+            ]))
+        );
+        assert!(parse_propfind(b"<D:propfind xmlns:D=\"DAV:\">").is_none());
+    }
+}
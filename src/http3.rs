@@ -0,0 +1,143 @@
+use crate::server::{Response, Server};
+use crate::{Drain, InFlightGuard};
+
+use anyhow::Result;
+use bytes::{Buf, Bytes};
+use http_body_util::BodyExt;
+use hyper::body::{Body, Frame};
+use hyper::header::{HeaderValue, HOST};
+use hyper::Uri;
+use quinn::crypto::rustls::QuicServerConfig;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_rustls::rustls::ServerConfig;
+
+pub fn serve(
+    server: Arc<Server>,
+    addr: SocketAddr,
+    mut config: ServerConfig,
+    drain: Drain,
+) -> Result<JoinHandle<()>> {
+    config.alpn_protocols = vec![b"h3".to_vec()];
+    let config = QuicServerConfig::try_from(config)?;
+    let endpoint = quinn::Endpoint::new(
+        quinn::EndpointConfig::default(),
+        Some(quinn::ServerConfig::with_crypto(Arc::new(config))),
+        create_socket(addr)?,
+        Arc::new(quinn::TokioRuntime),
+    )?;
+    let handle = tokio::spawn(async move {
+        while let Some(incoming) = endpoint.accept().await {
+            tokio::spawn(handle_connection(server.clone(), incoming, drain.clone()));
+        }
+    });
+    Ok(handle)
+}
+
+fn create_socket(addr: SocketAddr) -> Result<std::net::UdpSocket> {
+    use socket2::{Domain, Protocol, Socket, Type};
+    let socket = Socket::new(Domain::for_address(addr), Type::DGRAM, Some(Protocol::UDP))?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+    socket.bind(&addr.into())?;
+    socket.set_nonblocking(true)?;
+    Ok(socket.into())
+}
+
+async fn handle_connection(
+    server: Arc<Server>,
+    incoming: quinn::Incoming,
+    mut drain: Drain,
+) -> Result<()> {
+    drain.connections.fetch_add(1, Ordering::SeqCst);
+    let _conn_guard = InFlightGuard(drain.connections.clone());
+    let conn = incoming.await?;
+    let addr = conn.remote_address();
+    let mut conn: h3::server::Connection<_, Bytes> =
+        h3::server::Connection::new(h3_quinn::Connection::new(conn)).await?;
+    let mut requests = JoinSet::new();
+    let mut draining = false;
+    loop {
+        let resolver = tokio::select! {
+            ret = conn.accept() => match ret? {
+                Some(v) => v,
+                None => break,
+            },
+            _ = async { drain.signal.wait_for(|v| *v).await.is_ok() }, if !draining => {
+                // a GOAWAY refuses new requests, those already accepted still finish
+                draining = true;
+                conn.shutdown(0).await?;
+                continue;
+            }
+        };
+        let server = server.clone();
+        requests.spawn(async move {
+            let (req, stream) = resolver.resolve_request().await?;
+            let (mut send, mut recv) = stream.split();
+
+            let (tx, rx) = mpsc::channel(4);
+            tokio::spawn(async move {
+                loop {
+                    let chunk = match recv.recv_data().await {
+                        Ok(Some(mut chunk)) => Ok(chunk.copy_to_bytes(chunk.remaining())),
+                        Ok(None) => break,
+                        Err(err) => Err(anyhow::Error::from(err)),
+                    };
+                    let failed = chunk.is_err();
+                    if tx.send(chunk).await.is_err() || failed {
+                        break;
+                    }
+                }
+            });
+
+            // h3 requests carry an absolute uri, reduce it to the origin-form hyper hands us
+            let (mut parts, _) = req.into_parts();
+            if let Some(authority) = parts.uri.authority() {
+                if let Ok(host) = HeaderValue::from_str(authority.as_str()) {
+                    parts.headers.entry(HOST).or_insert(host);
+                }
+            }
+            if let Some(path_and_query) = parts.uri.path_and_query() {
+                parts.uri = Uri::from(path_and_query.clone());
+            }
+            let req = hyper::Request::from_parts(parts, RecvBody(rx).boxed());
+            let res: Response = server.call(req, Some(addr)).await?;
+            let (parts, mut body) = res.into_parts();
+            send.send_response(hyper::Response::from_parts(parts, ()))
+                .await?;
+            while let Some(frame) = body.frame().await {
+                if let Ok(data) = frame?.into_data() {
+                    send.send_data(data).await?;
+                }
+            }
+            send.finish().await?;
+            anyhow::Ok(())
+        });
+    }
+    // the connection counts towards the drain until its last response is sent
+    while requests.join_next().await.is_some() {}
+    Ok(())
+}
+
+/// Request body fed from the QUIC receive half.
+struct RecvBody(mpsc::Receiver<Result<Bytes>>);
+
+impl Body for RecvBody {
+    type Data = Bytes;
+    type Error = anyhow::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        self.0
+            .poll_recv(cx)
+            .map(|chunk| chunk.map(|v| v.map(Frame::data)))
+    }
+}
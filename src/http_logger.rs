@@ -1,3 +1,4 @@
+use hyper::Uri;
 use std::{collections::HashMap, str::FromStr};
 
 use crate::{auth::get_auth_user, server::Request};
@@ -29,7 +30,10 @@ impl HttpLogger {
             match element {
                 LogElement::Variable(name) => match name.as_str() {
                     "request" => {
-                        data.insert(name.to_string(), format!("{} {}", req.method(), req.uri()));
+                        data.insert(
+                            name.to_string(),
+                            format!("{} {}", req.method(), redact_uri(req.uri())),
+                        );
                     }
                     "remote_user" => {
                         if let Some(user) =
@@ -70,6 +74,22 @@ impl HttpLogger {
     }
 }
 
+/// A share link's token grants access on its own, so it never reaches the log.
+fn redact_uri(uri: &Uri) -> String {
+    let uri = uri.to_string();
+    let Some((base, query)) = uri.split_once('?') else {
+        return uri;
+    };
+    let query: Vec<_> = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some(("token", _)) => "token=REDACTED",
+            _ => pair,
+        })
+        .collect();
+    format!("{base}?{}", query.join("&"))
+}
+
 impl FromStr for HttpLogger {
     type Err = anyhow::Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
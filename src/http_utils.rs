@@ -1,21 +1,27 @@
 use bytes::{Bytes, BytesMut};
 use futures_util::Stream;
 use http_body_util::{combinators::BoxBody, BodyExt, Full};
-use hyper::body::{Body, Incoming};
+use hyper::body::Body;
 use std::{
+    io,
     pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
+    time::{Duration, Instant},
 };
-use tokio::io::AsyncRead;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 use tokio_util::io::poll_read_buf;
 
 #[derive(Debug)]
 pub struct IncomingStream {
-    inner: Incoming,
+    inner: BoxBody<Bytes, anyhow::Error>,
 }
 
 impl IncomingStream {
-    pub fn new(inner: Incoming) -> Self {
+    pub fn new(inner: BoxBody<Bytes, anyhow::Error>) -> Self {
         Self { inner }
     }
 }
@@ -48,12 +54,12 @@ pin_project_lite::pin_project! {
 }
 
 impl<R> LengthLimitedStream<R> {
-    pub fn new(reader: R, limit: usize) -> Self {
+    pub fn new(reader: R, limit: usize, capacity: usize) -> Self {
         Self {
             reader: Some(reader),
             remaining: limit,
             buf: BytesMut::new(),
-            capacity: 4096,
+            capacity,
         }
     }
 }
@@ -103,3 +109,94 @@ pub fn body_full(content: impl Into<hyper::body::Bytes>) -> BoxBody<Bytes, anyho
         .map_err(anyhow::Error::new)
         .boxed()
 }
+
+/// When bytes last moved on a connection.
+pub struct Activity {
+    start: Instant,
+    last: AtomicU64,
+}
+
+impl Activity {
+    fn touch(&self) {
+        let elapsed = self.start.elapsed().as_millis() as u64;
+        self.last.store(elapsed, Ordering::Relaxed);
+    }
+
+    pub fn idle_for(&self) -> Duration {
+        let last = Duration::from_millis(self.last.load(Ordering::Relaxed));
+        self.start.elapsed().saturating_sub(last)
+    }
+}
+
+/// Wraps a connection's stream to record its activity for `--idle-timeout`.
+pub struct IdleIo<T> {
+    inner: T,
+    activity: Arc<Activity>,
+}
+
+impl<T> IdleIo<T> {
+    pub fn new(inner: T) -> Self {
+        let activity = Activity {
+            start: Instant::now(),
+            last: AtomicU64::new(0),
+        };
+        Self {
+            inner,
+            activity: Arc::new(activity),
+        }
+    }
+
+    pub fn activity(&self) -> Arc<Activity> {
+        self.activity.clone()
+    }
+
+    fn track<V>(&self, ret: Poll<io::Result<V>>) -> Poll<io::Result<V>> {
+        if let Poll::Ready(Ok(_)) = ret {
+            self.activity.touch();
+        }
+        ret
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for IdleIo<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let ret = Pin::new(&mut self.inner).poll_read(cx, buf);
+        self.track(ret)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for IdleIo<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let ret = Pin::new(&mut self.inner).poll_write(cx, buf);
+        self.track(ret)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let ret = Pin::new(&mut self.inner).poll_write_vectored(cx, bufs);
+        self.track(ret)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
@@ -1,40 +1,54 @@
 mod args;
 mod auth;
+mod dav_props;
+#[cfg(feature = "http3")]
+mod http3;
 mod http_logger;
 mod http_utils;
 mod logger;
+mod metrics;
 mod server;
 mod utils;
 
 #[macro_use]
 extern crate log;
 
-use crate::args::{build_cli, print_completions, Args};
+use crate::args::{build_cli, print_completions, print_man, Args};
+use crate::auth::sign_share;
+use crate::http_utils::IdleIo;
 use crate::server::Server;
+use crate::utils::{encode_uri, unix_now};
 #[cfg(feature = "tls")]
 use crate::utils::{load_certs, load_private_key};
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use args::BindAddr;
 use clap_complete::Shell;
 use futures_util::future::join_all;
 
+use http_body_util::BodyExt;
 use hyper::{body::Incoming, service::service_fn, Request};
 use hyper_util::{
     rt::{TokioExecutor, TokioIo},
     server::conn::auto::Builder,
 };
-use std::net::{IpAddr, SocketAddr, TcpListener as StdTcpListener};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener as StdTcpListener};
+#[cfg(feature = "tls")]
+use std::path::Path;
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicUsize, Ordering},
     Arc,
 };
 use std::time::Duration;
+use tokio::sync::watch;
 use tokio::time::timeout;
 use tokio::{net::TcpListener, task::JoinHandle};
 #[cfg(feature = "tls")]
 use tokio_rustls::{rustls::ServerConfig, TlsAcceptor};
 
+/// How long a shutdown waits for open connections to finish their requests
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cmd = build_cli();
@@ -44,14 +58,49 @@ async fn main() -> Result<()> {
         print_completions(*generator, &mut cmd);
         return Ok(());
     }
+    if matches.get_flag("man") {
+        return print_man(build_cli());
+    }
+    let share = matches.get_one::<String>("share").cloned();
+    let share_ttl = matches
+        .get_one::<u64>("share-ttl")
+        .copied()
+        .unwrap_or(86400);
+    let check = matches.get_flag("check");
     let mut args = Args::parse(matches)?;
+    if let Some(path) = share {
+        println!("{}", share_link(&args, &path, share_ttl)?);
+        return Ok(());
+    }
     logger::init(args.log_file.clone()).map_err(|e| anyhow!("Failed to init logger, {e}"))?;
     let (new_addrs, print_addrs) = check_addrs(&args)?;
     args.addrs = new_addrs;
+    if check {
+        let summary = print_summary(&args);
+        check_config(args)?;
+        println!("{summary}");
+        println!("Configuration OK");
+        return Ok(());
+    }
     let running = Arc::new(AtomicBool::new(true));
+    let summary = (!args.quiet).then(|| print_summary(&args));
     let listening = print_listening(&args, &print_addrs)?;
-    let handles = serve(args, running.clone())?;
+    let open_url = args.open.then(|| browser_url(&args));
+    let (drain_tx, drain_rx) = watch::channel(false);
+    let drain = Drain {
+        connections: Arc::new(AtomicUsize::new(0)),
+        signal: drain_rx,
+    };
+    let connections = drain.connections.clone();
+    let handles = serve(args, running.clone(), drain)?;
+    let listeners: Vec<_> = handles.iter().map(|v| v.abort_handle()).collect();
+    if let Some(summary) = summary {
+        println!("{summary}");
+    }
     println!("{listening}");
+    if let Some(url) = open_url {
+        open_browser(url);
+    }
 
     tokio::select! {
         ret = join_all(handles) => {
@@ -63,20 +112,53 @@ async fn main() -> Result<()> {
             Ok(())
         },
         _ = shutdown_signal() => {
+            // stop accepting, then let the open connections finish their requests
+            for listener in listeners {
+                listener.abort();
+            }
+            let _ = drain_tx.send(true);
+            info!(
+                "Shutting down, waiting for {} connections to finish",
+                connections.load(Ordering::SeqCst)
+            );
+            let drained = async {
+                while connections.load(Ordering::SeqCst) > 0 {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            };
+            let drained = timeout(SHUTDOWN_TIMEOUT, drained).await.is_ok();
             running.store(false, Ordering::SeqCst);
+            if drained {
+                info!("Shutdown complete");
+            } else {
+                warn!(
+                    "Shutdown timed out, dropping {} connections",
+                    connections.load(Ordering::SeqCst)
+                );
+            }
             Ok(())
         },
     }
 }
 
-fn serve(args: Args, running: Arc<AtomicBool>) -> Result<Vec<JoinHandle<()>>> {
+fn serve(args: Args, running: Arc<AtomicBool>, drain: Drain) -> Result<Vec<JoinHandle<()>>> {
     let addrs = args.addrs.clone();
     let port = args.port;
     let tls_config = (args.tls_cert.clone(), args.tls_key.clone());
+    let conn_options = ConnOptions {
+        idle_timeout: args.idle_timeout.map(Duration::from_secs),
+    };
+    #[cfg(feature = "http3")]
+    let http3 = args.http3;
     let server_handle = Arc::new(Server::init(args, running)?);
+    if !server_handle.probe_ready() {
+        warn!("Serve path is not accessible yet, answering 503 until it is");
+        tokio::spawn(server_handle.clone().wait_ready());
+    }
     let mut handles = vec![];
     for bind_addr in addrs.iter() {
         let server_handle = server_handle.clone();
+        let drain = drain.clone();
         match bind_addr {
             BindAddr::IpAddr(ip) => {
                 let listener = create_listener(SocketAddr::new(*ip, port))
@@ -85,11 +167,19 @@ fn serve(args: Args, running: Arc<AtomicBool>) -> Result<Vec<JoinHandle<()>>> {
                 match &tls_config {
                     #[cfg(feature = "tls")]
                     (Some(cert_file), Some(key_file)) => {
-                        let certs = load_certs(cert_file)?;
-                        let key = load_private_key(key_file)?;
-                        let mut config = ServerConfig::builder()
-                            .with_no_client_auth()
-                            .with_single_cert(certs, key)?;
+                        let mut config = load_tls_config(cert_file, key_file)?;
+                        #[cfg(feature = "http3")]
+                        if http3 {
+                            let addr = SocketAddr::new(*ip, port);
+                            let handle = http3::serve(
+                                server_handle.clone(),
+                                addr,
+                                config.clone(),
+                                drain.clone(),
+                            )
+                            .with_context(|| format!("Failed to bind `{ip}:{port}` (udp)"))?;
+                            handles.push(handle);
+                        }
                         config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
                         let config = Arc::new(config);
                         let tls_accepter = TlsAcceptor::from(config);
@@ -108,11 +198,12 @@ fn serve(args: Args, running: Arc<AtomicBool>) -> Result<Vec<JoinHandle<()>>> {
                                 else {
                                     continue;
                                 };
-                                let stream = TokioIo::new(stream);
                                 tokio::spawn(handle_stream(
                                     server_handle.clone(),
                                     stream,
                                     Some(addr),
+                                    conn_options,
+                                    drain.clone(),
                                 ));
                             }
                         });
@@ -125,11 +216,12 @@ fn serve(args: Args, running: Arc<AtomicBool>) -> Result<Vec<JoinHandle<()>>> {
                                 let Ok((stream, addr)) = listener.accept().await else {
                                     continue;
                                 };
-                                let stream = TokioIo::new(stream);
                                 tokio::spawn(handle_stream(
                                     server_handle.clone(),
                                     stream,
                                     Some(addr),
+                                    conn_options,
+                                    drain.clone(),
                                 ));
                             }
                         });
@@ -160,8 +252,13 @@ fn serve(args: Args, running: Arc<AtomicBool>) -> Result<Vec<JoinHandle<()>>> {
                         let Ok((stream, _addr)) = listener.accept().await else {
                             continue;
                         };
-                        let stream = TokioIo::new(stream);
-                        tokio::spawn(handle_stream(server_handle.clone(), stream, None));
+                        tokio::spawn(handle_stream(
+                            server_handle.clone(),
+                            stream,
+                            None,
+                            conn_options,
+                            drain.clone(),
+                        ));
                     }
                 });
 
@@ -172,26 +269,90 @@ fn serve(args: Args, running: Arc<AtomicBool>) -> Result<Vec<JoinHandle<()>>> {
     Ok(handles)
 }
 
-async fn handle_stream<T>(handle: Arc<Server>, stream: TokioIo<T>, addr: Option<SocketAddr>)
-where
+async fn handle_stream<T>(
+    handle: Arc<Server>,
+    stream: T,
+    addr: Option<SocketAddr>,
+    options: ConnOptions,
+    mut drain: Drain,
+) where
     T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
 {
-    let hyper_service =
-        service_fn(move |request: Request<Incoming>| handle.clone().call(request, addr));
+    drain.connections.fetch_add(1, Ordering::SeqCst);
+    let _conn_guard = InFlightGuard(drain.connections.clone());
+    let stream = IdleIo::new(stream);
+    let activity = stream.activity();
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let in_flight_clone = in_flight.clone();
+    let hyper_service = service_fn(move |request: Request<Incoming>| {
+        let request = request.map(|body| body.map_err(anyhow::Error::from).boxed());
+        let handle = handle.clone();
+        let in_flight = in_flight_clone.clone();
+        async move {
+            in_flight.fetch_add(1, Ordering::SeqCst);
+            let _guard = InFlightGuard(in_flight);
+            handle.call(request, addr).await
+        }
+    });
 
-    match Builder::new(TokioExecutor::new())
-        .serve_connection_with_upgrades(stream, hyper_service)
-        .await
-    {
-        Ok(()) => {}
-        Err(_err) => {
-            // This error only appears when the client doesn't send a request and terminate the connection.
-            //
-            // If client sends one request then terminate connection whenever, it doesn't appear.
+    let builder = Builder::new(TokioExecutor::new());
+    let conn = builder.serve_connection_with_upgrades(TokioIo::new(stream), hyper_service);
+    tokio::pin!(conn);
+    let mut draining = false;
+    loop {
+        // nothing moved and no request is being handled, e.g. a stale proxy connection
+        let wait = match options.idle_timeout {
+            Some(idle_timeout) if in_flight.load(Ordering::SeqCst) == 0 => {
+                match idle_timeout.checked_sub(activity.idle_for()) {
+                    Some(v) if !v.is_zero() => v,
+                    _ => return,
+                }
+            }
+            Some(idle_timeout) => idle_timeout,
+            None => Duration::MAX,
+        };
+        tokio::select! {
+            ret = conn.as_mut() => {
+                match ret {
+                    Ok(()) => {}
+                    Err(_err) => {
+                        // This error only appears when the client doesn't send a request and terminate the connection.
+                        //
+                        // If client sends one request then terminate connection whenever, it doesn't appear.
+                    }
+                }
+                return;
+            }
+            _ = tokio::time::sleep(wait) => {}
+            _ = drain.signal.wait_for(|v| *v), if !draining => {
+                // finish the requests in flight, then close
+                draining = true;
+                conn.as_mut().graceful_shutdown();
+            }
         }
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+struct ConnOptions {
+    idle_timeout: Option<Duration>,
+}
+
+/// Shared by all connections, so a shutdown can wait for them to finish
+#[derive(Debug, Clone)]
+struct Drain {
+    connections: Arc<AtomicUsize>,
+    signal: watch::Receiver<bool>,
+}
+
+struct InFlightGuard(Arc<AtomicUsize>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 fn create_listener(addr: SocketAddr) -> Result<TcpListener> {
     use socket2::{Domain, Protocol, Socket, Type};
     let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
@@ -207,6 +368,33 @@ fn create_listener(addr: SocketAddr) -> Result<TcpListener> {
     Ok(listener)
 }
 
+#[cfg(feature = "tls")]
+fn load_tls_config(cert_file: &Path, key_file: &Path) -> Result<ServerConfig> {
+    let certs = load_certs(cert_file)?;
+    let key = load_private_key(key_file)?;
+    let config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .with_context(|| "Failed to use the tls certificate and key")?;
+    Ok(config)
+}
+
+/// Everything `serve` would trip over at startup, short of binding the ports.
+fn check_config(args: Args) -> Result<()> {
+    if args.addrs.is_empty() {
+        bail!("None of the bind addresses is available on this host");
+    }
+    #[cfg(feature = "tls")]
+    if let (Some(cert_file), Some(key_file)) = (&args.tls_cert, &args.tls_key) {
+        load_tls_config(cert_file, key_file)?;
+    }
+    let server = Server::init(args, Arc::new(AtomicBool::new(false)))?;
+    if !server.probe_ready() {
+        warn!("Serve path is not accessible yet, dufs would answer 503 until it is");
+    }
+    Ok(())
+}
+
 fn check_addrs(args: &Args) -> Result<(Vec<BindAddr>, Vec<BindAddr>)> {
     let mut new_addrs = vec![];
     let mut print_addrs = vec![];
@@ -262,6 +450,48 @@ fn interface_addrs() -> Result<(Vec<BindAddr>, Vec<BindAddr>)> {
     Ok((ipv4_addrs, ipv6_addrs))
 }
 
+fn print_summary(args: &Args) -> String {
+    let addrs = args
+        .addrs
+        .iter()
+        .map(|bind_addr| match bind_addr {
+            BindAddr::IpAddr(ip) => ip.to_string(),
+            #[cfg(unix)]
+            BindAddr::SocketPath(path) => path.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    let perms = [
+        ("upload", args.allow_upload),
+        ("delete", args.allow_delete),
+        ("search", args.allow_search),
+        ("symlink", args.allow_symlink),
+        ("archive", args.allow_archive),
+    ]
+    .into_iter()
+    .filter_map(|(name, enabled)| enabled.then_some(name))
+    .collect::<Vec<_>>();
+    let perms = if perms.is_empty() {
+        "read-only".to_string()
+    } else {
+        perms.join(",")
+    };
+    let tls = match (args.tls_cert.is_some(), args.http3) {
+        (true, true) => "on (http3)",
+        (true, false) => "on",
+        _ => "off",
+    };
+    [
+        format!("Serving {}", args.serve_path.display()),
+        format!("  bind: {addrs} (port {})", args.port),
+        format!("  path-prefix: {}", args.uri_prefix),
+        format!("  permissions: {perms}"),
+        format!("  auth: {}", args.auth.summary()),
+        format!("  tls: {tls}"),
+    ]
+    .join("\n")
+}
+
 fn print_listening(args: &Args, print_addrs: &[BindAddr]) -> Result<String> {
     let mut output = String::new();
     let urls = print_addrs
@@ -298,8 +528,99 @@ fn print_listening(args: &Args, print_addrs: &[BindAddr]) -> Result<String> {
     Ok(output)
 }
 
+/// The url `--open` points the browser at, preferring loopback when bound to it or to all interfaces.
+fn browser_url(args: &Args) -> Option<String> {
+    let ips: Vec<IpAddr> = args
+        .addrs
+        .iter()
+        .filter_map(|v| match v {
+            BindAddr::IpAddr(ip) => Some(*ip),
+            #[cfg(unix)]
+            BindAddr::SocketPath(_) => None,
+        })
+        .collect();
+    let ip = ips
+        .iter()
+        .find_map(|ip| match ip {
+            IpAddr::V4(v) if v.is_unspecified() || v.is_loopback() => {
+                Some(IpAddr::V4(Ipv4Addr::LOCALHOST))
+            }
+            IpAddr::V6(v) if v.is_unspecified() || v.is_loopback() => {
+                Some(IpAddr::V6(Ipv6Addr::LOCALHOST))
+            }
+            _ => None,
+        })
+        .or_else(|| ips.first().copied())?;
+    let host = match ip {
+        IpAddr::V4(_) => ip.to_string(),
+        IpAddr::V6(_) => format!("[{ip}]"),
+    };
+    let protocol = if args.tls_cert.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    Some(format!(
+        "{protocol}://{host}:{}{}",
+        args.port, args.uri_prefix
+    ))
+}
+
+/// A link that lets anyone read `path`, or everything under it, for `ttl` seconds.
+fn share_link(args: &Args, path: &str, ttl: u64) -> Result<String> {
+    let Some(secret) = &args.share_secret else {
+        bail!("--share needs --share-secret");
+    };
+    let parts: Vec<_> = path.split('/').filter(|v| !v.is_empty()).collect();
+    if parts.iter().any(|v| *v == "." || *v == "..") {
+        bail!("Invalid share path '{path}'");
+    }
+    let shared = parts.join("/");
+    let expires = unix_now()?.as_secs() + ttl;
+    // requests are verified against the path below the serve root, after aliases
+    let token = sign_share(secret, &args.apply_alias(shared.clone()), expires);
+    let mut href = encode_uri(&shared);
+    if !shared.is_empty() && path.ends_with('/') {
+        href.push('/');
+    }
+    let base = browser_url(args).unwrap_or_else(|| args.uri_prefix.clone());
+    Ok(format!("{base}{href}?expires={expires}&token={token}"))
+}
+
+fn open_browser(url: Option<String>) {
+    let Some(url) = url else {
+        warn!("Nothing to open, dufs only listens on unix sockets");
+        return;
+    };
+    if !webbrowser::Browser::is_available() {
+        warn!("No browser available, open {url} manually");
+        return;
+    }
+    // launching may wait on the platform opener
+    tokio::task::spawn_blocking(move || {
+        if let Err(err) = webbrowser::open(&url) {
+            warn!("Failed to open {url} in browser, {err}");
+        }
+    });
+}
+
 async fn shutdown_signal() {
-    tokio::signal::ctrl_c()
-        .await
-        .expect("Failed to install CTRL+C signal handler")
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install CTRL+C signal handler")
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM signal handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
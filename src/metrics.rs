@@ -0,0 +1,118 @@
+use hyper::{Method, StatusCode};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Methods counted under their own label, anything else is counted as `OTHER`
+const METHODS: &[&str] = &[
+    "GET",
+    "HEAD",
+    "POST",
+    "PUT",
+    "PATCH",
+    "DELETE",
+    "OPTIONS",
+    "PROPFIND",
+    "PROPPATCH",
+    "MKCOL",
+    "COPY",
+    "MOVE",
+    "LOCK",
+    "UNLOCK",
+];
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    requests: Mutex<BTreeMap<(&'static str, &'static str), u64>>,
+    response_bytes: AtomicU64,
+    active_uploads: AtomicUsize,
+}
+
+impl Metrics {
+    pub fn record_request(&self, method: &Method, status: StatusCode) {
+        let method = METHODS
+            .iter()
+            .find(|v| **v == method.as_str())
+            .copied()
+            .unwrap_or("OTHER");
+        let class = match status.as_u16() {
+            100..=199 => "1xx",
+            200..=299 => "2xx",
+            300..=399 => "3xx",
+            400..=499 => "4xx",
+            _ => "5xx",
+        };
+        *self
+            .requests
+            .lock()
+            .unwrap()
+            .entry((method, class))
+            .or_default() += 1;
+    }
+
+    pub fn record_response_bytes(&self, len: u64) {
+        self.response_bytes.fetch_add(len, Ordering::Relaxed);
+    }
+
+    /// Count an upload as in progress until the returned guard is dropped
+    pub fn start_upload(&self) -> ActiveUpload<'_> {
+        self.active_uploads.fetch_add(1, Ordering::Relaxed);
+        ActiveUpload(self)
+    }
+
+    /// Render in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        output
+            .push_str("# HELP dufs_requests_total Requests handled, by method and status class.\n");
+        output.push_str("# TYPE dufs_requests_total counter\n");
+        for ((method, class), count) in self.requests.lock().unwrap().iter() {
+            let _ = writeln!(
+                output,
+                "dufs_requests_total{{method=\"{method}\",status=\"{class}\"}} {count}"
+            );
+        }
+        let _ = writeln!(
+            output,
+            "# HELP dufs_response_bytes_total Bytes of response bodies sent.\n# TYPE dufs_response_bytes_total counter\ndufs_response_bytes_total {}",
+            self.response_bytes.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(
+            output,
+            "# HELP dufs_active_uploads Uploads in progress.\n# TYPE dufs_active_uploads gauge\ndufs_active_uploads {}",
+            self.active_uploads.load(Ordering::Relaxed)
+        );
+        output
+    }
+}
+
+pub struct ActiveUpload<'a>(&'a Metrics);
+
+impl Drop for ActiveUpload<'_> {
+    fn drop(&mut self) {
+        self.0.active_uploads.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::default();
+        metrics.record_request(&Method::GET, StatusCode::OK);
+        metrics.record_request(&Method::GET, StatusCode::NO_CONTENT);
+        metrics.record_request(&Method::from_bytes(b"BREW").unwrap(), StatusCode::NOT_FOUND);
+        metrics.record_response_bytes(10);
+        let upload = metrics.start_upload();
+        let output = metrics.render();
+        assert!(output.contains("dufs_requests_total{method=\"GET\",status=\"2xx\"} 2\n"));
+        assert!(output.contains("dufs_requests_total{method=\"OTHER\",status=\"4xx\"} 1\n"));
+        assert!(output.contains("dufs_response_bytes_total 10\n"));
+        assert!(output.contains("dufs_active_uploads 1\n"));
+        drop(upload);
+        assert!(metrics.render().contains("dufs_active_uploads 0\n"));
+    }
+}
@@ -1,14 +1,24 @@
 #![allow(clippy::too_many_arguments)]
 
-use crate::auth::{www_authenticate, AccessPaths, AccessPerm};
+use crate::args::{
+    Compress, DavInfiniteDepth, EtagMode, GroupDirs, HiddenMatcher, StaticBody, StaticResponse,
+};
+use crate::auth::{
+    get_auth_user, verify_share, www_authenticate, AccessPaths, AccessPerm, DirAuth, DIR_AUTH_FILE,
+};
+use crate::dav_props::{self, DeadProp, PropFind, PropName, DAV_NS, DAV_PROPS_FILE};
 use crate::http_utils::{body_full, IncomingStream, LengthLimitedStream};
+use crate::metrics::Metrics;
 use crate::utils::{
-    decode_uri, encode_uri, get_file_mtime_and_mode, get_file_name, glob, parse_range,
-    try_get_file_name,
+    decode_uri, encode_uri, get_file_mtime_and_mode, get_file_name, is_precompressed,
+    is_windows_reserved_name, parse_quality_values, parse_ranges, select_encoding, select_lang,
+    try_get_file_name, unix_now,
 };
 use crate::Args;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use async_compression::tokio::bufread::{BrotliEncoder, GzipEncoder};
+use async_compression::Level;
 use async_zip::{tokio::write::ZipFileWriter, Compression, ZipDateTime, ZipEntryBuilder};
 use base64::{engine::general_purpose::STANDARD, Engine as _};
 use bytes::Bytes;
@@ -17,41 +27,47 @@ use futures_util::{pin_mut, TryStreamExt};
 use headers::{
     AcceptRanges, AccessControlAllowCredentials, AccessControlAllowOrigin, CacheControl,
     ContentLength, ContentType, ETag, HeaderMap, HeaderMapExt, IfMatch, IfModifiedSince,
-    IfNoneMatch, IfRange, IfUnmodifiedSince, LastModified, Range,
+    IfNoneMatch, IfRange, IfUnmodifiedSince, LastModified,
 };
-use http_body_util::{combinators::BoxBody, BodyExt, StreamBody};
+use http_body_util::{combinators::BoxBody, BodyExt, LengthLimitError, Limited, StreamBody};
 use hyper::body::Frame;
 use hyper::{
-    body::Incoming,
     header::{
-        HeaderValue, AUTHORIZATION, CONNECTION, CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_RANGE,
-        CONTENT_TYPE, RANGE,
+        HeaderName, HeaderValue, ACCEPT_ENCODING, ACCEPT_RANGES, ACCESS_CONTROL_ALLOW_HEADERS,
+        ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS,
+        ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD,
+        ALLOW, ALT_SVC, AUTHORIZATION, CACHE_CONTROL, CONNECTION, CONTENT_DISPOSITION,
+        CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, HOST, IF_NONE_MATCH, LINK,
+        LOCATION, ORIGIN, PRAGMA, RANGE, RETRY_AFTER, VARY,
     },
     Method, StatusCode, Uri,
 };
+use regex::{Regex, RegexBuilder};
 use serde::Serialize;
 use sha2::{Digest, Sha256};
 use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fs::Metadata;
 use std::io::SeekFrom;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::path::{Component, Path, PathBuf};
-use std::sync::atomic::{self, AtomicBool};
-use std::sync::Arc;
-use std::time::SystemTime;
+use std::pin::Pin;
+use std::sync::atomic::{self, AtomicBool, AtomicU64, AtomicUsize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::fs::File;
-use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWrite};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt, DuplexStream};
+use tokio::sync::{watch, Semaphore};
 use tokio::{fs, io};
 
 use tokio_util::compat::FuturesAsyncWriteCompatExt;
-use tokio_util::io::{ReaderStream, StreamReader};
+use tokio_util::io::{ReaderStream, StreamReader, SyncIoBridge};
 use uuid::Uuid;
 use walkdir::WalkDir;
 use xml::escape::escape_str_pcdata;
 
-pub type Request = hyper::Request<Incoming>;
+pub type Request = hyper::Request<BoxBody<Bytes, anyhow::Error>>;
 pub type Response = hyper::Response<BoxBody<Bytes, anyhow::Error>>;
 
 const INDEX_HTML: &str = include_str!("../assets/index.html");
@@ -62,7 +78,31 @@ const INDEX_NAME: &str = "index.html";
 const BUF_SIZE: usize = 65536;
 const EDITABLE_TEXT_MAX_SIZE: u64 = 4194304; // 4M
 const RESUMABLE_UPLOAD_MIN_SIZE: u64 = 20971520; // 20M
+const UPLOAD_TEMP_MARKER: &str = ".dufs-upload-";
 const HEALTH_CHECK_PATH: &str = "__dufs__/health";
+const VERSION_PATH: &str = "__dufs__/version";
+const METRICS_PATH: &str = "__dufs__/metrics";
+const UPLOAD_STATUS_PATH: &str = "__dufs__/upload-status/";
+const UPLOAD_ID_HEADER: &str = "x-upload-id";
+const DUFS_DAV_NS: &str = "https://github.com/sigoden/dufs";
+const DAV_SUPPORTED_LOCK: &str = "<D:supportedlock><D:lockentry><D:lockscope><D:exclusive/></D:lockscope><D:locktype><D:write/></D:locktype></D:lockentry></D:supportedlock>";
+const SITEMAP_NAME: &str = "sitemap.xml";
+const SITEMAP_MAX_URLS: usize = 50000;
+const SITEMAP_TTL: Duration = Duration::from_secs(60);
+const SITEMAP_MAX_HOSTS: usize = 16;
+const MAX_BYTERANGES: usize = 64;
+const HTTP_COMPRESS_MIN_SIZE: u64 = 1024;
+const LOCK_MAX_TIMEOUT: u64 = 3600;
+const ETAG_HASH_MAX_SIZE: u64 = 67108864; // 64M
+const ETAG_HASH_CACHE_SIZE: usize = 10000;
+const GREP_MAX_FILE_SIZE: u64 = 1048576; // 1M
+const GREP_MAX_RESULTS: usize = 1000;
+const GREP_SNIPPET_MAX_CHARS: usize = 200;
+const SEARCH_REGEX_SIZE_LIMIT: usize = 1048576; // 1M
+const LIST_PER_PAGE: usize = 5000;
+const LIST_MAX_PER_PAGE: usize = 10000;
+const PROPPATCH_MAX_SIZE: usize = 65536; // 64K
+const PROPFIND_MAX_SIZE: usize = 16384; // 16K
 
 pub struct Server {
     args: Args,
@@ -70,6 +110,16 @@ pub struct Server {
     html: Cow<'static, str>,
     single_file_req_paths: Vec<String>,
     running: Arc<AtomicBool>,
+    ready: AtomicBool,
+    uploads: Mutex<HashMap<UploadKey, UploadProgress>>,
+    upload_slots: Mutex<HashMap<Option<IpAddr>, usize>>,
+    dir_auths: Mutex<HashMap<PathBuf, (SystemTime, Arc<DirAuth>)>>,
+    zip_jobs: ZipJobs,
+    sitemaps: Mutex<HashMap<String, Sitemap>>,
+    locks: Mutex<HashMap<String, LockInfo>>,
+    metrics: Arc<Metrics>,
+    request_slots: Option<Arc<Semaphore>>,
+    etag_hashes: Mutex<HashMap<PathBuf, (SystemTime, u64, String)>>,
 }
 
 impl Server {
@@ -88,6 +138,7 @@ impl Server {
         } else {
             vec![]
         };
+        let request_slots = args.max_connections.map(|v| Arc::new(Semaphore::new(v)));
         let html = match args.assets.as_ref() {
             Some(path) => Cow::Owned(std::fs::read_to_string(path.join("index.html"))?),
             None => Cow::Borrowed(INDEX_HTML),
@@ -98,9 +149,36 @@ impl Server {
             single_file_req_paths,
             assets_prefix,
             html,
+            ready: AtomicBool::new(false),
+            uploads: Default::default(),
+            upload_slots: Default::default(),
+            dir_auths: Default::default(),
+            zip_jobs: Default::default(),
+            sitemaps: Default::default(),
+            locks: Default::default(),
+            metrics: Default::default(),
+            request_slots,
+            etag_hashes: Default::default(),
         })
     }
 
+    /// Probe the serve path until it is accessible, answering 503 in the meantime.
+    pub async fn wait_ready(self: Arc<Self>) {
+        loop {
+            if fs::metadata(&self.args.serve_path).await.is_ok() {
+                self.ready.store(true, atomic::Ordering::SeqCst);
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        }
+    }
+
+    pub fn probe_ready(&self) -> bool {
+        let ready = std::fs::metadata(&self.args.serve_path).is_ok();
+        self.ready.store(ready, atomic::Ordering::SeqCst);
+        ready
+    }
+
     pub async fn call(
         self: Arc<Self>,
         req: Request,
@@ -115,14 +193,54 @@ impl Server {
             .and_then(|v| v.to_str().ok())
             .map(|v| v.starts_with("Microsoft-WebDAV-MiniRedir/"))
             .unwrap_or_default();
+        let prefers_json = prefers_json(req.headers());
+        let method = req.method().clone();
+        let cors_origin = req.headers().get(ORIGIN).cloned();
+        let cors_request_headers = req.headers().get(ACCESS_CONTROL_REQUEST_HEADERS).cloned();
+        let is_preflight = enable_cors
+            && method == Method::OPTIONS
+            && req.headers().contains_key(ACCESS_CONTROL_REQUEST_METHOD);
         let mut http_log_data = self.args.http_logger.data(&req);
         if let Some(addr) = addr {
             http_log_data.insert("remote_addr".to_string(), addr.ip().to_string());
         }
 
-        let mut res = match self.clone().handle(req, is_microsoft_webdav).await {
+        // held until the response body is done, so long downloads keep their slot
+        let permit = self
+            .request_slots
+            .as_ref()
+            .map(|v| v.clone().try_acquire_owned());
+
+        let handled = match permit {
+            Some(Err(_)) => {
+                let mut res = Response::default();
+                *res.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+                res.headers_mut()
+                    .insert(RETRY_AFTER, HeaderValue::from_static("1"));
+                *res.body_mut() = body_full("Service Unavailable");
+                Ok(res)
+            }
+            // preflights carry no credentials, so they are answered before auth
+            _ if is_preflight => {
+                let mut res = Response::default();
+                *res.status_mut() = StatusCode::NO_CONTENT;
+                if let Some(max_age) = self.args.cors_max_age {
+                    res.headers_mut()
+                        .insert(ACCESS_CONTROL_MAX_AGE, HeaderValue::from(max_age));
+                }
+                Ok(res)
+            }
+            _ => self.clone().handle(req, addr, is_microsoft_webdav).await,
+        };
+        let mut res = match handled {
             Ok(res) => {
                 http_log_data.insert("status".to_string(), res.status().as_u16().to_string());
+                if let Some(outcome) = res.extensions().get::<AuthOutcome>() {
+                    if let Some(rule) = &outcome.rule {
+                        http_log_data.insert("auth_rule".to_string(), rule.clone());
+                    }
+                    http_log_data.insert("auth_result".to_string(), outcome.result.to_string());
+                }
                 if !uri.path().starts_with(assets_prefix) {
                     self.args.http_logger.log(&http_log_data, None);
                 }
@@ -130,8 +248,14 @@ impl Server {
             }
             Err(err) => {
                 let mut res = Response::default();
-                let status = StatusCode::INTERNAL_SERVER_ERROR;
+                let status = error_status(&err);
                 *res.status_mut() = status;
+                if let Some(reason) = status
+                    .canonical_reason()
+                    .filter(|_| status != StatusCode::INTERNAL_SERVER_ERROR)
+                {
+                    *res.body_mut() = body_full(reason);
+                }
                 http_log_data.insert("status".to_string(), status.as_u16().to_string());
                 self.args
                     .http_logger
@@ -140,13 +264,57 @@ impl Server {
             }
         };
 
+        if res.status().is_client_error() || res.status().is_server_error() {
+            // error bodies follow `Accept`, see `prefers_json`
+            add_vary(&mut res, "Accept");
+            if prefers_json {
+                json_error_body(&mut res).await;
+            }
+        }
+
         if is_microsoft_webdav {
             // microsoft webdav requires this.
             res.headers_mut()
                 .insert(CONNECTION, HeaderValue::from_static("close"));
         }
+        for (name, value) in &self.args.header {
+            res.headers_mut().insert(name, value.clone());
+        }
         if enable_cors {
-            add_cors(&mut res);
+            add_cors(
+                &mut res,
+                &self.args,
+                cors_origin.as_ref(),
+                cors_request_headers.as_ref(),
+            );
+        }
+        if self.args.http3 {
+            let alt_svc = format!("h3=\":{}\"; ma=86400", self.args.port);
+            if let Ok(value) = HeaderValue::from_str(&alt_svc) {
+                res.headers_mut().insert(ALT_SVC, value);
+            }
+        }
+        if self.args.metrics {
+            self.metrics.record_request(&method, res.status());
+            let metrics = self.metrics.clone();
+            res = res.map(|body| {
+                body.map_frame(move |frame| {
+                    if let Some(data) = frame.data_ref() {
+                        metrics.record_response_bytes(data.len() as u64);
+                    }
+                    frame
+                })
+                .boxed()
+            });
+        }
+        if let Some(Ok(permit)) = permit {
+            res = res.map(|body| {
+                body.map_frame(move |frame| {
+                    let _ = &permit;
+                    frame
+                })
+                .boxed()
+            });
         }
         Ok(res)
     }
@@ -154,14 +322,29 @@ impl Server {
     pub async fn handle(
         self: Arc<Self>,
         req: Request,
+        addr: Option<SocketAddr>,
         is_microsoft_webdav: bool,
     ) -> Result<Response> {
         let mut res = Response::default();
 
+        if !self.ready.load(atomic::Ordering::SeqCst) {
+            *res.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+            res.headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from_static("1"));
+            *res.body_mut() = body_full("Service Unavailable");
+            return Ok(res);
+        }
+
         let req_path = req.uri().path();
         let headers = req.headers();
         let method = req.method().clone();
 
+        // `OPTIONS *` asks about the server as a whole, not about any resource
+        if method == Method::OPTIONS && req_path == "*" {
+            set_webdav_headers(&mut res, &self.args)?;
+            return Ok(res);
+        }
+
         let relative_path = match self.resolve_path(req_path) {
             Some(v) => v,
             None => {
@@ -170,6 +353,10 @@ impl Server {
             }
         };
 
+        if !self.validate_path(&relative_path, &mut res) {
+            return Ok(res);
+        }
+
         if method == Method::GET
             && self
                 .handle_internal(&relative_path, headers, &mut res)
@@ -178,13 +365,83 @@ impl Server {
             return Ok(res);
         }
 
+        if method == Method::GET {
+            if let Some(id) = relative_path.strip_prefix(UPLOAD_STATUS_PATH) {
+                self.handle_upload_status(id, headers, addr, &mut res)
+                    .await?;
+                return Ok(res);
+            }
+        }
+
+        if let Some(static_response) = self
+            .args
+            .static_response
+            .iter()
+            .find(|v| v.path == relative_path)
+        {
+            self.handle_static_response(static_response, &method, &mut res)
+                .await?;
+            return Ok(res);
+        }
+
+        if self.args.sitemap
+            && !self.args.path_is_file
+            && relative_path == SITEMAP_NAME
+            && (method == Method::GET || method == Method::HEAD)
+            && fs::metadata(self.args.serve_path.join(SITEMAP_NAME))
+                .await
+                .is_err()
+        {
+            self.handle_sitemap(headers, method == Method::HEAD, &mut res)
+                .await?;
+            return Ok(res);
+        }
+
+        if let Some(target) = &self.args.index_redirect {
+            if relative_path.is_empty() && (method == Method::GET || method == Method::HEAD) {
+                let mut location = format!("{}{}", self.args.uri_prefix, encode_uri(target));
+                if let Some(query) = req.uri().query() {
+                    location.push('?');
+                    location.push_str(query);
+                }
+                *res.status_mut() = StatusCode::FOUND;
+                res.headers_mut()
+                    .insert(LOCATION, HeaderValue::from_str(&location)?);
+                return Ok(res);
+            }
+        }
+
         let authorization = headers.get(AUTHORIZATION);
-        let guard =
+        let is_shared = self.is_shared(&relative_path, &method, req.uri().query());
+        let is_trusted = self.is_trusted_client(addr);
+        let guard = if is_trusted {
+            (None, Some(AccessPaths::new(AccessPerm::ReadWrite)))
+        } else if is_shared {
+            (None, Some(AccessPaths::new(AccessPerm::ReadOnly)))
+        } else {
             self.args
                 .auth
-                .guard(&relative_path, &method, authorization, is_microsoft_webdav);
+                .guard(&relative_path, &method, authorization, is_microsoft_webdav)
+        };
 
-        let (user, access_paths) = match guard {
+        if self.args.auth.exist() {
+            let (rule, result) = match &guard {
+                _ if is_trusted => (Some("trust-localhost".to_string()), "granted"),
+                _ if is_shared => (Some("share".to_string()), "granted"),
+                (None, None) => (None, "rejected"),
+                (user, access_paths) => (
+                    self.args.auth.matched_rule(&relative_path, user.as_deref()),
+                    if access_paths.is_some() {
+                        "granted"
+                    } else {
+                        "denied"
+                    },
+                ),
+            };
+            res.extensions_mut().insert(AuthOutcome { rule, result });
+        }
+
+        let (mut user, access_paths) = match guard {
             (None, None) => {
                 self.auth_reject(&mut res)?;
                 return Ok(res);
@@ -196,10 +453,51 @@ impl Server {
             (x, Some(y)) => (x, y),
         };
 
+        // the properties sidecar is only reached through PROPFIND and PROPPATCH
+        if !self.args.path_is_file && is_file_name(&relative_path, DAV_PROPS_FILE) {
+            status_not_found(&mut res);
+            return Ok(res);
+        }
+
+        if self.args.dir_auth && !self.args.path_is_file {
+            // the credentials file itself is never served or written
+            if is_file_name(&relative_path, DIR_AUTH_FILE) {
+                status_not_found(&mut res);
+                return Ok(res);
+            }
+            if method != Method::OPTIONS && !is_shared && !is_trusted {
+                if let Some(path) = self.join_path(&relative_path) {
+                    if let Some(dir_auth) = self.find_dir_auth(&path).await? {
+                        match dir_auth.guard(&method, authorization) {
+                            Some(v) => user = Some(v),
+                            None => {
+                                let rule = res
+                                    .extensions_mut()
+                                    .remove::<AuthOutcome>()
+                                    .and_then(|v| v.rule);
+                                res.extensions_mut().insert(AuthOutcome {
+                                    rule,
+                                    result: "rejected",
+                                });
+                                self.auth_reject(&mut res)?;
+                                return Ok(res);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
         let query = req.uri().query().unwrap_or_default();
-        let query_params: HashMap<String, String> = form_urlencoded::parse(query.as_bytes())
+        let mut query_params: HashMap<String, String> = form_urlencoded::parse(query.as_bytes())
             .map(|(k, v)| (k.to_string(), v.to_string()))
             .collect();
+        // `Accept: text/plain` asks for the same listing as `?plain`, `application/json` as `?json`
+        if prefers_plain(headers) {
+            query_params.entry("plain".to_string()).or_default();
+        } else if prefers_json(headers) {
+            query_params.entry("json".to_string()).or_default();
+        }
 
         if method.as_str() == "CHECKAUTH" {
             match user.clone() {
@@ -257,21 +555,54 @@ impl Server {
             return Ok(res);
         }
 
+        if is_dav_write(&method) {
+            if let Some(value) = headers.get("if") {
+                let Some(lists) = value.to_str().ok().and_then(parse_if_header) else {
+                    status_bad_request(&mut res, "Invalid If Header");
+                    return Ok(res);
+                };
+                if !self.if_header_passes(&relative_path, &lists).await {
+                    *res.status_mut() = StatusCode::PRECONDITION_FAILED;
+                    return Ok(res);
+                }
+            }
+        }
+
         match method {
             Method::GET | Method::HEAD => {
                 if is_dir {
                     if render_try_index {
-                        if allow_archive && has_query_flag(&query_params, "zip") {
+                        if has_query_flag(&query_params, "zip") {
                             if !allow_archive {
                                 status_not_found(&mut res);
                                 return Ok(res);
                             }
-                            self.handle_zip_dir(path, head_only, access_paths, &mut res)
-                                .await?;
-                        } else if allow_search && query_params.contains_key("q") {
+                            self.handle_zip_dir(
+                                path,
+                                query,
+                                headers,
+                                head_only,
+                                access_paths,
+                                &mut res,
+                            )
+                            .await?;
+                        } else if let Some(gzip) = tar_query(&query_params) {
+                            if !allow_archive {
+                                status_not_found(&mut res);
+                                return Ok(res);
+                            }
+                            self.handle_tar_dir(path, head_only, gzip, access_paths, &mut res)?;
+                        } else if query_params.contains_key("q")
+                            || query_params.contains_key("grep")
+                        {
+                            if !allow_search {
+                                status_forbid(&mut res);
+                                return Ok(res);
+                            }
                             self.handle_search_dir(
                                 path,
                                 &query_params,
+                                headers,
                                 head_only,
                                 user,
                                 access_paths,
@@ -306,44 +637,90 @@ impl Server {
                             status_not_found(&mut res);
                             return Ok(res);
                         }
-                        self.handle_zip_dir(path, head_only, access_paths, &mut res)
-                            .await?;
-                    } else if allow_search && query_params.contains_key("q") {
+                        self.handle_zip_dir(
+                            path,
+                            query,
+                            headers,
+                            head_only,
+                            access_paths,
+                            &mut res,
+                        )
+                        .await?;
+                    } else if let Some(gzip) = tar_query(&query_params) {
+                        if !allow_archive {
+                            status_not_found(&mut res);
+                            return Ok(res);
+                        }
+                        self.handle_tar_dir(path, head_only, gzip, access_paths, &mut res)?;
+                    } else if query_params.contains_key("q") || query_params.contains_key("grep") {
+                        if !allow_search {
+                            status_forbid(&mut res);
+                            return Ok(res);
+                        }
                         self.handle_search_dir(
                             path,
                             &query_params,
+                            headers,
                             head_only,
                             user,
                             access_paths,
                             &mut res,
                         )
                         .await?;
+                    } else if wants_ndjson(&query_params, headers) {
+                        self.handle_ndjson_dir(path, head_only, access_paths, &mut res)
+                            .await?;
                     } else {
                         self.handle_ls_dir(
                             path,
                             true,
                             &query_params,
+                            headers,
                             head_only,
                             user,
                             access_paths,
                             &mut res,
                         )
                         .await?;
+                        add_vary(&mut res, "Accept");
                     }
                 } else if is_file {
-                    if has_query_flag(&query_params, "edit") {
-                        self.handle_edit_file(path, DataKind::Edit, head_only, user, &mut res)
+                    if wants_source(headers) {
+                        self.handle_send_file(path, headers, head_only, &mut res)
                             .await?;
+                    } else if has_query_flag(&query_params, "edit") {
+                        self.handle_edit_file(
+                            path,
+                            DataKind::Edit,
+                            headers,
+                            head_only,
+                            user,
+                            &mut res,
+                        )
+                        .await?;
                     } else if has_query_flag(&query_params, "view") {
-                        self.handle_edit_file(path, DataKind::View, head_only, user, &mut res)
-                            .await?;
+                        self.handle_edit_file(
+                            path,
+                            DataKind::View,
+                            headers,
+                            head_only,
+                            user,
+                            &mut res,
+                        )
+                        .await?;
                     } else if has_query_flag(&query_params, "hash") {
                         self.handle_hash_file(path, head_only, &mut res).await?;
                     } else {
                         self.handle_send_file(path, headers, head_only, &mut res)
                             .await?;
                     }
-                } else if render_spa {
+                } else if let Some(file) = self
+                    .find_try_file(&relative_path, &method, authorization, is_microsoft_webdav)
+                    .await
+                {
+                    self.handle_send_file(&file, headers, head_only, &mut res)
+                        .await?;
+                } else if render_spa && !wants_source(headers) {
                     self.handle_render_spa(path, headers, head_only, &mut res)
                         .await?;
                 } else if allow_upload && req_path.ends_with('/') {
@@ -351,6 +728,7 @@ impl Server {
                         path,
                         false,
                         &query_params,
+                        headers,
                         head_only,
                         user,
                         access_paths,
@@ -362,13 +740,44 @@ impl Server {
                 }
             }
             Method::OPTIONS => {
-                set_webdav_headers(&mut res);
+                set_webdav_headers(&mut res, &self.args)?;
             }
             Method::PUT => {
-                if is_dir || !allow_upload || (!allow_delete && size > 0) {
+                let content_range = match parse_upload_content_range(headers) {
+                    Ok(v) => v,
+                    Err(err) => {
+                        status_bad_request(&mut res, &err.to_string());
+                        return Ok(res);
+                    }
+                };
+                // resuming only replaces what follows the range start
+                let kept = content_range.map_or(0, |v| v.start);
+                if !allow_upload {
+                    status_forbid(&mut res);
+                } else if is_dir {
+                    // a directory's own size says nothing about overwriting, so it is told first
+                    *res.status_mut() = StatusCode::CONFLICT;
+                    *res.body_mut() = body_full("Target is a directory");
+                } else if !self.upload_preconditions_pass(path, headers).await {
+                    *res.status_mut() = StatusCode::PRECONDITION_FAILED;
+                } else if !allow_delete && size > kept {
                     status_forbid(&mut res);
+                } else if self.is_locked(&relative_path, headers, false) {
+                    status_locked(&mut res);
+                } else if let Some(content_range) = content_range {
+                    if content_range.start > size {
+                        *res.status_mut() = StatusCode::RANGE_NOT_SATISFIABLE;
+                        res.headers_mut()
+                            .insert(CONTENT_RANGE, format!("bytes */{size}").parse()?);
+                    } else {
+                        let owner = (user.clone(), relative_path.clone());
+                        self.handle_resumed_upload(path, content_range, req, addr, owner, &mut res)
+                            .await?;
+                    }
                 } else {
-                    self.handle_upload(path, None, size, req, &mut res).await?;
+                    let owner = (user.clone(), relative_path.clone());
+                    self.handle_upload(path, None, size, req, addr, owner, &mut res)
+                        .await?;
                 }
             }
             Method::PATCH => {
@@ -376,21 +785,51 @@ impl Server {
                     status_not_found(&mut res);
                 } else if !allow_upload {
                     status_forbid(&mut res);
+                } else if is_dir {
+                    *res.status_mut() = StatusCode::CONFLICT;
+                    *res.body_mut() = body_full("Target is a directory");
+                } else if self.is_locked(&relative_path, headers, false) {
+                    status_locked(&mut res);
                 } else {
-                    let offset = match parse_upload_offset(headers, size) {
+                    let range = match parse_update_range(headers, size) {
                         Ok(v) => v,
                         Err(err) => {
                             status_bad_request(&mut res, &err.to_string());
                             return Ok(res);
                         }
                     };
-                    match offset {
-                        Some(offset) => {
-                            if offset < size && !allow_delete {
-                                status_forbid(&mut res);
+                    match range {
+                        Some(range) if range.start > size => {
+                            *res.status_mut() = StatusCode::RANGE_NOT_SATISFIABLE;
+                            res.headers_mut()
+                                .insert(CONTENT_RANGE, format!("bytes */{size}").parse()?);
+                        }
+                        Some(range) if range.start < size && !allow_delete => {
+                            status_forbid(&mut res);
+                        }
+                        Some(range) => {
+                            let content_length = headers.typed_get::<ContentLength>().map(|v| v.0);
+                            match (range.len, content_length) {
+                                (Some(len), Some(content_length)) if len != content_length => {
+                                    status_bad_request(
+                                        &mut res,
+                                        "Content-Length does not match X-Update-Range",
+                                    );
+                                }
+                                _ => {
+                                    let owner = (user.clone(), relative_path.clone());
+                                    self.handle_upload(
+                                        path,
+                                        Some(range),
+                                        size,
+                                        req,
+                                        addr,
+                                        owner,
+                                        &mut res,
+                                    )
+                                    .await?;
+                                }
                             }
-                            self.handle_upload(path, Some(offset), size, req, &mut res)
-                                .await?;
                         }
                         None => {
                             *res.status_mut() = StatusCode::METHOD_NOT_ALLOWED;
@@ -401,10 +840,15 @@ impl Server {
             Method::DELETE => {
                 if !allow_delete {
                     status_forbid(&mut res);
-                } else if !is_miss {
-                    self.handle_delete(path, is_dir, &mut res).await?
-                } else {
+                } else if is_miss {
                     status_not_found(&mut res);
+                } else if self.is_locked(&relative_path, headers, true) {
+                    status_locked(&mut res);
+                } else if is_dir && !self.passes_nested_dir_auths(path, &req, is_trusted).await? {
+                    status_forbid(&mut res);
+                } else {
+                    self.handle_delete(path, is_dir, &mut res).await?;
+                    self.release_locks(&relative_path);
                 }
             }
             method => match method.as_str() {
@@ -417,19 +861,23 @@ impl Server {
                             } else {
                                 access_paths
                             };
-                        self.handle_propfind_dir(path, headers, access_paths, &mut res)
+                        self.handle_propfind_dir(path, req, access_paths, &mut res)
                             .await?;
                     } else if is_file {
-                        self.handle_propfind_file(path, &mut res).await?;
+                        self.handle_propfind_file(path, req, &mut res).await?;
                     } else {
                         status_not_found(&mut res);
                     }
                 }
                 "PROPPATCH" => {
-                    if is_file {
-                        self.handle_proppatch(req_path, &mut res).await?;
-                    } else {
+                    if !allow_upload {
+                        status_forbid(&mut res);
+                    } else if is_miss {
                         status_not_found(&mut res);
+                    } else if self.is_locked(&relative_path, headers, false) {
+                        status_locked(&mut res);
+                    } else {
+                        self.handle_proppatch(path, req, &mut res).await?;
                     }
                 }
                 "MKCOL" => {
@@ -439,7 +887,7 @@ impl Server {
                         *res.status_mut() = StatusCode::METHOD_NOT_ALLOWED;
                         *res.body_mut() = body_full("Already exists");
                     } else {
-                        self.handle_mkcol(path, &mut res).await?;
+                        self.handle_mkcol(path, req, &mut res).await?;
                     }
                 }
                 "COPY" => {
@@ -448,7 +896,7 @@ impl Server {
                     } else if is_miss {
                         status_not_found(&mut res);
                     } else {
-                        self.handle_copy(path, &req, &mut res).await?
+                        self.handle_copy(path, &req, is_trusted, &mut res).await?
                     }
                 }
                 "MOVE" => {
@@ -456,27 +904,37 @@ impl Server {
                         status_forbid(&mut res);
                     } else if is_miss {
                         status_not_found(&mut res);
+                    } else if self.is_locked(&relative_path, headers, true) {
+                        status_locked(&mut res);
                     } else {
-                        self.handle_move(path, &req, &mut res).await?
+                        self.handle_move(path, &req, is_trusted, &mut res).await?;
+                        if res.status().is_success() {
+                            self.release_locks(&relative_path);
+                        }
                     }
                 }
                 "LOCK" => {
-                    // Fake lock
-                    if is_file {
-                        let has_auth = authorization.is_some();
-                        self.handle_lock(req_path, has_auth, &mut res).await?;
+                    if !allow_upload {
+                        status_forbid(&mut res);
+                    } else if is_file {
+                        self.handle_lock(&relative_path, req_path, headers, &mut res)?;
                     } else {
                         status_not_found(&mut res);
                     }
                 }
                 "UNLOCK" => {
-                    // Fake unlock
-                    if is_miss {
+                    if !allow_upload {
+                        status_forbid(&mut res);
+                    } else if is_miss {
                         status_not_found(&mut res);
+                    } else {
+                        self.handle_unlock(&relative_path, headers, &mut res);
                     }
                 }
                 _ => {
                     *res.status_mut() = StatusCode::METHOD_NOT_ALLOWED;
+                    res.headers_mut()
+                        .insert(ALLOW, HeaderValue::from_str(&allowed_methods(&self.args))?);
                 }
             },
         }
@@ -486,41 +944,208 @@ impl Server {
     async fn handle_upload(
         &self,
         path: &Path,
-        upload_offset: Option<u64>,
+        update_range: Option<UpdateRange>,
         size: u64,
         req: Request,
+        addr: Option<SocketAddr>,
+        owner: (Option<String>, String),
         res: &mut Response,
     ) -> Result<()> {
-        ensure_path_parent(path).await?;
-        let (mut file, status) = match upload_offset {
-            None => (fs::File::create(path).await?, StatusCode::CREATED),
+        if fs::metadata(path).await.is_ok_and(|v| v.is_dir()) {
+            *res.status_mut() = StatusCode::CONFLICT;
+            *res.body_mut() = body_full("Target is a directory");
+            return Ok(());
+        }
+        // held until the body has been copied
+        let _slot = match self.args.max_concurrent_uploads {
+            Some(max) => match UploadSlot::acquire(&self.upload_slots, addr, max) {
+                Some(v) => Some(v),
+                None => {
+                    *res.status_mut() = StatusCode::TOO_MANY_REQUESTS;
+                    res.headers_mut()
+                        .insert(RETRY_AFTER, HeaderValue::from_static("1"));
+                    *res.body_mut() = body_full("Too Many Uploads");
+                    return Ok(());
+                }
+            },
+            None => None,
+        };
+        let tracker = match req
+            .headers()
+            .get(UPLOAD_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .filter(|v| !v.is_empty() && v.len() <= 128)
+        {
+            Some(id) => {
+                let total = req.headers().typed_get::<ContentLength>().map(|v| v.0);
+                match UploadTracker::new(&self.uploads, owner, id, total) {
+                    Some(v) => Some(v),
+                    None => {
+                        *res.status_mut() = StatusCode::CONFLICT;
+                        *res.body_mut() = body_full("Upload id already in use");
+                        return Ok(());
+                    }
+                }
+            }
+            None => None,
+        };
+        let _upload = self.metrics.start_upload();
+        let mut digest = match update_range {
+            None => match UploadDigest::from_headers(req.headers()) {
+                Ok(v) => v,
+                Err(err) => {
+                    status_bad_request(res, &err.to_string());
+                    return Ok(());
+                }
+            },
+            Some(_) => None,
+        };
+        let max_size = self.args.max_upload_size;
+        if let Some(max_size) = max_size {
+            // in-place writes are checked before any byte lands, so the cap bounds the whole file
+            let offset = update_range.map_or(0, |v| v.start);
+            let incoming = update_range
+                .and_then(|v| v.len)
+                .or(req.headers().typed_get::<ContentLength>().map(|v| v.0));
+            match incoming {
+                Some(len) if offset.saturating_add(len) > max_size => {
+                    status_payload_too_large(res);
+                    return Ok(());
+                }
+                None if update_range.is_some() => {
+                    *res.status_mut() = StatusCode::LENGTH_REQUIRED;
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+        ensure_path_parent(path, self.args.dir_mode).await?;
+        let existed = fs::symlink_metadata(path).await.is_ok();
+        let verified = digest.is_some();
+        // a whole-file upload lands in a sibling first, so a rejected body never touches the target
+        let claimed = update_range.is_none()
+            && req
+                .headers()
+                .get(IF_NONE_MATCH)
+                .is_some_and(|v| v.as_bytes() == b"*");
+        let (mut file, temp_path, status) = match update_range.map(|v| v.start) {
+            None => {
+                // a create-only PUT takes the name up front, so of two racing ones only one wins
+                if claimed {
+                    match fs::OpenOptions::new()
+                        .write(true)
+                        .create_new(true)
+                        .open(path)
+                        .await
+                    {
+                        Ok(_) => {}
+                        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                            *res.status_mut() = StatusCode::PRECONDITION_FAILED;
+                            return Ok(());
+                        }
+                        Err(err) => return Err(err.into()),
+                    }
+                }
+                let temp_path = upload_temp_path(path);
+                let file = fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(&temp_path)
+                    .await?;
+                (file, Some(temp_path), StatusCode::CREATED)
+            }
+            // only cut once every check has passed, a rejected chunk keeps the file as it was
+            Some(offset) if update_range.is_some_and(|v| v.truncate) => {
+                let mut file = fs::OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .truncate(false)
+                    .open(path)
+                    .await?;
+                file.set_len(offset).await?;
+                file.seek(SeekFrom::Start(offset)).await?;
+                set_mode(path, self.args.file_mode).await?;
+                (file, None, StatusCode::NO_CONTENT)
+            }
             Some(offset) if offset == size => (
                 fs::OpenOptions::new().append(true).open(path).await?,
+                None,
                 StatusCode::NO_CONTENT,
             ),
             Some(offset) => {
                 let mut file = fs::OpenOptions::new().write(true).open(path).await?;
                 file.seek(SeekFrom::Start(offset)).await?;
-                (file, StatusCode::NO_CONTENT)
+                (file, None, StatusCode::NO_CONTENT)
             }
         };
-        let stream = IncomingStream::new(req.into_body());
+        let ret = {
+            let stream = IncomingStream::new(req.into_body()).inspect_ok(|chunk| {
+                if let Some(tracker) = &tracker {
+                    tracker.advance(chunk.len() as u64);
+                }
+                if let Some(digest) = digest.as_mut() {
+                    digest.update(chunk);
+                }
+            });
 
-        let body_with_io_error = stream.map_err(|err| io::Error::new(io::ErrorKind::Other, err));
-        let body_reader = StreamReader::new(body_with_io_error);
+            // tells a malformed or truncated body apart from failing to write the file
+            let body_with_io_error =
+                stream.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err));
+            let body_reader = StreamReader::new(body_with_io_error);
 
-        pin_mut!(body_reader);
+            // a bounded range never writes past its last byte, and one byte past the cap
+            // is enough to tell an oversized body
+            let limit = update_range.and_then(|v| v.len).unwrap_or(u64::MAX);
+            let limit = limit.min(max_size.map_or(u64::MAX, |v| v.saturating_add(1)));
+            let body_reader = body_reader.take(limit);
 
-        let ret = io::copy(&mut body_reader, &mut file).await;
-        let size = fs::metadata(path)
-            .await
-            .map(|v| v.len())
-            .unwrap_or_default();
-        if ret.is_err() {
-            if upload_offset.is_none() && size < RESUMABLE_UPLOAD_MIN_SIZE {
-                let _ = tokio::fs::remove_file(&path).await;
+            pin_mut!(body_reader);
+
+            io::copy(&mut body_reader, &mut file).await
+        };
+        drop(file);
+        if matches!((&ret, max_size), (Ok(len), Some(max_size)) if *len > max_size) {
+            if let Some(temp_path) = &temp_path {
+                discard_upload(temp_path, claimed.then_some(path)).await;
+            }
+            status_payload_too_large(res);
+            return Ok(());
+        }
+        if let Some(mismatch) = digest.filter(|_| ret.is_ok()).and_then(|v| v.mismatch()) {
+            if let Some(temp_path) = &temp_path {
+                discard_upload(temp_path, claimed.then_some(path)).await;
+            }
+            status_bad_request(res, &mismatch);
+            return Ok(());
+        }
+        if let Err(err) = ret {
+            if let Some(temp_path) = &temp_path {
+                let size = fs::metadata(temp_path)
+                    .await
+                    .map(|v| v.len())
+                    .unwrap_or_default();
+                // a large enough part of a new file is kept for the client to resume, but never
+                // one that replaces an existing file or that was meant to match a digest
+                if size < RESUMABLE_UPLOAD_MIN_SIZE
+                    || existed
+                    || verified
+                    || self.finish_upload_file(temp_path, path).await.is_err()
+                {
+                    discard_upload(temp_path, claimed.then_some(path)).await;
+                }
+            }
+            if err.kind() == io::ErrorKind::InvalidData {
+                status_bad_request(res, "Invalid Request Body");
+                return Ok(());
+            }
+            return Err(err.into());
+        }
+
+        if let Some(temp_path) = &temp_path {
+            if let Err(err) = self.finish_upload_file(temp_path, path).await {
+                discard_upload(temp_path, claimed.then_some(path)).await;
+                return Err(err);
             }
-            ret?;
         }
 
         *res.status_mut() = status;
@@ -528,41 +1153,128 @@ impl Server {
         Ok(())
     }
 
-    async fn handle_delete(&self, path: &Path, is_dir: bool, res: &mut Response) -> Result<()> {
-        match is_dir {
-            true => fs::remove_dir_all(path).await?,
-            false => fs::remove_file(path).await?,
+    /// Moves a completed upload over `path`, keeping the permissions of the file it replaces
+    /// unless `--file-mode` sets them.
+    async fn finish_upload_file(&self, temp_path: &Path, path: &Path) -> Result<()> {
+        match (self.args.file_mode, fs::metadata(path).await) {
+            (None, Ok(meta)) => fs::set_permissions(temp_path, meta.permissions()).await?,
+            (mode, _) => set_mode(temp_path, mode).await?,
         }
-
-        status_no_content(res);
+        fs::rename(temp_path, path).await?;
         Ok(())
     }
 
-    async fn handle_ls_dir(
+    /// `If-None-Match: *` makes a PUT create-only, `If-Match` pins the version it replaces.
+    async fn upload_preconditions_pass(
         &self,
         path: &Path,
-        exist: bool,
-        query_params: &HashMap<String, String>,
-        head_only: bool,
-        user: Option<String>,
-        access_paths: AccessPaths,
+        headers: &HeaderMap<HeaderValue>,
+    ) -> bool {
+        let etag = match fs::metadata(path).await {
+            Ok(meta) => self.cache_headers(path, &meta, None).await.map(|v| v.0),
+            Err(_) => None,
+        };
+        if let (Some(if_none_match), Some(etag)) = (headers.typed_get::<IfNoneMatch>(), &etag) {
+            if !if_none_match.precondition_passes(etag) {
+                return false;
+            }
+        }
+        match headers.typed_get::<IfMatch>() {
+            Some(if_match) => etag.is_some_and(|etag| if_match.precondition_passes(&etag)),
+            None => true,
+        }
+    }
+
+    /// Writes one `Content-Range` chunk of a PUT, dropping whatever followed its start.
+    ///
+    /// Answers 201 once the chunk completes the file and 204 while more is to come.
+    async fn handle_resumed_upload(
+        &self,
+        path: &Path,
+        content_range: UploadContentRange,
+        req: Request,
+        addr: Option<SocketAddr>,
+        owner: (Option<String>, String),
         res: &mut Response,
     ) -> Result<()> {
-        let mut paths = vec![];
-        if exist {
-            paths = match self.list_dir(path, path, access_paths.clone()).await {
-                Ok(paths) => paths,
-                Err(_) => {
-                    status_forbid(res);
-                    return Ok(());
-                }
-            }
-        };
+        let len = content_range.end - content_range.start + 1;
+        if req
+            .headers()
+            .typed_get::<ContentLength>()
+            .is_some_and(|v| v.0 != len)
+        {
+            status_bad_request(res, "Content-Length does not match Content-Range");
+            return Ok(());
+        }
+        if let Some(max_size) = self.args.max_upload_size {
+            if content_range.end >= max_size || content_range.total.is_some_and(|v| v > max_size) {
+                status_payload_too_large(res);
+                return Ok(());
+            }
+        }
+        let range = UpdateRange {
+            start: content_range.start,
+            len: Some(len),
+            truncate: true,
+        };
+        self.handle_upload(
+            path,
+            Some(range),
+            content_range.start,
+            req,
+            addr,
+            owner,
+            res,
+        )
+        .await?;
+        if res.status() == StatusCode::NO_CONTENT
+            && content_range.total == Some(content_range.end + 1)
+        {
+            *res.status_mut() = StatusCode::CREATED;
+        }
+        Ok(())
+    }
+
+    async fn handle_delete(&self, path: &Path, is_dir: bool, res: &mut Response) -> Result<()> {
+        match is_dir {
+            true => fs::remove_dir_all(path).await?,
+            false => fs::remove_file(path).await?,
+        }
+        dav_props::transfer(path, None, false).await?;
+
+        status_no_content(res);
+        Ok(())
+    }
+
+    async fn handle_ls_dir(
+        &self,
+        path: &Path,
+        exist: bool,
+        query_params: &HashMap<String, String>,
+        headers: &HeaderMap<HeaderValue>,
+        head_only: bool,
+        user: Option<String>,
+        access_paths: AccessPaths,
+        res: &mut Response,
+    ) -> Result<()> {
+        let mut paths = vec![];
+        let mut truncated = false;
+        if exist {
+            (paths, truncated) = match self.list_dir(path, path, access_paths.clone()).await {
+                Ok(v) => v,
+                Err(_) => {
+                    status_forbid(res);
+                    return Ok(());
+                }
+            }
+        };
         self.send_index(
             path,
             paths,
             exist,
+            truncated,
             query_params,
+            headers,
             head_only,
             user,
             access_paths,
@@ -570,32 +1282,123 @@ impl Server {
         )
     }
 
+    /// Stream the entries of `path` as one JSON object per line, in the order they are read.
+    async fn handle_ndjson_dir(
+        self: &Arc<Self>,
+        path: &Path,
+        head_only: bool,
+        access_paths: AccessPaths,
+        res: &mut Response,
+    ) -> Result<()> {
+        let (names, rd) = if access_paths.perm().indexonly() {
+            let names = access_paths.child_names().into_iter().cloned().collect();
+            (names, None)
+        } else {
+            match fs::read_dir(path).await {
+                Ok(rd) => (VecDeque::new(), Some(rd)),
+                Err(_) => {
+                    status_forbid(res);
+                    return Ok(());
+                }
+            }
+        };
+        res.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/x-ndjson"),
+        );
+        res.headers_mut()
+            .typed_insert(CacheControl::new().with_no_cache());
+        add_vary(res, "Accept");
+        if head_only {
+            return Ok(());
+        }
+        let state = (self.clone(), path.to_path_buf(), names, rd);
+        let stream = futures_util::stream::unfold(state, |mut state| async move {
+            let (server, dir, names, rd) = &mut state;
+            loop {
+                let entry_path = match names.pop_front() {
+                    Some(name) => dir.join(name),
+                    None => match rd.as_mut()?.next_entry().await {
+                        Ok(Some(entry)) => entry.path(),
+                        _ => return None,
+                    },
+                };
+                let Some(item) = server.listable_pathitem(dir, &entry_path).await else {
+                    continue;
+                };
+                let line = serde_json::to_string(&item).map(|v| Bytes::from(v + "\n"));
+                return Some((line.map(Frame::data).map_err(Into::into), state));
+            }
+        });
+        *res.body_mut() = StreamBody::new(stream).boxed();
+        Ok(())
+    }
+
     async fn handle_search_dir(
         &self,
         path: &Path,
         query_params: &HashMap<String, String>,
+        headers: &HeaderMap<HeaderValue>,
         head_only: bool,
         user: Option<String>,
         access_paths: AccessPaths,
         res: &mut Response,
     ) -> Result<()> {
         let mut paths: Vec<PathItem> = vec![];
-        let search = query_params
-            .get("q")
-            .ok_or_else(|| anyhow!("invalid q"))?
-            .to_lowercase();
-        if search.is_empty() {
+        let truncated;
+        let is_on = |name: &str| {
+            matches!(
+                query_params.get(name).map(|v| v.as_str()),
+                Some("" | "1" | "true")
+            )
+        };
+        let (regex, case_sensitive) = (is_on("regex"), is_on("case"));
+        let build = |name: &str| {
+            let value = query_params.get(name).filter(|v| !v.is_empty())?;
+            let pattern = if regex {
+                Cow::from(value)
+            } else {
+                Cow::from(regex::escape(value))
+            };
+            Some(
+                RegexBuilder::new(&pattern)
+                    .case_insensitive(!case_sensitive)
+                    .size_limit(SEARCH_REGEX_SIZE_LIMIT)
+                    .build(),
+            )
+        };
+        let (search, grep) = match (build("q").transpose(), build("grep").transpose()) {
+            (Ok(search), Ok(grep)) => (search, grep),
+            (Err(err), _) | (_, Err(err)) => {
+                status_bad_request(res, &err.to_string());
+                return Ok(());
+            }
+        };
+        if search.is_none() && grep.is_none() {
             return self
-                .handle_ls_dir(path, true, query_params, head_only, user, access_paths, res)
+                .handle_ls_dir(
+                    path,
+                    true,
+                    query_params,
+                    headers,
+                    head_only,
+                    user,
+                    access_paths,
+                    res,
+                )
                 .await;
         } else {
             let path_buf = path.to_path_buf();
-            let hidden = Arc::new(self.args.hidden.to_vec());
-            let hidden = hidden.clone();
+            let hidden = self.args.hidden_matcher.clone();
+            let dir_auth = self.args.dir_auth;
             let running = self.running.clone();
             let access_paths = access_paths.clone();
-            let search_paths = tokio::task::spawn_blocking(move || {
-                let mut paths: Vec<PathBuf> = vec![];
+            let mut max_entries = self.args.max_list_entries.unwrap_or(usize::MAX);
+            if grep.is_some() {
+                max_entries = max_entries.min(GREP_MAX_RESULTS);
+            }
+            let (search_paths, search_truncated) = tokio::task::spawn_blocking(move || {
+                let mut paths: Vec<(PathBuf, Option<String>)> = vec![];
                 for dir in access_paths.entry_paths(&path_buf) {
                     let mut it = WalkDir::new(&dir).into_iter();
                     it.next();
@@ -606,23 +1409,41 @@ impl Server {
                         let entry_path = entry.path();
                         let base_name = get_file_name(entry_path);
                         let is_dir = entry.file_type().is_dir();
-                        if is_hidden(&hidden, base_name, is_dir) {
+                        if is_hidden(&hidden, entry_path, is_dir)
+                            || (cfg!(windows) && is_windows_reserved_name(base_name))
+                            || (dir_auth && is_dir && is_dir_auth_protected(entry_path))
+                        {
                             if is_dir {
                                 it.skip_current_dir();
                             }
                             continue;
                         }
-                        if !base_name.to_lowercase().contains(&search) {
+                        if search.as_ref().is_some_and(|v| !v.is_match(base_name)) {
                             continue;
                         }
-                        paths.push(entry_path.to_path_buf());
+                        // symlinks are never read, so their targets can't leak out of the root
+                        let snippet = match &grep {
+                            Some(_) if is_dir || entry.path_is_symlink() => continue,
+                            Some(grep) => match grep_file(entry_path, grep) {
+                                Some(v) => Some(v),
+                                None => continue,
+                            },
+                            None => None,
+                        };
+                        if paths.len() >= max_entries {
+                            return (paths, true);
+                        }
+                        paths.push((entry_path.to_path_buf(), snippet));
                     }
                 }
-                paths
+                (paths, false)
             })
             .await?;
-            for search_path in search_paths.into_iter() {
-                if let Ok(Some(item)) = self.to_pathitem(search_path, path.to_path_buf()).await {
+            truncated = search_truncated;
+            for (search_path, snippet) in search_paths.into_iter() {
+                if let Ok(Some(mut item)) = self.to_pathitem(search_path, path.to_path_buf()).await
+                {
+                    item.snippet = snippet;
                     paths.push(item);
                 }
             }
@@ -631,7 +1452,9 @@ impl Server {
             path,
             paths,
             true,
+            truncated,
             query_params,
+            headers,
             head_only,
             user,
             access_paths,
@@ -639,50 +1462,420 @@ impl Server {
         )
     }
 
+    /// Zip the directory, or only the entries named by repeated `path` params.
     async fn handle_zip_dir(
         &self,
         path: &Path,
+        query: &str,
+        headers: &HeaderMap<HeaderValue>,
         head_only: bool,
         access_paths: AccessPaths,
         res: &mut Response,
     ) -> Result<()> {
-        let (mut writer, reader) = tokio::io::duplex(BUF_SIZE);
         let filename = try_get_file_name(path)?;
+        let Some(selection) = parse_zip_selection(query) else {
+            status_bad_request(res, "Invalid path");
+            return Ok(());
+        };
+        // identical requests against an unchanged directory share one archive, and one kept
+        // around by --zip-cache must see a change anywhere below it, not just in the directory
+        let mtime = match self.args.zip_cache {
+            Some(_) => tree_mtime(path).await?,
+            None => fs::metadata(path).await?.modified().ok(),
+        };
+        let key = format!(
+            "{}|{:?}|{:?}|{:?}",
+            path.display(),
+            access_paths,
+            mtime,
+            selection
+        );
+        if let Some(ttl) = self.args.zip_cache {
+            let ttl = Duration::from_secs(ttl);
+            self.handle_cached_zip_dir(
+                path,
+                &selection,
+                &key,
+                headers,
+                head_only,
+                access_paths,
+                ttl,
+                res,
+            )
+            .await?;
+        } else if !head_only {
+            self.handle_stream_zip_dir(path, &selection, &key, access_paths, res)?;
+        }
         set_content_disposition(res, false, &format!("{}.zip", filename))?;
         res.headers_mut()
             .insert("content-type", HeaderValue::from_static("application/zip"));
+        Ok(())
+    }
+
+    /// Stream a tar, or with `gzip` a tar.gz, of the directory while it is being built.
+    fn handle_tar_dir(
+        &self,
+        path: &Path,
+        head_only: bool,
+        gzip: bool,
+        access_paths: AccessPaths,
+        res: &mut Response,
+    ) -> Result<()> {
+        let filename = try_get_file_name(path)?;
+        let (ext, content_type) = match gzip {
+            true => ("tar.gz", "application/gzip"),
+            false => ("tar", "application/x-tar"),
+        };
+        set_content_disposition(res, false, &format!("{filename}.{ext}"))?;
+        res.headers_mut()
+            .insert("content-type", HeaderValue::from_static(content_type));
         if head_only {
             return Ok(());
         }
+
+        let (writer, reader) = tokio::io::duplex(BUF_SIZE);
+        let writer = SyncIoBridge::new(writer);
         let path = path.to_owned();
-        let hidden = self.args.hidden.clone();
+        let hidden = self.args.hidden_matcher.clone();
+        let dir_auth = self.args.dir_auth;
         let running = self.running.clone();
-        let compression = self.args.compress.to_compression();
-        tokio::spawn(async move {
-            if let Err(e) = zip_dir(
-                &mut writer,
-                &path,
-                access_paths,
-                &hidden,
-                compression,
-                running,
-            )
-            .await
-            {
-                error!("Failed to zip {}, {}", path.display(), e);
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = tar_dir(writer, &path, access_paths, &hidden, dir_auth, running) {
+                error!("Failed to tar {}, {}", path.display(), e);
             }
         });
-        let reader_stream = ReaderStream::with_capacity(reader, BUF_SIZE);
+
+        let chunk_size = self.stream_chunk_size();
+        let reader: Pin<Box<dyn io::AsyncRead + Send + Sync>> = match gzip {
+            true => Box::pin(GzipEncoder::new(io::BufReader::with_capacity(
+                chunk_size, reader,
+            ))),
+            false => Box::pin(reader),
+        };
+        let reader_stream = ReaderStream::with_capacity(reader, chunk_size);
         let stream_body = StreamBody::new(
             reader_stream
                 .map_ok(Frame::data)
                 .map_err(|err| anyhow!("{err}")),
         );
-        let boxed_body = stream_body.boxed();
-        *res.body_mut() = boxed_body;
+        *res.body_mut() = stream_body.boxed();
         Ok(())
     }
 
+    /// Stream the archive while it is being built, joining a build already under way.
+    fn handle_stream_zip_dir(
+        &self,
+        path: &Path,
+        selection: &[PathBuf],
+        key: &str,
+        access_paths: AccessPaths,
+        res: &mut Response,
+    ) -> Result<()> {
+        let reader = {
+            let mut zip_jobs = self.zip_jobs.lock().unwrap();
+            let job = match zip_jobs.get(key) {
+                Some(job) => job.clone(),
+                None => {
+                    let job = self.spawn_zip_job(key, path, selection, access_paths)?;
+                    zip_jobs.insert(key.to_string(), job.clone());
+                    job
+                }
+            };
+            ZipJobReader::attach(job)
+        };
+        let stream = futures_util::stream::unfold(reader, |mut reader| async move {
+            let chunk = reader.next_chunk().await.transpose()?;
+            Some((chunk.map(Frame::data), reader))
+        });
+        *res.body_mut() = StreamBody::new(stream).boxed();
+        Ok(())
+    }
+
+    /// Wait for the archive to be fully built, then send it as a file so ranges and resumes work.
+    ///
+    /// Finished archives are reused for `ttl`, a HEAD only reports on one that is already built.
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_cached_zip_dir(
+        &self,
+        path: &Path,
+        selection: &[PathBuf],
+        key: &str,
+        headers: &HeaderMap<HeaderValue>,
+        head_only: bool,
+        access_paths: AccessPaths,
+        ttl: Duration,
+        res: &mut Response,
+    ) -> Result<()> {
+        let job = {
+            let mut zip_jobs = self.zip_jobs.lock().unwrap();
+            match zip_jobs.get(key) {
+                Some(job) if !job.is_expired(ttl) => Some(job.clone()),
+                _ if head_only => None,
+                _ => {
+                    let job = self.spawn_zip_job(key, path, selection, access_paths)?;
+                    zip_jobs.insert(key.to_string(), job.clone());
+                    Some(job)
+                }
+            }
+        };
+        let Some(job) = job else {
+            return Ok(());
+        };
+        let mut progress = job.progress.clone();
+        let state = match head_only {
+            true => progress.borrow().state,
+            false => match progress.wait_for(|v| v.state != ZipState::Running).await {
+                Ok(v) => v.state,
+                Err(_) => bail!("Zip job went away"),
+            },
+        };
+        match state {
+            ZipState::Done => {
+                self.handle_send_file(&job.spool, headers, head_only, res)
+                    .await
+            }
+            ZipState::Failed => bail!("Failed to build zip"),
+            ZipState::Running => Ok(()),
+        }
+    }
+
+    /// Start building the archive of `path` into a spool file that any number of readers can follow.
+    fn spawn_zip_job(
+        &self,
+        key: &str,
+        path: &Path,
+        selection: &[PathBuf],
+        access_paths: AccessPaths,
+    ) -> Result<Arc<ZipJob>> {
+        let spool = std::env::temp_dir().join(format!(
+            "dufs-zip-{}-{}.zip",
+            std::process::id(),
+            ZIP_SPOOL_ID.fetch_add(1, atomic::Ordering::SeqCst)
+        ));
+        let file = std::fs::File::create(&spool)
+            .with_context(|| format!("Failed to create {}", spool.display()))?;
+        let (progress_tx, progress) = watch::channel(ZipProgress::default());
+        let job = Arc::new(ZipJob {
+            spool,
+            progress,
+            readers: Default::default(),
+        });
+
+        let job_clone = job.clone();
+        let zip_jobs = self.zip_jobs.clone();
+        let key = key.to_string();
+        let path = path.to_owned();
+        let selection = selection.to_vec();
+        let hidden = self.args.hidden_matcher.clone();
+        let dir_auth = self.args.dir_auth;
+        let running = self.running.clone();
+        let compression = self.args.compress.to_compression();
+        let zip_cache = self.args.zip_cache.map(Duration::from_secs);
+        tokio::spawn(async move {
+            let job = job_clone;
+            let (mut writer, reader) = tokio::io::duplex(BUF_SIZE);
+            let zip = async {
+                let ret = zip_dir(
+                    &mut writer,
+                    &path,
+                    &selection,
+                    access_paths,
+                    &hidden,
+                    dir_auth,
+                    compression,
+                    running,
+                )
+                .await;
+                drop(writer);
+                ret
+            };
+            let spool = spool_zip(
+                reader,
+                fs::File::from_std(file),
+                &progress_tx,
+                &job,
+                &zip_jobs,
+                &key,
+                zip_cache.is_none(),
+            );
+            let (zip_ret, spool_ret) = tokio::join!(zip, spool);
+            let mut state = ZipState::Done;
+            if let Err(e) = zip_ret {
+                error!("Failed to zip {}, {}", path.display(), e);
+                state = ZipState::Failed;
+            }
+            match spool_ret {
+                Ok(true) => {}
+                Ok(false) => state = ZipState::Failed,
+                Err(e) => {
+                    error!("Failed to spool zip of {}, {}", path.display(), e);
+                    state = ZipState::Failed;
+                }
+            }
+            match zip_cache {
+                Some(ttl) if state == ZipState::Done => {
+                    let job = Arc::downgrade(&job);
+                    tokio::spawn(async move {
+                        tokio::time::sleep(ttl).await;
+                        if let Some(job) = job.upgrade() {
+                            remove_zip_job(&mut zip_jobs.lock().unwrap(), &key, &job);
+                        }
+                    });
+                }
+                _ => remove_zip_job(&mut zip_jobs.lock().unwrap(), &key, &job),
+            }
+            progress_tx.send_modify(|v| {
+                v.state = state;
+                v.finished_at = Some(Instant::now());
+            });
+        });
+        Ok(job)
+    }
+
+    /// Generate `/sitemap.xml` from the files anonymous users can read, reused for a short while.
+    async fn handle_sitemap(
+        &self,
+        headers: &HeaderMap<HeaderValue>,
+        head_only: bool,
+        res: &mut Response,
+    ) -> Result<()> {
+        let Some(access_paths) = self.args.auth.guard("", &Method::GET, None, false).1 else {
+            status_not_found(res);
+            return Ok(());
+        };
+        let Some(host) = headers.get(HOST).and_then(|v| v.to_str().ok()) else {
+            status_bad_request(res, "Missing Host");
+            return Ok(());
+        };
+        let scheme = if self.args.tls_cert.is_some() {
+            "https"
+        } else {
+            "http"
+        };
+        let base_url = format!("{scheme}://{host}{}", self.args.uri_prefix);
+        // the root mtime only catches changes to its own entries, hence the ttl
+        let mtime = fs::metadata(&self.args.serve_path).await?.modified().ok();
+        let cached = self
+            .sitemaps
+            .lock()
+            .unwrap()
+            .get(&base_url)
+            .filter(|v| v.mtime == mtime && v.created.elapsed() < SITEMAP_TTL)
+            .cloned();
+        let sitemap = match cached {
+            Some(v) => v,
+            None => {
+                let body = self.build_sitemap(&base_url, access_paths).await?;
+                let sitemap = Sitemap {
+                    mtime,
+                    created: Instant::now(),
+                    generated: SystemTime::now(),
+                    etag: format!(r#""{:x}""#, md5::compute(&body)).parse()?,
+                    body: body.into(),
+                };
+                let mut sitemaps = self.sitemaps.lock().unwrap();
+                // every Host gets its own entry, so stale ones go first and then the oldest
+                sitemaps.retain(|_, v| v.created.elapsed() < SITEMAP_TTL);
+                if sitemaps.len() >= SITEMAP_MAX_HOSTS {
+                    let oldest = sitemaps
+                        .iter()
+                        .min_by_key(|(_, v)| v.created)
+                        .map(|(k, _)| k.clone());
+                    if let Some(oldest) = oldest {
+                        sitemaps.remove(&oldest);
+                    }
+                }
+                sitemaps.insert(base_url, sitemap.clone());
+                sitemap
+            }
+        };
+        let last_modified = LastModified::from(sitemap.generated);
+        res.headers_mut().typed_insert(sitemap.etag.clone());
+        res.headers_mut().typed_insert(last_modified);
+        if is_not_modified(headers, &sitemap.etag, last_modified) {
+            *res.status_mut() = StatusCode::NOT_MODIFIED;
+            return Ok(());
+        }
+        res.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/xml; charset=utf-8"),
+        );
+        res.headers_mut()
+            .typed_insert(ContentLength(sitemap.body.len() as u64));
+        if !head_only {
+            *res.body_mut() = body_full(sitemap.body);
+        }
+        Ok(())
+    }
+
+    async fn build_sitemap(&self, base_url: &str, access_paths: AccessPaths) -> Result<String> {
+        let mut dirs = vec![];
+        for dir in access_paths.entry_paths(&self.args.serve_path) {
+            if self.find_dir_auth(&dir).await?.is_none() {
+                dirs.push(dir);
+            }
+        }
+        let serve_path = self.args.serve_path.clone();
+        let hidden = self.args.hidden_matcher.clone();
+        let dir_auth = self.args.dir_auth;
+        let running = self.running.clone();
+        let mut files = tokio::task::spawn_blocking(move || {
+            let mut files: Vec<(PathBuf, Option<SystemTime>)> = vec![];
+            for dir in dirs {
+                let mut it = WalkDir::new(&dir).into_iter();
+                while let Some(Ok(entry)) = it.next() {
+                    if !running.load(atomic::Ordering::SeqCst) || files.len() >= SITEMAP_MAX_URLS {
+                        return files;
+                    }
+                    let entry_path = entry.path();
+                    let base_name = get_file_name(entry_path);
+                    let file_type = entry.file_type();
+                    if entry.depth() > 0
+                        && (is_hidden(&hidden, entry_path, file_type.is_dir())
+                            || (cfg!(windows) && is_windows_reserved_name(base_name))
+                            || (dir_auth
+                                && file_type.is_dir()
+                                && is_dir_auth_protected(entry_path)))
+                    {
+                        if file_type.is_dir() {
+                            it.skip_current_dir();
+                        }
+                        continue;
+                    }
+                    if !file_type.is_file() {
+                        continue;
+                    }
+                    if let Ok(path) = entry_path.strip_prefix(&serve_path) {
+                        let mtime = entry.metadata().ok().and_then(|v| v.modified().ok());
+                        files.push((path.to_path_buf(), mtime));
+                    }
+                }
+            }
+            files
+        })
+        .await?;
+        files.sort();
+        let mut output = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+"#,
+        );
+        for (path, mtime) in files {
+            let loc = format!("{base_url}{}", encode_uri(&normalize_path(&path)));
+            output.push_str(&format!("<url><loc>{}</loc>", escape_str_pcdata(&loc)));
+            if let Some(mtime) = mtime {
+                let mtime = chrono::DateTime::<Utc>::from(mtime);
+                output.push_str(&format!(
+                    "<lastmod>{}</lastmod>",
+                    mtime.format("%Y-%m-%dT%H:%M:%SZ")
+                ));
+            }
+            output.push_str("</url>\n");
+        }
+        output.push_str("</urlset>\n");
+        Ok(output)
+    }
+
     async fn handle_render_index(
         &self,
         path: &Path,
@@ -703,8 +1896,17 @@ impl Server {
             self.handle_send_file(&index_path, headers, head_only, res)
                 .await?;
         } else if self.args.render_try_index {
-            self.handle_ls_dir(path, true, query_params, head_only, user, access_paths, res)
-                .await?;
+            self.handle_ls_dir(
+                path,
+                true,
+                query_params,
+                headers,
+                head_only,
+                user,
+                access_paths,
+                res,
+            )
+            .await?;
         } else {
             status_not_found(res)
         }
@@ -728,6 +1930,123 @@ impl Server {
         Ok(())
     }
 
+    /// Resolves `--try-files` templates for a missing path, `$uri` standing for the request path.
+    ///
+    /// A candidate is only served if it is a file the client could have requested directly.
+    async fn find_try_file(
+        &self,
+        relative_path: &str,
+        method: &Method,
+        authorization: Option<&HeaderValue>,
+        is_microsoft_webdav: bool,
+    ) -> Option<PathBuf> {
+        for template in &self.args.try_files {
+            let candidate = template.replace("$uri", &format!("/{relative_path}"));
+            let candidate = candidate.trim_matches('/');
+            if candidate.is_empty() || candidate == relative_path {
+                continue;
+            }
+            if self.args.dir_auth && is_file_name(candidate, DIR_AUTH_FILE) {
+                continue;
+            }
+            let (_, access_paths) =
+                self.args
+                    .auth
+                    .guard(candidate, method, authorization, is_microsoft_webdav);
+            if access_paths.is_none() {
+                continue;
+            }
+            let path = self.join_path(candidate)?;
+            if !fs::metadata(&path).await.is_ok_and(|v| v.is_file()) {
+                continue;
+            }
+            if !self.args.allow_symlink && !self.is_root_contained(&path).await {
+                continue;
+            }
+            if self.args.dir_auth && !self.args.path_is_file {
+                match self.find_dir_auth(&path).await {
+                    Ok(Some(dir_auth)) if dir_auth.guard(method, authorization).is_none() => {
+                        continue
+                    }
+                    Err(_) => continue,
+                    _ => {}
+                }
+            }
+            return Some(path);
+        }
+        None
+    }
+
+    /// Progress of an in-flight upload, only shown to whoever could have started it.
+    ///
+    /// Ids are chosen by clients, so entries are kept per user and the upload path must be readable.
+    async fn handle_upload_status(
+        &self,
+        id: &str,
+        headers: &HeaderMap<HeaderValue>,
+        addr: Option<SocketAddr>,
+        res: &mut Response,
+    ) -> Result<()> {
+        let is_trusted = self.is_trusted_client(addr);
+        let authorization = headers.get(AUTHORIZATION);
+        let user = authorization
+            .filter(|_| !is_trusted)
+            .and_then(get_auth_user);
+        let found = {
+            let uploads = self.uploads.lock().unwrap();
+            [user, None].into_iter().find_map(|user| {
+                let key = (user, id.to_string());
+                uploads.get(&key).map(|v| (key.0, v.clone()))
+            })
+        };
+        let Some((user, progress)) = found else {
+            status_not_found(res);
+            return Ok(());
+        };
+        if !is_trusted
+            && !self
+                .is_upload_owner(&progress.path, user.as_deref(), authorization)
+                .await?
+        {
+            match authorization {
+                None => self.auth_reject(res)?,
+                Some(_) => status_forbid(res),
+            }
+            return Ok(());
+        }
+        res.headers_mut()
+            .typed_insert(ContentType::from(mime_guess::mime::APPLICATION_JSON));
+        res.headers_mut()
+            .typed_insert(CacheControl::new().with_no_cache());
+        *res.body_mut() = body_full(serde_json::to_string(&progress)?);
+        Ok(())
+    }
+
+    /// Whether `authorization` authenticates as `user`, or `None` for anonymous access,
+    /// with read access to `relative_path`.
+    async fn is_upload_owner(
+        &self,
+        relative_path: &str,
+        user: Option<&str>,
+        authorization: Option<&HeaderValue>,
+    ) -> Result<bool> {
+        let authorization = authorization.filter(|_| user.is_some());
+        let (auth_user, access_paths) =
+            self.args
+                .auth
+                .guard(relative_path, &Method::GET, authorization, false);
+        if access_paths.is_none() {
+            return Ok(false);
+        }
+        if let Some(path) = self.join_path(relative_path) {
+            if let Some(dir_auth) = self.find_dir_auth(&path).await? {
+                return Ok(user.is_some()
+                    && dir_auth.guard(&Method::GET, authorization).as_deref() == user);
+            }
+        }
+        Ok(auth_user.as_deref() == user)
+    }
+
     async fn handle_internal(
         &self,
         req_path: &str,
@@ -746,25 +2065,15 @@ impl Server {
                     }
                 }
                 None => match name {
-                    "index.js" => {
-                        *res.body_mut() = body_full(INDEX_JS);
-                        res.headers_mut().insert(
-                            "content-type",
-                            HeaderValue::from_static("application/javascript; charset=UTF-8"),
-                        );
-                    }
+                    "index.js" => send_embedded_asset(
+                        res,
+                        INDEX_JS.as_bytes(),
+                        "application/javascript; charset=UTF-8",
+                    ),
                     "index.css" => {
-                        *res.body_mut() = body_full(INDEX_CSS);
-                        res.headers_mut().insert(
-                            "content-type",
-                            HeaderValue::from_static("text/css; charset=UTF-8"),
-                        );
-                    }
-                    "favicon.ico" => {
-                        *res.body_mut() = body_full(FAVICON_ICO);
-                        res.headers_mut()
-                            .insert("content-type", HeaderValue::from_static("image/x-icon"));
+                        send_embedded_asset(res, INDEX_CSS.as_bytes(), "text/css; charset=UTF-8")
                     }
+                    "favicon.ico" => send_embedded_asset(res, FAVICON_ICO, "image/x-icon"),
                     _ => {
                         status_not_found(res);
                     }
@@ -779,17 +2088,117 @@ impl Server {
                 HeaderValue::from_static("nosniff"),
             );
             Ok(true)
-        } else if req_path == HEALTH_CHECK_PATH {
+        } else if req_path == HEALTH_CHECK_PATH && !self.args.no_health {
+            res.headers_mut()
+                .typed_insert(ContentType::from(mime_guess::mime::APPLICATION_JSON));
+
+            *res.body_mut() = body_full(r#"{"status":"OK"}"#);
+            Ok(true)
+        } else if req_path == METRICS_PATH && self.args.metrics {
+            res.headers_mut().insert(
+                CONTENT_TYPE,
+                HeaderValue::from_static("text/plain; version=0.0.4; charset=utf-8"),
+            );
+            res.headers_mut()
+                .typed_insert(CacheControl::new().with_no_cache());
+            *res.body_mut() = body_full(self.metrics.render());
+            Ok(true)
+        } else if req_path == VERSION_PATH {
+            let features: Vec<&str> = [
+                ("tls", cfg!(feature = "tls")),
+                ("http3", cfg!(feature = "http3")),
+            ]
+            .into_iter()
+            .filter_map(|(name, enabled)| enabled.then_some(name))
+            .collect();
+            let output = serde_json::json!({
+                "version": env!("CARGO_PKG_VERSION"),
+                "git_hash": option_env!("DUFS_GIT_HASH"),
+                "features": features,
+            });
             res.headers_mut()
                 .typed_insert(ContentType::from(mime_guess::mime::APPLICATION_JSON));
+            *res.body_mut() = body_full(output.to_string());
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    async fn cache_headers(
+        &self,
+        path: &Path,
+        meta: &Metadata,
+        encoding: Option<&str>,
+    ) -> Option<(ETag, LastModified)> {
+        let tag = self.file_etag(path, meta, encoding).await?;
+        extract_cache_headers(meta, self.args.weak_etags, &tag)
+    }
+
+    /// The quoted opaque tag, without the weak prefix.
+    ///
+    /// With `--etag hash` it is a digest of the content, cached until the mtime or size
+    /// changes. Files over `ETAG_HASH_MAX_SIZE` keep the mtime-size tag.
+    async fn file_etag(
+        &self,
+        path: &Path,
+        meta: &Metadata,
+        encoding: Option<&str>,
+    ) -> Option<String> {
+        if self.args.etag != EtagMode::Hash || meta.len() > ETAG_HASH_MAX_SIZE {
+            return mtime_etag(meta, encoding);
+        }
+        let key = (meta.modified().ok()?, meta.len());
+        let cached = self
+            .etag_hashes
+            .lock()
+            .unwrap()
+            .get(path)
+            .filter(|v| (v.0, v.1) == key)
+            .map(|v| v.2.clone());
+        let hash = match cached {
+            Some(v) => v,
+            None => {
+                let hash = sha256_file(path).await.ok()?;
+                let mut hashes = self.etag_hashes.lock().unwrap();
+                if hashes.len() >= ETAG_HASH_CACHE_SIZE {
+                    hashes.clear();
+                }
+                hashes.insert(path.to_path_buf(), (key.0, key.1, hash.clone()));
+                hash
+            }
+        };
+        let suffix = encoding.map(|v| format!("-{v}")).unwrap_or_default();
+        Some(format!(r#""{}{suffix}""#, &hash[..32]))
+    }
 
-            *res.body_mut() = body_full(r#"{"status":"OK"}"#);
-            Ok(true)
-        } else {
-            Ok(false)
+    /// `--cache-control` lets clients keep files, otherwise they revalidate each time.
+    fn file_cache_control(&self) -> CacheControl {
+        match self.args.cache_control {
+            Some(secs) => {
+                let cache_control = CacheControl::new()
+                    .with_public()
+                    .with_max_age(Duration::from_secs(secs));
+                if self.args.cache_immutable {
+                    cache_control.with_immutable()
+                } else {
+                    cache_control
+                }
+            }
+            None => CacheControl::new().with_no_cache(),
         }
     }
 
+    fn is_attachment(&self, path: &Path) -> bool {
+        let ext = path
+            .extension()
+            .map(|v| v.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let matches = |exts: &[String]| exts.iter().any(|v| v == "*" || *v == ext);
+        matches(&self.args.attachment_extensions) && !matches(&self.args.inline_extensions)
+    }
+
+    /// Send a file, with the disposition picked by its extension wherever it was found.
     async fn handle_send_file(
         &self,
         path: &Path,
@@ -799,40 +2208,40 @@ impl Server {
     ) -> Result<()> {
         let (file, meta) = tokio::join!(fs::File::open(path), fs::metadata(path),);
         let (mut file, meta) = (file?, meta?);
+        if meta.is_dir() {
+            // The path was replaced by a directory after it was classified as a file
+            status_not_found(res);
+            return Ok(());
+        }
         let size = meta.len();
+        let content_type = get_content_type(path).await?;
+        let encoding = self.select_file_encoding(headers, size, &content_type, res);
         let mut use_range = true;
-        if let Some((etag, last_modified)) = extract_cache_headers(&meta) {
-            if let Some(if_unmodified_since) = headers.typed_get::<IfUnmodifiedSince>() {
-                if !if_unmodified_since.precondition_passes(last_modified.into()) {
-                    *res.status_mut() = StatusCode::PRECONDITION_FAILED;
-                    return Ok(());
-                }
-            }
-            if let Some(if_match) = headers.typed_get::<IfMatch>() {
-                if !if_match.precondition_passes(&etag) {
-                    *res.status_mut() = StatusCode::PRECONDITION_FAILED;
-                    return Ok(());
-                }
-            }
-            if let Some(if_modified_since) = headers.typed_get::<IfModifiedSince>() {
-                if !if_modified_since.is_modified(last_modified.into()) {
-                    *res.status_mut() = StatusCode::NOT_MODIFIED;
-                    return Ok(());
-                }
-            }
-            if let Some(if_none_match) = headers.typed_get::<IfNoneMatch>() {
-                if !if_none_match.precondition_passes(&etag) {
-                    *res.status_mut() = StatusCode::NOT_MODIFIED;
-                    return Ok(());
-                }
+        if let Some((etag, last_modified)) = self.cache_headers(path, &meta, encoding).await {
+            // If-Unmodified-Since is only a fallback for clients that have no ETag to pin
+            let precondition_passes = match headers.typed_get::<IfMatch>() {
+                Some(if_match) => if_match.precondition_passes(&etag),
+                None => headers
+                    .typed_get::<IfUnmodifiedSince>()
+                    .map(|v| v.precondition_passes(last_modified.into()))
+                    .unwrap_or(true),
+            };
+            if !precondition_passes {
+                *res.status_mut() = StatusCode::PRECONDITION_FAILED;
+                return Ok(());
             }
+            let not_modified = is_not_modified(headers, &etag, last_modified);
 
-            res.headers_mut()
-                .typed_insert(CacheControl::new().with_no_cache());
+            res.headers_mut().typed_insert(self.file_cache_control());
             res.headers_mut().typed_insert(last_modified);
             res.headers_mut().typed_insert(etag.clone());
 
-            if headers.typed_get::<Range>().is_some() {
+            if not_modified {
+                *res.status_mut() = StatusCode::NOT_MODIFIED;
+                return Ok(());
+            }
+
+            if headers.contains_key(RANGE) {
                 use_range = headers
                     .typed_get::<IfRange>()
                     .map(|if_range| !if_range.is_modified(Some(&etag), Some(&last_modified)))
@@ -843,29 +2252,33 @@ impl Server {
             }
         }
 
-        let range = if use_range {
+        // an empty file has no satisfiable range, send it whole rather than a 416
+        let ranges = if use_range && size > 0 {
             headers.get(RANGE).map(|range| {
                 range
                     .to_str()
                     .ok()
-                    .and_then(|range| parse_range(range, size))
+                    .and_then(|range| parse_ranges(range, size))
             })
         } else {
             None
         };
+        // so many disjoint pieces cost more than the whole file, ignore the header
+        let ranges = ranges.filter(|v| v.as_ref().is_none_or(|v| v.len() <= MAX_BYTERANGES));
 
-        res.headers_mut().insert(
-            CONTENT_TYPE,
-            HeaderValue::from_str(&get_content_type(path).await?)?,
-        );
+        res.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_str(&content_type)?);
 
         let filename = try_get_file_name(path)?;
-        set_content_disposition(res, true, filename)?;
+        set_content_disposition(res, !self.is_attachment(path), filename)?;
 
         res.headers_mut().typed_insert(AcceptRanges::bytes());
 
-        if let Some(range) = range {
-            if let Some((start, end)) = range {
+        if let Some(ranges) = ranges {
+            if let Some(ranges) = ranges.as_ref().filter(|v| v.len() > 1) {
+                *res.status_mut() = StatusCode::PARTIAL_CONTENT;
+                self.send_byteranges(file, size, &content_type, ranges, head_only, res)?;
+            } else if let Some((start, end)) = ranges.map(|v| v[0]) {
                 file.seek(SeekFrom::Start(start)).await?;
                 let range_size = end - start + 1;
                 *res.status_mut() = StatusCode::PARTIAL_CONTENT;
@@ -879,7 +2292,7 @@ impl Server {
                 }
 
                 let stream_body = StreamBody::new(
-                    LengthLimitedStream::new(file, range_size as usize)
+                    LengthLimitedStream::new(file, range_size as usize, self.stream_chunk_size())
                         .map_ok(Frame::data)
                         .map_err(|err| anyhow!("{err}")),
                 );
@@ -890,6 +2303,29 @@ impl Server {
                 res.headers_mut()
                     .insert(CONTENT_RANGE, format!("bytes */{size}").parse()?);
             }
+        } else if let Some(encoding) = encoding {
+            // the encoded length is unknown until the encoder is done, so the body is chunked
+            res.headers_mut()
+                .insert(CONTENT_ENCODING, HeaderValue::from_static(encoding));
+            if head_only {
+                return Ok(());
+            }
+
+            let level = Level::Precise(self.args.http_compress.to_http_quality().unwrap_or(1));
+            let chunk_size = self.stream_chunk_size();
+            let reader = io::BufReader::with_capacity(chunk_size, file);
+            let encoder: Pin<Box<dyn io::AsyncRead + Send + Sync>> = if encoding == "br" {
+                Box::pin(BrotliEncoder::with_quality(reader, level))
+            } else {
+                Box::pin(GzipEncoder::with_quality(reader, level))
+            };
+            let reader_stream = ReaderStream::with_capacity(encoder, chunk_size);
+            let stream_body = StreamBody::new(
+                reader_stream
+                    .map_ok(Frame::data)
+                    .map_err(|err| anyhow!("{err}")),
+            );
+            *res.body_mut() = stream_body.boxed();
         } else {
             res.headers_mut()
                 .insert(CONTENT_LENGTH, format!("{size}").parse()?);
@@ -897,7 +2333,7 @@ impl Server {
                 return Ok(());
             }
 
-            let reader_stream = ReaderStream::with_capacity(file, BUF_SIZE);
+            let reader_stream = ReaderStream::with_capacity(file, self.stream_chunk_size());
             let stream_body = StreamBody::new(
                 reader_stream
                     .map_ok(Frame::data)
@@ -909,20 +2345,124 @@ impl Server {
         Ok(())
     }
 
+    /// Picks `br` or `gzip` for a whole text file when `--http-compress` is on.
+    ///
+    /// Range requests are always answered from the identity bytes, offsets into a
+    /// compressed stream would not be stable between requests.
+    fn select_file_encoding(
+        &self,
+        headers: &HeaderMap<HeaderValue>,
+        size: u64,
+        content_type: &str,
+        res: &mut Response,
+    ) -> Option<&'static str> {
+        if self.args.http_compress == Compress::None
+            || size < HTTP_COMPRESS_MIN_SIZE
+            || !is_compressible(content_type)
+        {
+            return None;
+        }
+        add_vary(res, "Accept-Encoding");
+        if headers.contains_key(RANGE) {
+            return None;
+        }
+        let accept_encoding = headers.get(ACCEPT_ENCODING).and_then(|v| v.to_str().ok());
+        match select_encoding(accept_encoding, &["br", "gzip", "identity"]) {
+            Some("identity") | None => None,
+            encoding => encoding,
+        }
+    }
+
+    /// A `multipart/byteranges` body with one part per range, RFC 7233 appendix A.
+    fn send_byteranges(
+        &self,
+        mut file: File,
+        size: u64,
+        content_type: &str,
+        ranges: &[(u64, u64)],
+        head_only: bool,
+        res: &mut Response,
+    ) -> Result<()> {
+        let boundary = Uuid::new_v4().simple().to_string();
+        let parts: Vec<_> = ranges
+            .iter()
+            .enumerate()
+            .map(|(i, &(start, end))| {
+                let delimiter = if i == 0 { "" } else { "\r\n" };
+                let head = format!(
+                    "{delimiter}--{boundary}\r\nContent-Type: {content_type}\r\nContent-Range: bytes {start}-{end}/{size}\r\n\r\n"
+                );
+                (head, start, end - start + 1)
+            })
+            .collect();
+        let tail = format!("\r\n--{boundary}--\r\n");
+        let content_length = parts
+            .iter()
+            .map(|(head, _, len)| head.len() as u64 + len)
+            .sum::<u64>()
+            + tail.len() as u64;
+        res.headers_mut().insert(
+            CONTENT_TYPE,
+            format!("multipart/byteranges; boundary={boundary}").parse()?,
+        );
+        res.headers_mut()
+            .insert(CONTENT_LENGTH, format!("{content_length}").parse()?);
+        if head_only {
+            return Ok(());
+        }
+        let chunk_size = self.stream_chunk_size();
+        let stream = async_stream::stream! {
+            for (head, start, len) in parts {
+                yield Ok(Bytes::from(head));
+                if let Err(err) = file.seek(SeekFrom::Start(start)).await {
+                    yield Err(err);
+                    return;
+                }
+                let part = LengthLimitedStream::new(&mut file, len as usize, chunk_size);
+                pin_mut!(part);
+                while let Some(chunk) = futures_util::StreamExt::next(&mut part).await {
+                    yield chunk;
+                }
+            }
+            yield Ok(Bytes::from(tail));
+        };
+        let stream_body = StreamBody::new(
+            stream
+                .map_ok(Frame::data)
+                .map_err(|err: io::Error| anyhow!("{err}")),
+        );
+        *res.body_mut() = stream_body.boxed();
+        Ok(())
+    }
+
+    /// `--lang` if set, else the best match for `Accept-Language`.
+    fn ui_lang(&self, headers: &HeaderMap<HeaderValue>, res: &mut Response) -> &str {
+        if let Some(lang) = &self.args.lang {
+            return lang;
+        }
+        add_vary(res, "Accept-Language");
+        select_lang(headers.get("accept-language").and_then(|v| v.to_str().ok()))
+    }
+
+    /// File bodies are read one chunk at a time, only when hyper polls for more to send.
+    ///
+    /// So a slow client holds at most this chunk plus hyper's write buffer in memory.
+    fn stream_chunk_size(&self) -> usize {
+        self.args.stream_buffer.map_or(BUF_SIZE, |v| v as usize)
+    }
+
     async fn handle_edit_file(
         &self,
         path: &Path,
         kind: DataKind,
+        headers: &HeaderMap<HeaderValue>,
         head_only: bool,
         user: Option<String>,
         res: &mut Response,
     ) -> Result<()> {
         let (file, meta) = tokio::join!(fs::File::open(path), fs::metadata(path),);
         let (file, meta) = (file?, meta?);
-        let href = format!(
-            "/{}",
-            normalize_path(path.strip_prefix(&self.args.serve_path)?)
-        );
+        let href = self.href_of(path)?;
         let mut buffer: Vec<u8> = vec![];
         file.take(1024).read_to_end(&mut buffer).await?;
         let editable =
@@ -935,6 +2475,7 @@ impl Server {
             allow_delete: self.args.allow_delete,
             auth: self.args.auth.exist(),
             user,
+            lang: self.ui_lang(headers, res).to_string(),
             editable,
         };
         res.headers_mut()
@@ -976,68 +2517,188 @@ impl Server {
         Ok(())
     }
 
+    async fn handle_static_response(
+        &self,
+        static_response: &StaticResponse,
+        method: &Method,
+        res: &mut Response,
+    ) -> Result<()> {
+        if method != Method::GET && method != Method::HEAD {
+            *res.status_mut() = StatusCode::METHOD_NOT_ALLOWED;
+            res.headers_mut()
+                .insert(ALLOW, HeaderValue::from_static("GET, HEAD"));
+            return Ok(());
+        }
+        let body = match &static_response.body {
+            StaticBody::File(path) => match fs::read(path).await {
+                Ok(v) => v,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                    status_not_found(res);
+                    return Ok(());
+                }
+                Err(err) => return Err(err.into()),
+            },
+            StaticBody::Inline(text) => text.as_bytes().to_vec(),
+        };
+        res.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_str(&static_response.content_type)?,
+        );
+        res.headers_mut()
+            .typed_insert(ContentLength(body.len() as u64));
+        if method == Method::GET {
+            *res.body_mut() = body_full(body);
+        }
+        Ok(())
+    }
+
     async fn handle_propfind_dir(
         &self,
         path: &Path,
-        headers: &HeaderMap<HeaderValue>,
+        req: Request,
         access_paths: AccessPaths,
         res: &mut Response,
     ) -> Result<()> {
-        let depth: u32 = match headers.get("depth") {
-            Some(v) => match v.to_str().ok().and_then(|v| v.parse().ok()) {
-                Some(0) => 0,
-                Some(1) => 1,
-                _ => {
-                    status_bad_request(res, "Invalid depth: only 0 and 1 are allowed.");
+        let (parts, body) = req.into_parts();
+        let headers = &parts.headers;
+        let depth = match headers.get("depth").map(|v| v.to_str().unwrap_or_default()) {
+            None | Some("1") => 1,
+            Some("0") => 0,
+            Some(v) if v.eq_ignore_ascii_case("infinity") => match self.args.dav_infinite_depth {
+                DavInfiniteDepth::Allow => usize::MAX,
+                DavInfiniteDepth::Limit(n) => n,
+                DavInfiniteDepth::Deny => {
+                    *res.status_mut() = StatusCode::FORBIDDEN;
+                    res.headers_mut().insert(
+                        "content-type",
+                        HeaderValue::from_static("application/xml; charset=utf-8"),
+                    );
+                    *res.body_mut() = body_full(
+                        r#"<?xml version="1.0" encoding="utf-8" ?>
+<D:error xmlns:D="DAV:"><D:propfind-finite-depth/></D:error>"#,
+                    );
                     return Ok(());
                 }
             },
-            None => 1,
+            _ => {
+                status_bad_request(res, "Invalid depth: only 0, 1 and infinity are allowed.");
+                return Ok(());
+            }
+        };
+        let Some(propfind) = read_propfind(body, res).await else {
+            return Ok(());
         };
         let mut paths = match self.to_pathitem(path, &self.args.serve_path).await? {
             Some(v) => vec![v],
             None => vec![],
         };
-        if depth == 1 {
-            match self
-                .list_dir(path, &self.args.serve_path, access_paths)
-                .await
-            {
-                Ok(child) => paths.extend(child),
+        let mut truncated = false;
+        if depth > 0 {
+            match self.list_dir_deep(path, access_paths, depth).await {
+                Ok((child, child_truncated)) => {
+                    paths.extend(child);
+                    truncated = child_truncated;
+                }
                 Err(_) => {
                     status_forbid(res);
                     return Ok(());
                 }
             }
         }
-        let output = paths
+        if propfind_not_modified(headers, &paths, res) {
+            return Ok(());
+        }
+        self.attach_dead_props(&mut paths).await;
+        let mut output = paths
             .iter()
-            .map(|v| v.to_dav_xml(self.args.uri_prefix.as_str()))
+            .map(|v| v.to_dav_xml(self.args.uri_prefix.as_str(), &propfind))
             .fold(String::new(), |mut acc, v| {
                 acc.push_str(&v);
                 acc
             });
+        if truncated {
+            // RFC 5323 marks a truncated result with 507 on the request-URI
+            output.push_str(&format!(
+                r#"<D:response>
+<D:href>{}</D:href>
+<D:status>HTTP/1.1 507 Insufficient Storage</D:status>
+</D:response>"#,
+                escape_str_pcdata(parts.uri.path())
+            ));
+        }
         res_multistatus(res, &output);
         Ok(())
     }
 
-    async fn handle_propfind_file(&self, path: &Path, res: &mut Response) -> Result<()> {
-        if let Some(pathitem) = self.to_pathitem(path, &self.args.serve_path).await? {
-            res_multistatus(res, &pathitem.to_dav_xml(self.args.uri_prefix.as_str()));
+    async fn handle_propfind_file(
+        &self,
+        path: &Path,
+        req: Request,
+        res: &mut Response,
+    ) -> Result<()> {
+        let (parts, body) = req.into_parts();
+        let headers = &parts.headers;
+        let Some(propfind) = read_propfind(body, res).await else {
+            return Ok(());
+        };
+        if let Some(mut pathitem) = self.to_pathitem(path, &self.args.serve_path).await? {
+            if propfind_not_modified(headers, std::slice::from_ref(&pathitem), res) {
+                return Ok(());
+            }
+            pathitem.dead_props = dav_props::load_for(path).await;
+            res_multistatus(
+                res,
+                &pathitem.to_dav_xml(self.args.uri_prefix.as_str(), &propfind),
+            );
         } else {
             status_not_found(res);
         }
         Ok(())
     }
 
-    async fn handle_mkcol(&self, path: &Path, res: &mut Response) -> Result<()> {
-        fs::create_dir_all(path).await?;
+    /// Fills in the PROPPATCH properties, reading each directory's sidecar once.
+    async fn attach_dead_props(&self, paths: &mut [PathItem]) {
+        let mut cache: HashMap<PathBuf, _> = HashMap::new();
+        for item in paths.iter_mut().filter(|v| !v.name.is_empty()) {
+            let path = self.args.serve_path.join(&item.name);
+            let Some(dir) = path.parent() else {
+                continue;
+            };
+            if !cache.contains_key(dir) {
+                cache.insert(dir.to_path_buf(), dav_props::load(dir).await);
+            }
+            if let Some(props) = cache.get_mut(dir).and_then(|v| v.remove(item.base_name())) {
+                item.dead_props = props;
+            }
+        }
+    }
+
+    async fn handle_mkcol(&self, path: &Path, req: Request, res: &mut Response) -> Result<()> {
+        // extended MKCOL isn't supported, so any body is something we can't process, RFC 4918 9.3
+        let mut body = req.into_body();
+        while let Some(frame) = body.frame().await {
+            let Ok(frame) = frame else {
+                status_bad_request(res, "Invalid Request Body");
+                return Ok(());
+            };
+            if frame.data_ref().is_some_and(|data| !data.is_empty()) {
+                *res.status_mut() = StatusCode::UNSUPPORTED_MEDIA_TYPE;
+                return Ok(());
+            }
+        }
+        create_dir_all(path, self.args.dir_mode).await?;
         *res.status_mut() = StatusCode::CREATED;
         Ok(())
     }
 
-    async fn handle_copy(&self, path: &Path, req: &Request, res: &mut Response) -> Result<()> {
-        let dest = match self.extract_dest(req, res) {
+    async fn handle_copy(
+        &self,
+        path: &Path,
+        req: &Request,
+        is_trusted: bool,
+        res: &mut Response,
+    ) -> Result<()> {
+        let dest = match self.extract_dest(req, is_trusted, res).await? {
             Some(dest) => dest,
             None => {
                 return Ok(());
@@ -1050,47 +2711,173 @@ impl Server {
             return Ok(());
         }
 
-        ensure_path_parent(&dest).await?;
+        let Some(replaced) = self.check_dest(path, &dest, req, is_trusted, res).await? else {
+            return Ok(());
+        };
+
+        ensure_path_parent(&dest, self.args.dir_mode).await?;
 
-        fs::copy(path, &dest).await?;
+        // copy beside the destination first, a failed copy must not cost the old content
+        let temp_path = upload_temp_path(&dest);
+        if let Err(err) = fs::copy(path, &temp_path).await {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(err.into());
+        }
+        if let Err(err) = replace_dest(&temp_path, &dest).await {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(err);
+        }
+        dav_props::transfer(path, Some(&dest), true).await?;
 
-        status_no_content(res);
+        status_created_or_replaced(res, replaced);
         Ok(())
     }
 
-    async fn handle_move(&self, path: &Path, req: &Request, res: &mut Response) -> Result<()> {
-        let dest = match self.extract_dest(req, res) {
+    async fn handle_move(
+        &self,
+        path: &Path,
+        req: &Request,
+        is_trusted: bool,
+        res: &mut Response,
+    ) -> Result<()> {
+        let dest = match self.extract_dest(req, is_trusted, res).await? {
             Some(dest) => dest,
             None => {
                 return Ok(());
             }
         };
 
-        ensure_path_parent(&dest).await?;
+        if fs::symlink_metadata(path).await?.is_dir()
+            && !self.passes_nested_dir_auths(path, req, is_trusted).await?
+        {
+            status_forbid(res);
+            return Ok(());
+        }
 
-        fs::rename(path, &dest).await?;
+        let Some(replaced) = self.check_dest(path, &dest, req, is_trusted, res).await? else {
+            return Ok(());
+        };
 
-        status_no_content(res);
+        ensure_path_parent(&dest, self.args.dir_mode).await?;
+
+        replace_dest(path, &dest).await?;
+        dav_props::transfer(path, Some(&dest), false).await?;
+
+        status_created_or_replaced(res, replaced);
         Ok(())
     }
 
-    async fn handle_lock(&self, req_path: &str, auth: bool, res: &mut Response) -> Result<()> {
-        let token = if auth {
-            format!("opaquelocktoken:{}", Uuid::new_v4())
-        } else {
-            Utc::now().timestamp().to_string()
+    /// Checks whether a COPY or MOVE may replace `dest` and reports whether there is anything
+    /// to replace, or returns `None` with the refusal already set on `res`.
+    async fn check_dest(
+        &self,
+        path: &Path,
+        dest: &Path,
+        req: &Request,
+        is_trusted: bool,
+        res: &mut Response,
+    ) -> Result<Option<bool>> {
+        // replacing an ancestor of the source would delete the source with it,
+        // and a directory cannot be moved into itself
+        if dest.starts_with(path) || path.starts_with(dest) {
+            status_forbid(res);
+            return Ok(None);
+        }
+        let Ok(meta) = fs::symlink_metadata(dest).await else {
+            return Ok(Some(false));
+        };
+        let headers = req.headers();
+        // `Overwrite` defaults to `T`, `F` asks to fail rather than replace
+        if headers
+            .get("overwrite")
+            .is_some_and(|v| v.as_bytes().eq_ignore_ascii_case(b"F"))
+        {
+            *res.status_mut() = StatusCode::PRECONDITION_FAILED;
+            return Ok(None);
+        }
+        // replacing is deleting, just as for PUT over an existing file
+        if !self.args.allow_delete {
+            status_forbid(res);
+            return Ok(None);
+        }
+        if meta.is_dir() && !self.passes_nested_dir_auths(dest, req, is_trusted).await? {
+            status_forbid(res);
+            return Ok(None);
+        }
+        Ok(Some(true))
+    }
+
+    /// Deleting, moving or replacing a directory takes every subtree it holds with it,
+    /// so the request must also pass the `.dufs-auth` of each one guarded on its own.
+    async fn passes_nested_dir_auths(
+        &self,
+        dir: &Path,
+        req: &Request,
+        is_trusted: bool,
+    ) -> Result<bool> {
+        if !self.args.dir_auth || is_trusted {
+            return Ok(true);
+        }
+        let authorization = req.headers().get(AUTHORIZATION);
+        for file in nested_dir_auths(dir).await? {
+            let dir_auth = DirAuth::new(&fs::read_to_string(&file).await?);
+            if dir_auth.guard(req.method(), authorization).is_none() {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Grants an exclusive write lock, or refreshes it when the request names its token.
+    fn handle_lock(
+        &self,
+        relative_path: &str,
+        req_path: &str,
+        headers: &HeaderMap<HeaderValue>,
+        res: &mut Response,
+    ) -> Result<()> {
+        let timeout = parse_lock_timeout(headers);
+        let submitted = submitted_lock_tokens(headers);
+        let expires = Instant::now() + Duration::from_secs(timeout);
+        let (token, created) = {
+            let mut locks = self.active_locks();
+            match locks.get_mut(relative_path) {
+                Some(lock) if submitted.contains(&lock.token) => {
+                    lock.expires = expires;
+                    (lock.token.clone(), false)
+                }
+                Some(_) => {
+                    status_locked(res);
+                    return Ok(());
+                }
+                None => {
+                    let token = format!("opaquelocktoken:{}", Uuid::new_v4());
+                    let lock = LockInfo {
+                        token: token.clone(),
+                        expires,
+                    };
+                    locks.insert(relative_path.to_string(), lock);
+                    (token, true)
+                }
+            }
         };
 
         res.headers_mut().insert(
             "content-type",
             HeaderValue::from_static("application/xml; charset=utf-8"),
         );
-        res.headers_mut()
-            .insert("lock-token", format!("<{token}>").parse()?);
+        if created {
+            res.headers_mut()
+                .insert("lock-token", format!("<{token}>").parse()?);
+        }
 
         *res.body_mut() = body_full(format!(
             r#"<?xml version="1.0" encoding="utf-8"?>
 <D:prop xmlns:D="DAV:"><D:lockdiscovery><D:activelock>
+<D:locktype><D:write/></D:locktype>
+<D:lockscope><D:exclusive/></D:lockscope>
+<D:depth>0</D:depth>
+<D:timeout>Second-{timeout}</D:timeout>
 <D:locktoken><D:href>{token}</D:href></D:locktoken>
 <D:lockroot><D:href>{req_path}</D:href></D:lockroot>
 </D:activelock></D:lockdiscovery></D:prop>"#
@@ -1098,15 +2885,180 @@ impl Server {
         Ok(())
     }
 
-    async fn handle_proppatch(&self, req_path: &str, res: &mut Response) -> Result<()> {
+    fn handle_unlock(
+        &self,
+        relative_path: &str,
+        headers: &HeaderMap<HeaderValue>,
+        res: &mut Response,
+    ) {
+        let Some(token) = headers
+            .get("lock-token")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.trim().trim_start_matches('<').trim_end_matches('>'))
+        else {
+            status_bad_request(res, "Missing Lock-Token Header");
+            return;
+        };
+        let mut locks = self.active_locks();
+        match locks.get(relative_path) {
+            Some(lock) if lock.token == token => {
+                locks.remove(relative_path);
+                status_no_content(res);
+            }
+            // not locked, or locked under another token
+            _ => *res.status_mut() = StatusCode::CONFLICT,
+        }
+    }
+
+    /// Any list of the `If` header may hold, and a list holds when all its conditions do.
+    async fn if_header_passes(&self, relative_path: &str, lists: &[IfList]) -> bool {
+        for list in lists {
+            let path = match &list.resource {
+                Some(tag) => match tag
+                    .parse::<Uri>()
+                    .ok()
+                    .and_then(|uri| self.resolve_path(uri.path()))
+                {
+                    Some(v) => v,
+                    None => continue,
+                },
+                None => relative_path.to_string(),
+            };
+            let lock_token = self
+                .active_locks()
+                .get(&path)
+                .map(|lock| lock.token.clone());
+            let etag = match self.join_path(&path) {
+                Some(path) => match fs::metadata(&path).await {
+                    Ok(meta) => self.file_etag(&path, &meta, None).await,
+                    Err(_) => None,
+                },
+                None => None,
+            };
+            let passes = list.conditions.iter().all(|(not, condition)| {
+                let matched = match condition {
+                    IfCondition::Token(token) => lock_token.as_ref() == Some(token),
+                    IfCondition::ETag(tag) => etag
+                        .as_deref()
+                        .is_some_and(|etag| tag.trim_start_matches("W/") == etag),
+                };
+                matched != *not
+            });
+            if passes {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// The lock table with expired entries dropped.
+    fn active_locks(&self) -> std::sync::MutexGuard<'_, HashMap<String, LockInfo>> {
+        let now = Instant::now();
+        let mut locks = self.locks.lock().unwrap();
+        locks.retain(|_, lock| lock.expires > now);
+        locks
+    }
+
+    /// Whether writing `relative_path` would break a lock whose token the request doesn't hold.
+    ///
+    /// `deep` also checks everything below it, for DELETE and MOVE of a directory.
+    fn is_locked(&self, relative_path: &str, headers: &HeaderMap<HeaderValue>, deep: bool) -> bool {
+        let submitted = submitted_lock_tokens(headers);
+        self.active_locks().iter().any(|(path, lock)| {
+            is_lock_covered(path, relative_path, deep) && !submitted.contains(&lock.token)
+        })
+    }
+
+    /// Locks go away with the resource, they don't follow it to a MOVE destination.
+    fn release_locks(&self, relative_path: &str) {
+        self.active_locks()
+            .retain(|path, _| !is_lock_covered(path, relative_path, true));
+    }
+
+    /// Stores dead properties, live ones stay computed from the filesystem.
+    ///
+    /// The update is all or nothing, RFC 4918 section 9.2: one refused property fails the rest with 424.
+    async fn handle_proppatch(&self, path: &Path, req: Request, res: &mut Response) -> Result<()> {
+        let req_path = req.uri().path().to_string();
+        let body = match Limited::new(req.into_body(), PROPPATCH_MAX_SIZE)
+            .collect()
+            .await
+        {
+            Ok(v) => v.to_bytes(),
+            Err(err) if err.is::<LengthLimitError>() => {
+                status_payload_too_large(res);
+                return Ok(());
+            }
+            Err(_) => {
+                status_bad_request(res, "Invalid Request Body");
+                return Ok(());
+            }
+        };
+        let updates = if body.is_empty() {
+            Some(vec![])
+        } else {
+            dav_props::parse_propertyupdate(&body)
+        };
+        let Some(updates) = updates else {
+            status_bad_request(res, "Invalid Request Body");
+            return Ok(());
+        };
+        let mut mtime = None;
+        let mut refused = vec![];
+        for update in &updates {
+            let prop = &update.prop;
+            // the root's sidecar would live outside the served directory
+            if path == self.args.serve_path {
+                refused.push(prop);
+            } else if prop.ns == DAV_NS {
+                // Windows sets `getlastmodified` after a copy, it is applied to the file itself
+                match (prop.name.as_str(), update.remove) {
+                    ("getlastmodified", false) => {
+                        match chrono::DateTime::parse_from_rfc2822(prop.value.trim()) {
+                            Ok(v) => mtime = Some(SystemTime::from(v)),
+                            Err(_) => refused.push(prop),
+                        }
+                    }
+                    _ => refused.push(prop),
+                }
+            }
+        }
+        let failed = !refused.is_empty();
+        if !failed {
+            if let Some(mtime) = mtime {
+                let file = fs::OpenOptions::new()
+                    .read(true)
+                    .write(!path.is_dir())
+                    .open(path)
+                    .await?;
+                file.into_std().await.set_modified(mtime)?;
+            }
+            let dead: Vec<_> = updates
+                .iter()
+                .filter(|v| v.prop.ns != DAV_NS)
+                .cloned()
+                .collect();
+            if !dead.is_empty() {
+                dav_props::apply(path, &dead).await?;
+            }
+        }
+        let propstat = |props: &[&DeadProp], status: &str| {
+            let names: Vec<_> = props.iter().map(|v| v.to_name_xml()).collect();
+            dav_props::propstat(&names, status)
+        };
+        let rest: Vec<_> = updates
+            .iter()
+            .map(|v| &v.prop)
+            .filter(|v| !refused.contains(v))
+            .collect();
+        let propstats = if failed {
+            propstat(&refused, "403 Forbidden") + &propstat(&rest, "424 Failed Dependency")
+        } else {
+            propstat(&rest, "200 OK")
+        };
         let output = format!(
             r#"<D:response>
-<D:href>{req_path}</D:href>
-<D:propstat>
-<D:prop>
-</D:prop>
-<D:status>HTTP/1.1 403 Forbidden</D:status>
-</D:propstat>
+<D:href>{req_path}</D:href>{propstats}
 </D:response>"#
         );
         res_multistatus(res, &output);
@@ -1119,30 +3071,46 @@ impl Server {
         path: &Path,
         mut paths: Vec<PathItem>,
         exist: bool,
+        truncated: bool,
         query_params: &HashMap<String, String>,
+        headers: &HeaderMap<HeaderValue>,
         head_only: bool,
         user: Option<String>,
         access_paths: AccessPaths,
         res: &mut Response,
     ) -> Result<()> {
-        if let Some(sort) = query_params.get("sort") {
-            if sort == "name" {
-                paths.sort_by(|v1, v2| v1.sort_by_name(v2))
-            } else if sort == "mtime" {
-                paths.sort_by(|v1, v2| v1.sort_by_mtime(v2))
-            } else if sort == "size" {
-                paths.sort_by(|v1, v2| v1.sort_by_size(v2))
-            }
-            if query_params
+        let Some((page, per_page)) = parse_pagination(query_params) else {
+            status_bad_request(res, "Invalid page");
+            return Ok(());
+        };
+        let case_sensitive = self.args.sort_case_sensitive;
+        let sort = match query_params.get("sort").map(|v| v.as_str()) {
+            None => Some("name"),
+            Some(v @ ("name" | "mtime" | "size")) => Some(v),
+            _ => None,
+        };
+        let compare: Option<fn(&PathItem, &PathItem, bool) -> Ordering> = match sort {
+            Some("name") => Some(PathItem::sort_by_name),
+            Some("mtime") => Some(PathItem::sort_by_mtime),
+            Some("size") => Some(PathItem::sort_by_size),
+            _ => None,
+        };
+        if let Some(compare) = compare {
+            let group_dirs = self.args.group_dirs;
+            paths.sort_by(|v1, v2| {
+                v1.group_by_type(v2, group_dirs)
+                    .then_with(|| compare(v1, v2, case_sensitive))
+            });
+        }
+        let desc = query_params.contains_key("sort")
+            && query_params
                 .get("order")
                 .map(|v| v == "desc")
-                .unwrap_or_default()
-            {
-                paths.reverse()
-            }
-        } else {
-            paths.sort_by(|v1, v2| v1.sort_by_name(v2))
+                .unwrap_or_default();
+        if desc {
+            paths.reverse()
         }
+        let order = sort.map(|_| if desc { "desc" } else { "asc" });
         if has_query_flag(query_params, "simple") {
             let output = paths
                 .into_iter()
@@ -1165,22 +3133,72 @@ impl Server {
             }
             return Ok(());
         }
-        let href = format!(
-            "/{}",
-            normalize_path(path.strip_prefix(&self.args.serve_path)?)
-        );
+        if has_query_flag(query_params, "plain") {
+            let output: String = paths.iter().map(PathItem::to_plain_line).collect();
+            res.headers_mut()
+                .typed_insert(ContentType::from(mime_guess::mime::TEXT_PLAIN_UTF_8));
+            res.headers_mut()
+                .typed_insert(ContentLength(output.len() as u64));
+            res.headers_mut()
+                .typed_insert(CacheControl::new().with_no_cache());
+            if !head_only {
+                *res.body_mut() = body_full(output);
+            }
+            return Ok(());
+        }
+        if has_query_flag(query_params, "tree") {
+            let nodes: Vec<_> = paths.iter().map(TreeNode::from).collect();
+            let output = serde_json::to_string(&nodes)?;
+            res.headers_mut()
+                .typed_insert(ContentType::from(mime_guess::mime::APPLICATION_JSON));
+            res.headers_mut()
+                .typed_insert(ContentLength(output.len() as u64));
+            if !head_only {
+                *res.body_mut() = body_full(output);
+            }
+            return Ok(());
+        }
+        let href = self.href_of(path)?;
+        // no parent link at the served root, so `..` never escapes it
+        let parent_href = (href != "/").then(|| {
+            let parent = href[1..].rsplit_once('/').map_or("", |(v, _)| v);
+            self.prefixed_href(parent)
+        });
         let readwrite = access_paths.perm().readwrite();
+        // sliced after sorting, so pages stay stable
+        let total_count = paths.len();
+        // a directory's size is its entry count, so only files add up
+        let total_size = paths.iter().filter(|v| !v.is_dir()).map(|v| v.size).sum();
+        if let Some(link) = pagination_link(&href, query_params, page, per_page, total_count) {
+            res.headers_mut()
+                .insert(LINK, HeaderValue::from_str(&link)?);
+        }
+        let paths: Vec<PathItem> = paths
+            .into_iter()
+            .skip((page - 1).saturating_mul(per_page))
+            .take(per_page)
+            .collect();
         let data = IndexData {
             kind: DataKind::Index,
             href,
+            parent_href,
             uri_prefix: self.args.uri_prefix.clone(),
             allow_upload: self.args.allow_upload && readwrite,
             allow_delete: self.args.allow_delete && readwrite,
             allow_search: self.args.allow_search,
             allow_archive: self.args.allow_archive,
+            allow_symlink: self.args.allow_symlink,
             dir_exists: exist,
             auth: self.args.auth.exist(),
             user,
+            lang: self.ui_lang(headers, res).to_string(),
+            sort: sort.map(|v| v.to_string()),
+            order: order.map(|v| v.to_string()),
+            page,
+            per_page,
+            total_count,
+            total_size,
+            truncated,
             paths,
         };
         let output = if has_query_flag(query_params, "json") {
@@ -1215,7 +3233,7 @@ impl Server {
     }
 
     fn auth_reject(&self, res: &mut Response) -> Result<()> {
-        set_webdav_headers(res);
+        set_webdav_headers(res, &self.args)?;
 
         www_authenticate(res, &self.args)?;
         *res.status_mut() = StatusCode::UNAUTHORIZED;
@@ -1230,7 +3248,12 @@ impl Server {
             .unwrap_or_default()
     }
 
-    fn extract_dest(&self, req: &Request, res: &mut Response) -> Option<PathBuf> {
+    async fn extract_dest(
+        &self,
+        req: &Request,
+        is_trusted: bool,
+        res: &mut Response,
+    ) -> Result<Option<PathBuf>> {
         let headers = req.headers();
         let dest_path = match self
             .extract_destination_header(headers)
@@ -1239,33 +3262,91 @@ impl Server {
             Some(dest) => dest,
             None => {
                 status_bad_request(res, "Invalid Destination");
-                return None;
+                return Ok(None);
             }
         };
 
+        if !self.validate_path(&dest_path, res) {
+            return Ok(None);
+        }
+
+        if self.is_locked(&dest_path, headers, true) {
+            status_locked(res);
+            return Ok(None);
+        }
+
         let authorization = headers.get(AUTHORIZATION);
-        let guard = self
-            .args
-            .auth
-            .guard(&dest_path, req.method(), authorization, false);
+        if !is_trusted {
+            let guard = self
+                .args
+                .auth
+                .guard(&dest_path, req.method(), authorization, false);
 
-        match guard {
-            (_, Some(_)) => {}
-            _ => {
-                status_forbid(res);
-                return None;
-            }
-        };
+            match guard {
+                (_, Some(_)) => {}
+                _ => {
+                    status_forbid(res);
+                    return Ok(None);
+                }
+            };
+        }
 
         let dest = match self.join_path(&dest_path) {
             Some(dest) => dest,
             None => {
                 *res.status_mut() = StatusCode::BAD_REQUEST;
-                return None;
+                return Ok(None);
             }
         };
 
-        Some(dest)
+        if is_file_name(get_file_name(&dest), DAV_PROPS_FILE) {
+            status_forbid(res);
+            return Ok(None);
+        }
+
+        if self.args.dir_auth {
+            let allowed = !is_file_name(get_file_name(&dest), DIR_AUTH_FILE)
+                && (is_trusted
+                    || match self.find_dir_auth(&dest).await? {
+                        Some(dir_auth) => dir_auth.guard(req.method(), authorization).is_some(),
+                        None => true,
+                    });
+            if !allowed {
+                status_forbid(res);
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(dest))
+    }
+
+    /// Load the nearest `.dufs-auth` at or above `path`, reusing the cached copy while its mtime holds.
+    async fn find_dir_auth(&self, path: &Path) -> Result<Option<Arc<DirAuth>>> {
+        if !self.args.dir_auth {
+            return Ok(None);
+        }
+        for dir in path.ancestors() {
+            if !dir.starts_with(&self.args.serve_path) {
+                break;
+            }
+            let file = dir.join(DIR_AUTH_FILE);
+            let Ok(meta) = fs::metadata(&file).await else {
+                continue;
+            };
+            let mtime = meta.modified()?;
+            if let Some((cached_mtime, dir_auth)) = self.dir_auths.lock().unwrap().get(&file) {
+                if *cached_mtime == mtime {
+                    return Ok(Some(dir_auth.clone()));
+                }
+            }
+            let dir_auth = Arc::new(DirAuth::new(&fs::read_to_string(&file).await?));
+            self.dir_auths
+                .lock()
+                .unwrap()
+                .insert(file, (mtime, dir_auth.clone()));
+            return Ok(Some(dir_auth));
+        }
+        Ok(None)
     }
 
     fn extract_destination_header(&self, headers: &HeaderMap<HeaderValue>) -> Option<String> {
@@ -1274,6 +3355,36 @@ impl Server {
         Some(uri.path().to_string())
     }
 
+    /// With `--trust-localhost`, loopback clients skip every auth check.
+    fn is_trusted_client(&self, addr: Option<SocketAddr>) -> bool {
+        self.args.trust_localhost && addr.is_some_and(|addr| addr.ip().to_canonical().is_loopback())
+    }
+
+    /// A signed, unexpired share link grants read access whatever the auth rules say.
+    fn is_shared(&self, relative_path: &str, method: &Method, query: Option<&str>) -> bool {
+        let Some(secret) = &self.args.share_secret else {
+            return false;
+        };
+        if method != Method::GET && method != Method::HEAD {
+            return false;
+        }
+        let (mut expires, mut token) = (None, None);
+        for (key, value) in form_urlencoded::parse(query.unwrap_or_default().as_bytes()) {
+            match key.as_ref() {
+                "expires" => expires = value.parse::<u64>().ok(),
+                "token" => token = Some(value),
+                _ => {}
+            }
+        }
+        let (Some(expires), Some(token)) = (expires, token) else {
+            return false;
+        };
+        let Ok(now) = unix_now() else {
+            return false;
+        };
+        verify_share(secret, relative_path, expires, &token, now.as_secs())
+    }
+
     fn resolve_path(&self, path: &str) -> Option<String> {
         let path = decode_uri(path)?;
         let path = path.trim_matches('/');
@@ -1294,12 +3405,55 @@ impl Server {
         }
         let new_path = parts.join("/");
         let path_prefix = self.args.path_prefix.as_str();
-        if path_prefix.is_empty() {
-            return Some(new_path);
+        let new_path = if path_prefix.is_empty() {
+            new_path
+        } else {
+            new_path
+                .strip_prefix(path_prefix.trim_start_matches('/'))
+                .map(|v| v.trim_matches('/').to_string())?
+        };
+        Some(self.args.apply_alias(new_path))
+    }
+
+    /// The path, below `--path-prefix`, that the UI shows for `path`, through the alias that
+    /// reaches it when there is one, so breadcrumbs and `..` follow the URL being browsed.
+    fn href_of(&self, path: &Path) -> Result<String> {
+        let path = normalize_path(path.strip_prefix(&self.args.serve_path)?);
+        for alias in self.args.alias.iter().filter(|v| !v.to.is_empty()) {
+            if let Some(rest) = path.strip_prefix(&alias.to) {
+                if rest.is_empty() || rest.starts_with('/') {
+                    return Ok(format!("/{}{rest}", alias.from));
+                }
+            }
+        }
+        Ok(format!("/{path}"))
+    }
+
+    /// The full URL of a directory given relative to the served root, as the breadcrumbs build it.
+    fn prefixed_href(&self, dir: &str) -> String {
+        match dir {
+            "" => self.args.uri_prefix.clone(),
+            _ => format!("{}{}/", self.args.uri_prefix, encode_uri(dir)),
+        }
+    }
+
+    fn validate_path(&self, path: &str, res: &mut Response) -> bool {
+        if path.len() > self.args.max_path_length {
+            *res.status_mut() = StatusCode::URI_TOO_LONG;
+            *res.body_mut() = body_full("Path Too Long");
+            return false;
+        }
+        if path.split('/').filter(|v| !v.is_empty()).count() > self.args.max_path_depth {
+            status_bad_request(res, "Path Too Deep");
+            return false;
+        }
+        if cfg!(windows) {
+            if let Some(name) = path.split('/').find(|v| is_windows_reserved_name(v)) {
+                status_bad_request(res, &format!("Invalid file name `{name}` on Windows"));
+                return false;
+            }
         }
-        new_path
-            .strip_prefix(path_prefix.trim_start_matches('/'))
-            .map(|v| v.trim_matches('/').to_string())
+        true
     }
 
     fn join_path(&self, path: &str) -> Option<PathBuf> {
@@ -1314,36 +3468,108 @@ impl Server {
         Some(self.args.serve_path.join(path))
     }
 
+    /// The flag is set when the listing stopped early at `--max-list-entries`,
+    /// that is when another listable entry was left out.
     async fn list_dir(
         &self,
         entry_path: &Path,
         base_path: &Path,
         access_paths: AccessPaths,
-    ) -> Result<Vec<PathItem>> {
+    ) -> Result<(Vec<PathItem>, bool)> {
+        let max_entries = self.args.max_list_entries.unwrap_or(usize::MAX);
         let mut paths: Vec<PathItem> = vec![];
         if access_paths.perm().indexonly() {
             for name in access_paths.child_names() {
                 let entry_path = entry_path.join(name);
-                self.add_pathitem(&mut paths, base_path, &entry_path).await;
+                let Some(item) = self.listable_pathitem(base_path, &entry_path).await else {
+                    continue;
+                };
+                if paths.len() >= max_entries {
+                    return Ok((paths, true));
+                }
+                paths.push(item);
             }
         } else {
             let mut rd = fs::read_dir(entry_path).await?;
             while let Ok(Some(entry)) = rd.next_entry().await {
                 let entry_path = entry.path();
-                self.add_pathitem(&mut paths, base_path, &entry_path).await;
+                let Some(item) = self.listable_pathitem(base_path, &entry_path).await else {
+                    continue;
+                };
+                if paths.len() >= max_entries {
+                    return Ok((paths, true));
+                }
+                paths.push(item);
             }
         }
-        Ok(paths)
+        Ok((paths, false))
     }
 
-    async fn add_pathitem(&self, paths: &mut Vec<PathItem>, base_path: &Path, entry_path: &Path) {
-        let base_name = get_file_name(entry_path);
-        if let Ok(Some(item)) = self.to_pathitem(entry_path, base_path).await {
-            if is_hidden(&self.args.hidden, base_name, item.is_dir()) {
-                return;
+    /// List `entry_path` and its descendants down to `depth` levels, breadth first.
+    ///
+    /// Symlinked directories and directories guarded by their own `.dufs-auth` are listed
+    /// but not descended into. The flag is set when any of the listings was truncated.
+    async fn list_dir_deep(
+        &self,
+        entry_path: &Path,
+        access_paths: AccessPaths,
+        depth: usize,
+    ) -> Result<(Vec<PathItem>, bool)> {
+        let base_path = &self.args.serve_path;
+        let mut paths = vec![];
+        let mut queue = VecDeque::new();
+        let (mut children, mut truncated) = self
+            .list_dir(entry_path, base_path, access_paths.clone())
+            .await?;
+        let mut current = (entry_path.to_path_buf(), access_paths, 1);
+        loop {
+            let (dir, access_paths, level) = &current;
+            if *level < depth {
+                for item in &children {
+                    if item.path_type != PathType::Dir {
+                        continue;
+                    }
+                    let child_access_paths = match access_paths.find(item.base_name()) {
+                        Some(v) => v,
+                        None => continue,
+                    };
+                    let child = dir.join(item.base_name());
+                    if self.args.dir_auth
+                        && fs::metadata(child.join(DIR_AUTH_FILE))
+                            .await
+                            .is_ok_and(|v| v.is_file())
+                    {
+                        continue;
+                    }
+                    queue.push_back((child, child_access_paths, level + 1));
+                }
             }
-            paths.push(item);
+            paths.extend(children);
+            current = match queue.pop_front() {
+                Some(v) => v,
+                None => break,
+            };
+            let child_truncated;
+            (children, child_truncated) = self
+                .list_dir(&current.0, base_path, current.1.clone())
+                .await
+                .unwrap_or_default();
+            truncated |= child_truncated;
+        }
+        Ok((paths, truncated))
+    }
+
+    /// The listing entry for `entry_path`, unless it is hidden or otherwise not listed.
+    async fn listable_pathitem(&self, base_path: &Path, entry_path: &Path) -> Option<PathItem> {
+        let base_name = get_file_name(entry_path);
+        if cfg!(windows) && is_windows_reserved_name(base_name) {
+            return None;
         }
+        let item = self.to_pathitem(entry_path, base_path).await.ok()??;
+        if is_hidden(&self.args.hidden_matcher, entry_path, item.is_dir()) {
+            return None;
+        }
+        Some(item)
     }
 
     async fn to_pathitem<P: AsRef<Path>>(&self, path: P, base_path: P) -> Result<Option<PathItem>> {
@@ -1368,13 +3594,12 @@ impl Server {
                 let mut entries = tokio::fs::read_dir(&path).await?;
                 while let Some(entry) = entries.next_entry().await? {
                     let entry_path = entry.path();
-                    let base_name = get_file_name(&entry_path);
                     let is_dir = entry
                         .file_type()
                         .await
                         .map(|v| v.is_dir())
                         .unwrap_or_default();
-                    if is_hidden(&self.args.hidden, base_name, is_dir) {
+                    if is_hidden(&self.args.hidden_matcher, &entry_path, is_dir) {
                         continue;
                     }
                     count += 1;
@@ -1383,6 +3608,22 @@ impl Server {
             }
             PathType::File | PathType::SymlinkFile => meta.len(),
         };
+        let human_size = match path_type {
+            PathType::File | PathType::SymlinkFile => self.args.size_units.map(|v| v.format(size)),
+            PathType::Dir | PathType::SymlinkDir => None,
+        };
+        let is_recent = self.args.recent_window.is_some_and(|window| {
+            let now = to_timestamp(&SystemTime::now());
+            mtime.saturating_add(window.saturating_mul(1000)) >= now
+        });
+        let symlink_target = if is_symlink && self.args.show_symlink_target {
+            fs::read_link(path)
+                .await
+                .ok()
+                .map(|v| v.to_string_lossy().to_string())
+        } else {
+            None
+        };
         let rel_path = path.strip_prefix(base_path)?;
         let name = normalize_path(rel_path);
         Ok(Some(PathItem {
@@ -1390,6 +3631,11 @@ impl Server {
             name,
             mtime,
             size,
+            human_size,
+            is_recent,
+            symlink_target,
+            snippet: None,
+            dead_props: vec![],
         }))
     }
 }
@@ -1404,15 +3650,25 @@ enum DataKind {
 #[derive(Debug, Serialize)]
 struct IndexData {
     href: String,
+    parent_href: Option<String>,
     kind: DataKind,
     uri_prefix: String,
     allow_upload: bool,
     allow_delete: bool,
     allow_search: bool,
     allow_archive: bool,
+    allow_symlink: bool,
     dir_exists: bool,
     auth: bool,
     user: Option<String>,
+    lang: String,
+    sort: Option<String>,
+    order: Option<String>,
+    page: usize,
+    per_page: usize,
+    total_count: usize,
+    total_size: u64,
+    truncated: bool,
     paths: Vec<PathItem>,
 }
 
@@ -1425,6 +3681,7 @@ struct EditData {
     allow_delete: bool,
     auth: bool,
     user: Option<String>,
+    lang: String,
     editable: bool,
 }
 
@@ -1434,6 +3691,17 @@ struct PathItem {
     name: String,
     mtime: u64,
     size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    human_size: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    is_recent: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symlink_target: Option<String>,
+    /// The first matching line of a `?grep=` search
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snippet: Option<String>,
+    #[serde(skip)]
+    dead_props: Vec<DeadProp>,
 }
 
 impl PathItem {
@@ -1441,7 +3709,29 @@ impl PathItem {
         self.path_type == PathType::Dir || self.path_type == PathType::SymlinkDir
     }
 
-    pub fn to_dav_xml(&self, prefix: &str) -> String {
+    /// `name\tsize\tmtime` with a `/` after directory names, control characters escaped.
+    pub fn to_plain_line(&self) -> String {
+        let mut name = String::with_capacity(self.name.len());
+        for c in self.name.chars() {
+            match c {
+                '\\' => name.push_str("\\\\"),
+                '\t' => name.push_str("\\t"),
+                '\n' => name.push_str("\\n"),
+                '\r' => name.push_str("\\r"),
+                c => name.push(c),
+            }
+        }
+        if self.is_dir() {
+            name.push('/');
+        }
+        let mtime = match Utc.timestamp_millis_opt(self.mtime as i64) {
+            LocalResult::Single(v) => v.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            _ => String::new(),
+        };
+        format!("{name}\t{}\t{mtime}\n", self.size)
+    }
+
+    pub fn to_dav_xml(&self, prefix: &str, propfind: &PropFind) -> String {
         let mtime = match Utc.timestamp_millis_opt(self.mtime as i64) {
             LocalResult::Single(v) => format!("{}", v.format("%a, %d %b %Y %H:%M:%S GMT")),
             _ => String::new(),
@@ -1450,63 +3740,431 @@ impl PathItem {
         if self.is_dir() && !href.ends_with('/') {
             href.push('/');
         }
-        let displayname = escape_str_pcdata(self.base_name());
-        match self.path_type {
-            PathType::Dir | PathType::SymlinkDir => format!(
-                r#"<D:response>
-<D:href>{href}</D:href>
-<D:propstat>
-<D:prop>
-<D:displayname>{displayname}</D:displayname>
-<D:getlastmodified>{mtime}</D:getlastmodified>
-<D:resourcetype><D:collection/></D:resourcetype>
-</D:prop>
-<D:status>HTTP/1.1 200 OK</D:status>
-</D:propstat>
-</D:response>"#
-            ),
-            PathType::File | PathType::SymlinkFile => format!(
-                r#"<D:response>
-<D:href>{href}</D:href>
-<D:propstat>
-<D:prop>
-<D:displayname>{displayname}</D:displayname>
-<D:getcontentlength>{}</D:getcontentlength>
-<D:getlastmodified>{mtime}</D:getlastmodified>
-<D:resourcetype></D:resourcetype>
-</D:prop>
-<D:status>HTTP/1.1 200 OK</D:status>
-</D:propstat>
-</D:response>"#,
-                self.size
-            ),
+        let dav = |name: &str, value: String| {
+            (
+                PropName::new(DAV_NS, name),
+                format!("<D:{name}>{value}</D:{name}>"),
+            )
+        };
+        let mut props = vec![dav(
+            "displayname",
+            escape_str_pcdata(self.base_name()).to_string(),
+        )];
+        if !self.is_dir() {
+            props.push(dav("getcontentlength", self.size.to_string()));
+        }
+        props.push(dav("getlastmodified", mtime));
+        if self.is_dir() {
+            props.push(dav("resourcetype", "<D:collection/>".into()));
+        } else {
+            props.push(dav("resourcetype", String::new()));
+            // by extension only, PROPFIND doesn't open every listed file
+            let content_type = mime_guess::from_path(&self.name).first_or_octet_stream();
+            props.push(dav("getcontenttype", content_type.to_string()));
+        }
+        props.push((
+            PropName::new(DAV_NS, "supportedlock"),
+            DAV_SUPPORTED_LOCK.to_string(),
+        ));
+        if let PathType::SymlinkDir | PathType::SymlinkFile = self.path_type {
+            props.push((
+                PropName::new(DUFS_DAV_NS, "symlink"),
+                format!(
+                    "<dufs:symlink xmlns:dufs=\"{DUFS_DAV_NS}\">{}</dufs:symlink>",
+                    escape_str_pcdata(self.symlink_target.as_deref().unwrap_or_default())
+                ),
+            ));
         }
+        props.extend(
+            self.dead_props
+                .iter()
+                .map(|v| (PropName::new(&v.ns, &v.name), v.to_xml())),
+        );
+        format!(
+            r#"<D:response>
+<D:href>{href}</D:href>{}
+</D:response>"#,
+            propfind.propstats(&props)
+        )
     }
 
     pub fn base_name(&self) -> &str {
-        self.name.split('/').last().unwrap_or_default()
+        self.name.split('/').next_back().unwrap_or_default()
+    }
+
+    pub fn group_by_type(&self, other: &Self, group_dirs: GroupDirs) -> Ordering {
+        match group_dirs {
+            GroupDirs::First => self.path_type.cmp(&other.path_type),
+            GroupDirs::Last => other.path_type.cmp(&self.path_type),
+            GroupDirs::None => Ordering::Equal,
+        }
+    }
+
+    pub fn sort_by_name(&self, other: &Self, case_sensitive: bool) -> Ordering {
+        self.compare_name(other, case_sensitive)
+    }
+
+    pub fn sort_by_mtime(&self, other: &Self, case_sensitive: bool) -> Ordering {
+        self.mtime
+            .cmp(&other.mtime)
+            .then_with(|| self.compare_name(other, case_sensitive))
+    }
+
+    pub fn sort_by_size(&self, other: &Self, case_sensitive: bool) -> Ordering {
+        self.size
+            .cmp(&other.size)
+            .then_with(|| self.compare_name(other, case_sensitive))
+    }
+
+    /// Natural order, so `File2` sorts before `File10`
+    fn compare_name(&self, other: &Self, case_sensitive: bool) -> Ordering {
+        let exact = || alphanumeric_sort::compare_str(&self.name, &other.name);
+        if case_sensitive {
+            exact()
+        } else {
+            alphanumeric_sort::compare_str(self.name.to_lowercase(), other.name.to_lowercase())
+                .then_with(exact)
+        }
+    }
+}
+
+/// Uploads are tracked per user, so ids chosen by different users never collide.
+type UploadKey = (Option<String>, String);
+
+#[derive(Debug, Clone, Serialize)]
+struct UploadProgress {
+    #[serde(skip)]
+    path: String,
+    received: u64,
+    total: Option<u64>,
+    percent: Option<f64>,
+}
+
+/// Records progress of an in-flight upload, the entry is dropped once the upload ends.
+struct UploadTracker<'a> {
+    uploads: &'a Mutex<HashMap<UploadKey, UploadProgress>>,
+    key: UploadKey,
+}
+
+impl<'a> UploadTracker<'a> {
+    /// `None` while the user already has an upload in flight under `id`.
+    fn new(
+        uploads: &'a Mutex<HashMap<UploadKey, UploadProgress>>,
+        (user, path): (Option<String>, String),
+        id: &str,
+        total: Option<u64>,
+    ) -> Option<Self> {
+        let key = (user, id.to_string());
+        let mut uploads_guard = uploads.lock().unwrap();
+        if uploads_guard.contains_key(&key) {
+            return None;
+        }
+        let progress = UploadProgress {
+            path,
+            received: 0,
+            total,
+            percent: total.map(|_| 0.0),
+        };
+        uploads_guard.insert(key.clone(), progress);
+        Some(Self { uploads, key })
+    }
+
+    fn advance(&self, len: u64) {
+        if let Some(progress) = self.uploads.lock().unwrap().get_mut(&self.key) {
+            progress.received += len;
+            progress.percent = progress.total.map(|total| match total {
+                0 => 100.0,
+                _ => (progress.received as f64 * 100.0 / total as f64).min(100.0),
+            });
+        }
+    }
+}
+
+impl Drop for UploadTracker<'_> {
+    fn drop(&mut self) {
+        self.uploads.lock().unwrap().remove(&self.key);
+    }
+}
+
+/// One of a client's concurrent upload slots, released on drop.
+struct UploadSlot<'a> {
+    slots: &'a Mutex<HashMap<Option<IpAddr>, usize>>,
+    ip: Option<IpAddr>,
+}
+
+impl<'a> UploadSlot<'a> {
+    fn acquire(
+        slots: &'a Mutex<HashMap<Option<IpAddr>, usize>>,
+        addr: Option<SocketAddr>,
+        max: usize,
+    ) -> Option<Self> {
+        let ip = addr.map(|v| v.ip());
+        let mut slots_guard = slots.lock().unwrap();
+        let count = slots_guard.entry(ip).or_default();
+        if *count >= max {
+            return None;
+        }
+        *count += 1;
+        Some(Self { slots, ip })
+    }
+}
+
+impl Drop for UploadSlot<'_> {
+    fn drop(&mut self) {
+        let mut slots = self.slots.lock().unwrap();
+        if let Some(count) = slots.get_mut(&self.ip) {
+            *count -= 1;
+            if *count == 0 {
+                slots.remove(&self.ip);
+            }
+        }
+    }
+}
+
+/// How authentication decided a request, for the `$auth_rule` and `$auth_result` log variables.
+#[derive(Debug, Clone)]
+struct AuthOutcome {
+    rule: Option<String>,
+    result: &'static str,
+}
+
+/// An exclusive write lock taken with LOCK, keyed by path in `Server::locks`.
+#[derive(Debug, Clone)]
+struct LockInfo {
+    token: String,
+    expires: Instant,
+}
+
+/// A generated `/sitemap.xml` for one base url, reused while the root mtime matches and it is
+/// younger than `SITEMAP_TTL`.
+#[derive(Debug, Clone)]
+struct Sitemap {
+    mtime: Option<SystemTime>,
+    created: Instant,
+    generated: SystemTime,
+    etag: ETag,
+    body: Bytes,
+}
+
+type ZipJobs = Arc<Mutex<HashMap<String, Arc<ZipJob>>>>;
+
+static ZIP_SPOOL_ID: AtomicU64 = AtomicU64::new(0);
+
+/// An archive being built into a spool file, shared by concurrent requests for the same directory.
+struct ZipJob {
+    spool: PathBuf,
+    progress: watch::Receiver<ZipProgress>,
+    readers: AtomicUsize,
+}
+
+impl ZipJob {
+    fn is_expired(&self, ttl: Duration) -> bool {
+        self.progress
+            .borrow()
+            .finished_at
+            .is_some_and(|v| v.elapsed() >= ttl)
+    }
+}
+
+impl Drop for ZipJob {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.spool);
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct ZipProgress {
+    len: u64,
+    state: ZipState,
+    finished_at: Option<Instant>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum ZipState {
+    #[default]
+    Running,
+    Done,
+    Failed,
+}
+
+/// Follows a zip job's spool file from the start, waiting for more bytes while it is built.
+struct ZipJobReader {
+    file: Option<fs::File>,
+    progress: watch::Receiver<ZipProgress>,
+    pos: u64,
+    finished: bool,
+    job: Arc<ZipJob>,
+}
+
+impl ZipJobReader {
+    /// Must be called with the zip jobs lock held, so an abandoned job is never joined.
+    fn attach(job: Arc<ZipJob>) -> Self {
+        job.readers.fetch_add(1, atomic::Ordering::SeqCst);
+        Self {
+            file: None,
+            progress: job.progress.clone(),
+            pos: 0,
+            finished: false,
+            job,
+        }
+    }
+
+    async fn next_chunk(&mut self) -> Result<Option<Bytes>> {
+        if self.finished {
+            return Ok(None);
+        }
+        let ret = self.read_chunk().await;
+        if !matches!(ret, Ok(Some(_))) {
+            self.finished = true;
+        }
+        ret
+    }
+
+    async fn read_chunk(&mut self) -> Result<Option<Bytes>> {
+        loop {
+            let progress = *self.progress.borrow_and_update();
+            if self.pos < progress.len {
+                let file = match self.file.as_mut() {
+                    Some(v) => v,
+                    None => self.file.insert(fs::File::open(&self.job.spool).await?),
+                };
+                let size = (progress.len - self.pos).min(BUF_SIZE as u64) as usize;
+                let mut buf = vec![0; size];
+                file.read_exact(&mut buf).await?;
+                self.pos += size as u64;
+                return Ok(Some(buf.into()));
+            }
+            match progress.state {
+                ZipState::Done => return Ok(None),
+                ZipState::Failed => bail!("Failed to build zip"),
+                ZipState::Running => {}
+            }
+            if self.progress.changed().await.is_err()
+                && self.progress.borrow().state == ZipState::Running
+            {
+                bail!("Zip job went away");
+            }
+        }
+    }
+}
+
+impl Drop for ZipJobReader {
+    fn drop(&mut self) {
+        self.job.readers.fetch_sub(1, atomic::Ordering::SeqCst);
+    }
+}
+
+/// Copy the archive into the spool file, publishing its length as it grows.
+///
+/// Returns `Ok(false)` when every reader has gone away and the job was abandoned,
+/// which only happens if `abandonable`, cached archives are always finished.
+async fn spool_zip(
+    mut reader: DuplexStream,
+    mut file: fs::File,
+    progress: &watch::Sender<ZipProgress>,
+    job: &Arc<ZipJob>,
+    zip_jobs: &ZipJobs,
+    key: &str,
+    abandonable: bool,
+) -> Result<bool> {
+    let mut buf = vec![0; BUF_SIZE];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(true);
+        }
+        file.write_all(&buf[..n]).await?;
+        file.flush().await?;
+        progress.send_modify(|v| v.len += n as u64);
+        if abandonable && job.readers.load(atomic::Ordering::SeqCst) == 0 {
+            let mut zip_jobs = zip_jobs.lock().unwrap();
+            if job.readers.load(atomic::Ordering::SeqCst) == 0 {
+                remove_zip_job(&mut zip_jobs, key, job);
+                return Ok(false);
+            }
+        }
+    }
+}
+
+fn remove_zip_job(zip_jobs: &mut HashMap<String, Arc<ZipJob>>, key: &str, job: &Arc<ZipJob>) {
+    if zip_jobs.get(key).is_some_and(|v| Arc::ptr_eq(v, job)) {
+        zip_jobs.remove(key);
+    }
+}
+
+/// Checksum from `Content-MD5` or an RFC 3230 `Digest` header, verified against the upload body.
+enum UploadDigest {
+    Md5(md5::Context, Vec<u8>),
+    Sha256(Sha256, Vec<u8>),
+}
+
+impl UploadDigest {
+    fn from_headers(headers: &HeaderMap<HeaderValue>) -> Result<Option<Self>> {
+        let decode = |v: &str| {
+            STANDARD
+                .decode(v.trim())
+                .map_err(|_| anyhow!("Invalid Digest"))
+        };
+        if let Some(value) = headers.get("digest") {
+            let mut md5_value = None;
+            for item in value.to_str()?.split(',') {
+                let Some((alg, value)) = item.split_once('=') else {
+                    continue;
+                };
+                match alg.trim().to_ascii_lowercase().as_str() {
+                    "sha-256" => return Ok(Some(Self::Sha256(Sha256::new(), decode(value)?))),
+                    "md5" => md5_value = Some(decode(value)?),
+                    _ => {}
+                }
+            }
+            if let Some(expect) = md5_value {
+                return Ok(Some(Self::Md5(md5::Context::new(), expect)));
+            }
+        }
+        if let Some(value) = headers.get("content-md5") {
+            let expect = decode(value.to_str()?)?;
+            return Ok(Some(Self::Md5(md5::Context::new(), expect)));
+        }
+        Ok(None)
     }
 
-    pub fn sort_by_name(&self, other: &Self) -> Ordering {
-        match self.path_type.cmp(&other.path_type) {
-            Ordering::Equal => {
-                alphanumeric_sort::compare_str(self.name.to_lowercase(), other.name.to_lowercase())
-            }
-            v => v,
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Md5(ctx, _) => ctx.consume(data),
+            Self::Sha256(hasher, _) => hasher.update(data),
         }
     }
 
-    pub fn sort_by_mtime(&self, other: &Self) -> Ordering {
-        match self.path_type.cmp(&other.path_type) {
-            Ordering::Equal => self.mtime.cmp(&other.mtime),
-            v => v,
-        }
+    /// Names the expected and actual digests when the body doesn't match.
+    fn mismatch(self) -> Option<String> {
+        let (expect, actual) = match self {
+            Self::Md5(ctx, expect) => (expect, ctx.compute().0.to_vec()),
+            Self::Sha256(hasher, expect) => (expect, hasher.finalize().to_vec()),
+        };
+        (expect != actual).then(|| {
+            format!(
+                "Digest Mismatch: expected {}, got {}",
+                STANDARD.encode(expect),
+                STANDARD.encode(actual)
+            )
+        })
     }
+}
+
+/// One level of a directory in the minimal shape a lazily expanded tree view needs.
+#[derive(Debug, Serialize)]
+struct TreeNode<'a> {
+    name: &'a str,
+    is_dir: bool,
+    size: u64,
+}
 
-    pub fn sort_by_size(&self, other: &Self) -> Ordering {
-        match self.path_type.cmp(&other.path_type) {
-            Ordering::Equal => self.size.cmp(&other.size),
-            v => v,
+impl<'a> From<&'a PathItem> for TreeNode<'a> {
+    fn from(item: &'a PathItem) -> Self {
+        Self {
+            name: &item.name,
+            is_dir: item.is_dir(),
+            size: item.size,
         }
     }
 }
@@ -1552,40 +4210,211 @@ fn normalize_path<P: AsRef<Path>>(path: P) -> String {
     }
 }
 
-async fn ensure_path_parent(path: &Path) -> Result<()> {
+/// Removes what a failed whole-file upload left, the name it claimed for `If-None-Match: *` included.
+async fn discard_upload(temp_path: &Path, claimed: Option<&Path>) {
+    let _ = fs::remove_file(temp_path).await;
+    if let Some(path) = claimed {
+        let _ = fs::remove_file(path).await;
+    }
+}
+
+/// A hidden sibling of `path` that a write is staged in before it is renamed into place.
+fn upload_temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|v| v.to_string_lossy())
+        .unwrap_or_default();
+    path.with_file_name(format!(
+        ".{name}{UPLOAD_TEMP_MARKER}{}",
+        Uuid::new_v4().simple()
+    ))
+}
+
+/// Whether `name` is one of the staging siblings `upload_temp_path` makes.
+fn is_upload_temp_name(name: &str) -> bool {
+    name.starts_with('.') && name.contains(UPLOAD_TEMP_MARKER)
+}
+
+/// Renames `src` to `dest`, setting an existing `dest` aside first so that a failed rename puts
+/// it back rather than leaving the destination gone.
+async fn replace_dest(src: &Path, dest: &Path) -> Result<()> {
+    let backup_path = upload_temp_path(dest);
+    let backup = match fs::rename(dest, &backup_path).await {
+        Ok(()) => Some(backup_path),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+    if let Err(err) = fs::rename(src, dest).await {
+        if let Some(backup) = backup {
+            let _ = fs::rename(&backup, dest).await;
+        }
+        return Err(err.into());
+    }
+    if let Some(backup) = backup {
+        if fs::symlink_metadata(&backup).await?.is_dir() {
+            fs::remove_dir_all(&backup).await?;
+        } else {
+            fs::remove_file(&backup).await?;
+        }
+    }
+    Ok(())
+}
+
+/// The `.dufs-auth` files anywhere below `dir`.
+async fn nested_dir_auths(dir: &Path) -> Result<Vec<PathBuf>> {
+    let dir = dir.to_path_buf();
+    let files = tokio::task::spawn_blocking(move || {
+        WalkDir::new(&dir)
+            .min_depth(1)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry.file_type().is_file()
+                    && is_file_name(&entry.file_name().to_string_lossy(), DIR_AUTH_FILE)
+            })
+            .map(|entry| entry.into_path())
+            .collect()
+    })
+    .await?;
+    Ok(files)
+}
+
+async fn ensure_path_parent(path: &Path, dir_mode: Option<u32>) -> Result<()> {
     if let Some(parent) = path.parent() {
         if fs::symlink_metadata(parent).await.is_err() {
-            fs::create_dir_all(&parent).await?;
+            create_dir_all(parent, dir_mode).await?;
         }
     }
     Ok(())
 }
 
-fn add_cors(res: &mut Response) {
-    res.headers_mut()
-        .typed_insert(AccessControlAllowOrigin::ANY);
+async fn create_dir_all(path: &Path, dir_mode: Option<u32>) -> Result<()> {
+    let mut created = vec![];
+    if dir_mode.is_some() {
+        for ancestor in path.ancestors() {
+            if fs::symlink_metadata(ancestor).await.is_ok() {
+                break;
+            }
+            created.push(ancestor.to_path_buf());
+        }
+    }
+    fs::create_dir_all(path).await?;
+    for dir in created {
+        set_mode(&dir, dir_mode).await?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn set_mode(path: &Path, mode: Option<u32>) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    if let Some(mode) = mode {
+        fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).await?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn set_mode(_path: &Path, _mode: Option<u32>) -> Result<()> {
+    Ok(())
+}
+
+/// Without `--cors-origin` any origin is allowed, but never with credentials since
+/// browsers reject `*` alongside them. With it, a listed request origin is echoed back.
+fn add_cors(
+    res: &mut Response,
+    args: &Args,
+    origin: Option<&HeaderValue>,
+    request_headers: Option<&HeaderValue>,
+) {
+    let credentials = if args.cors_origin.is_empty() {
+        res.headers_mut()
+            .typed_insert(AccessControlAllowOrigin::ANY);
+        false
+    } else {
+        add_vary(res, "Origin");
+        let origin = origin.filter(|origin| {
+            args.cors_origin
+                .iter()
+                .any(|v| v.as_bytes().eq_ignore_ascii_case(origin.as_bytes()))
+        });
+        let Some(origin) = origin else {
+            return;
+        };
+        res.headers_mut()
+            .insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+        res.headers_mut()
+            .typed_insert(AccessControlAllowCredentials);
+        true
+    };
+    // `*` is taken literally in credentialed requests
+    let methods = if !args.cors_methods.is_empty() {
+        HeaderValue::from_str(&args.cors_methods.join(",")).ok()
+    } else if credentials {
+        HeaderValue::from_str(&allowed_methods(args)).ok()
+    } else {
+        Some(HeaderValue::from_static("*"))
+    };
+    if let Some(methods) = methods {
+        res.headers_mut()
+            .insert(ACCESS_CONTROL_ALLOW_METHODS, methods);
+    }
+    let allow_headers = match request_headers {
+        Some(v) if credentials => v.clone(),
+        _ => HeaderValue::from_static("Authorization,*"),
+    };
     res.headers_mut()
-        .typed_insert(AccessControlAllowCredentials);
-    res.headers_mut().insert(
-        "Access-Control-Allow-Methods",
-        HeaderValue::from_static("*"),
-    );
-    res.headers_mut().insert(
-        "Access-Control-Allow-Headers",
-        HeaderValue::from_static("Authorization,*"),
-    );
+        .insert(ACCESS_CONTROL_ALLOW_HEADERS, allow_headers);
     res.headers_mut().insert(
-        "Access-Control-Expose-Headers",
+        ACCESS_CONTROL_EXPOSE_HEADERS,
         HeaderValue::from_static("Authorization,*"),
     );
 }
 
+/// Adds `name` to the `Vary` header unless it is already listed.
+fn add_vary(res: &mut Response, name: &'static str) {
+    let headers = res.headers_mut();
+    let listed = headers
+        .get_all(VARY)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|v| v.trim() == "*" || v.trim().eq_ignore_ascii_case(name));
+    if !listed {
+        headers.append(VARY, HeaderValue::from_static(name));
+    }
+}
+
+/// The body of a PROPFIND request, `None` when it can't be read or isn't well-formed.
+/// PROPFIND is open to anyone who can read, so its body is bounded before it is buffered.
+///
+/// On `None` the response is already set, 413 for an oversized body and 400 for an invalid one.
+async fn read_propfind(
+    body: BoxBody<Bytes, anyhow::Error>,
+    res: &mut Response,
+) -> Option<PropFind> {
+    let propfind = match Limited::new(body, PROPFIND_MAX_SIZE).collect().await {
+        Ok(v) => dav_props::parse_propfind(&v.to_bytes()),
+        Err(err) if err.is::<LengthLimitError>() => {
+            status_payload_too_large(res);
+            return None;
+        }
+        Err(_) => None,
+    };
+    if propfind.is_none() {
+        status_bad_request(res, "Invalid Request Body");
+    }
+    propfind
+}
+
 fn res_multistatus(res: &mut Response, content: &str) {
     *res.status_mut() = StatusCode::MULTI_STATUS;
     res.headers_mut().insert(
         "content-type",
         HeaderValue::from_static("application/xml; charset=utf-8"),
     );
+    res.headers_mut()
+        .typed_insert(CacheControl::new().with_no_cache());
     *res.body_mut() = body_full(format!(
         r#"<?xml version="1.0" encoding="utf-8" ?>
 <D:multistatus xmlns:D="DAV:">
@@ -1594,17 +4423,34 @@ fn res_multistatus(res: &mut Response, content: &str) {
     ));
 }
 
+/// The latest mtime of `dir` and everything below it.
+async fn tree_mtime(dir: &Path) -> Result<Option<SystemTime>> {
+    let dir = dir.to_path_buf();
+    let mtime = tokio::task::spawn_blocking(move || {
+        WalkDir::new(dir)
+            .into_iter()
+            .filter_map(|entry| entry.ok()?.metadata().ok()?.modified().ok())
+            .max()
+    })
+    .await?;
+    Ok(mtime)
+}
+
+/// Everything below `dir`, or with a `selection` only the entries it names, relative to `dir`.
 async fn zip_dir<W: AsyncWrite + Unpin>(
     writer: &mut W,
     dir: &Path,
+    selection: &[PathBuf],
     access_paths: AccessPaths,
-    hidden: &[String],
+    hidden: &HiddenMatcher,
+    dir_auth: bool,
     compression: Compression,
     running: Arc<AtomicBool>,
 ) -> Result<()> {
     let mut writer = ZipFileWriter::with_tokio(writer);
-    let hidden = Arc::new(hidden.to_vec());
+    let hidden = hidden.clone();
     let dir_clone = dir.to_path_buf();
+    let selection = selection.to_vec();
     let zip_paths = tokio::task::spawn_blocking(move || {
         let mut paths: Vec<PathBuf> = vec![];
         for dir in access_paths.entry_paths(&dir_clone) {
@@ -1617,12 +4463,26 @@ async fn zip_dir<W: AsyncWrite + Unpin>(
                 let entry_path = entry.path();
                 let base_name = get_file_name(entry_path);
                 let file_type = entry.file_type();
-                if is_hidden(&hidden, base_name, file_type.is_dir()) {
+                if is_hidden(&hidden, entry_path, file_type.is_dir())
+                    || (cfg!(windows) && is_windows_reserved_name(base_name))
+                    || (dir_auth && file_type.is_dir() && is_dir_auth_protected(entry_path))
+                {
                     if file_type.is_dir() {
                         it.skip_current_dir();
                     }
                     continue;
                 }
+                if !selection.is_empty() {
+                    let relative = entry_path.strip_prefix(&dir_clone).unwrap_or(entry_path);
+                    if !selection.iter().any(|v| relative.starts_with(v)) {
+                        // keep walking only towards a selected entry
+                        if file_type.is_dir() && !selection.iter().any(|v| v.starts_with(relative))
+                        {
+                            it.skip_current_dir();
+                        }
+                        continue;
+                    }
+                }
                 if entry.path().symlink_metadata().is_err() {
                     continue;
                 }
@@ -1641,6 +4501,11 @@ async fn zip_dir<W: AsyncWrite + Unpin>(
             None => continue,
         };
         let (datetime, mode) = get_file_mtime_and_mode(&zip_path).await?;
+        // compressing media again costs CPU and saves next to nothing
+        let compression = match is_precompressed(&zip_path) {
+            true => Compression::Stored,
+            false => compression,
+        };
         let builder = ZipEntryBuilder::new(filename.into(), compression)
             .unix_permissions(mode)
             .last_modification_date(ZipDateTime::from_chrono(&datetime));
@@ -1653,15 +4518,247 @@ async fn zip_dir<W: AsyncWrite + Unpin>(
     Ok(())
 }
 
-fn extract_cache_headers(meta: &Metadata) -> Option<(ETag, LastModified)> {
+/// Writes `dir` as a tar archive with modes and mtimes kept.
+///
+/// Symlinks become link entries while they resolve inside `dir`, and are left out otherwise.
+fn tar_dir<W: std::io::Write>(
+    writer: W,
+    dir: &Path,
+    access_paths: AccessPaths,
+    hidden: &HiddenMatcher,
+    dir_auth: bool,
+    running: Arc<AtomicBool>,
+) -> Result<()> {
+    let mut builder = tar::Builder::new(writer);
+    builder.follow_symlinks(false);
+    let canonical_dir = std::fs::canonicalize(dir)?;
+    for entry_dir in access_paths.entry_paths(dir) {
+        let mut it = WalkDir::new(&entry_dir).into_iter();
+        it.next();
+        while let Some(Ok(entry)) = it.next() {
+            if !running.load(atomic::Ordering::SeqCst) {
+                bail!("Server is shutting down");
+            }
+            let entry_path = entry.path();
+            let base_name = get_file_name(entry_path);
+            let file_type = entry.file_type();
+            if is_hidden(hidden, entry_path, file_type.is_dir())
+                || (cfg!(windows) && is_windows_reserved_name(base_name))
+                || (dir_auth && file_type.is_dir() && is_dir_auth_protected(entry_path))
+            {
+                if file_type.is_dir() {
+                    it.skip_current_dir();
+                }
+                continue;
+            }
+            let Ok(name) = entry_path.strip_prefix(dir) else {
+                continue;
+            };
+            if file_type.is_symlink() {
+                // only links that stay inside the archive, stored relative to the link itself
+                // so they neither leak server paths nor point elsewhere once extracted
+                let Some(target) = std::fs::canonicalize(entry_path)
+                    .ok()
+                    .and_then(|v| v.strip_prefix(&canonical_dir).ok().map(|v| v.to_path_buf()))
+                else {
+                    continue;
+                };
+                let mut link = PathBuf::new();
+                for _ in 1..name.components().count() {
+                    link.push("..");
+                }
+                link.push(target);
+                if link.as_os_str().is_empty() {
+                    link.push(".");
+                }
+                let mut header = tar::Header::new_gnu();
+                header.set_metadata(&entry.metadata()?);
+                header.set_entry_type(tar::EntryType::Symlink);
+                header.set_size(0);
+                builder.append_link(&mut header, name, link)?;
+                continue;
+            } else if !file_type.is_file() && !file_type.is_dir() {
+                continue;
+            }
+            builder.append_path_with_name(entry_path, name)?;
+        }
+    }
+    builder.finish()?;
+    Ok(())
+}
+
+/// Weak tags only promise equivalent content, so If-Match and If-Range, which compare strongly,
+/// never match them and always fall back to the full, unconditional response.
+/// Each content coding gets its own entity tag, the encoded bytes differ from the file's.
+fn extract_cache_headers(meta: &Metadata, weak: bool, tag: &str) -> Option<(ETag, LastModified)> {
     let mtime = meta.modified().ok()?;
-    let timestamp = to_timestamp(&mtime);
-    let size = meta.len();
-    let etag = format!(r#""{timestamp}-{size}""#).parse::<ETag>().ok()?;
+    let prefix = if weak { "W/" } else { "" };
+    let etag = format!("{prefix}{tag}").parse::<ETag>().ok()?;
     let last_modified = LastModified::from(mtime);
     Some((etag, last_modified))
 }
 
+/// The quoted opaque tag built from mtime and size, without the weak prefix.
+fn mtime_etag(meta: &Metadata, encoding: Option<&str>) -> Option<String> {
+    let timestamp = to_timestamp(&meta.modified().ok()?);
+    let size = meta.len();
+    let suffix = encoding.map(|v| format!("-{v}")).unwrap_or_default();
+    Some(format!(r#""{timestamp}-{size}{suffix}""#))
+}
+
+/// If-None-Match takes precedence, If-Modified-Since only applies without it.
+fn is_not_modified(
+    headers: &HeaderMap<HeaderValue>,
+    etag: &ETag,
+    last_modified: LastModified,
+) -> bool {
+    if requests_no_cache(headers) {
+        return false;
+    }
+    match headers.typed_get::<IfNoneMatch>() {
+        Some(if_none_match) => !if_none_match.precondition_passes(etag),
+        None => headers
+            .typed_get::<IfModifiedSince>()
+            .map(|v| !v.is_modified(last_modified.into()))
+            .unwrap_or_default(),
+    }
+}
+
+/// `Cache-Control: no-cache`, or `Pragma: no-cache` without `Cache-Control`, asks for the full body.
+fn requests_no_cache(headers: &HeaderMap<HeaderValue>) -> bool {
+    let has_no_cache = |name: HeaderName| {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .any(|v| v.trim().eq_ignore_ascii_case("no-cache"))
+    };
+    if headers.contains_key(CACHE_CONTROL) {
+        has_no_cache(CACHE_CONTROL)
+    } else {
+        has_no_cache(PRAGMA)
+    }
+}
+
+/// Set validators for a PROPFIND response and turn it into a 304 when the client is up to date.
+///
+/// Not something RFC 4918 defines: the ETag covers the listed entries (name, mtime, size),
+/// so polling clients can skip unchanged multistatus bodies.
+fn propfind_not_modified(
+    headers: &HeaderMap<HeaderValue>,
+    paths: &[PathItem],
+    res: &mut Response,
+) -> bool {
+    let mut ctx = md5::Context::new();
+    let mut mtime = 0;
+    for item in paths {
+        ctx.consume(format!("{}\0{}\0{}\n", item.name, item.mtime, item.size));
+        mtime = mtime.max(item.mtime);
+    }
+    let Ok(etag) = format!(r#""{:x}""#, ctx.compute()).parse::<ETag>() else {
+        return false;
+    };
+    let last_modified =
+        LastModified::from(SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(mtime));
+    res.headers_mut().typed_insert(etag.clone());
+    res.headers_mut().typed_insert(last_modified);
+    if is_not_modified(headers, &etag, last_modified) {
+        *res.status_mut() = StatusCode::NOT_MODIFIED;
+        return true;
+    }
+    false
+}
+
+/// Whether a directory listing should be streamed as NDJSON, by `?ndjson` or `Accept`.
+fn wants_ndjson(query_params: &HashMap<String, String>, headers: &HeaderMap<HeaderValue>) -> bool {
+    if has_query_flag(query_params, "ndjson") {
+        return true;
+    }
+    let Some(accept) = headers.get("accept").and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    parse_quality_values(accept)
+        .into_iter()
+        .any(|(value, quality)| value == "application/x-ndjson" && quality > 0.0)
+}
+
+/// Whether the client ranks `text/plain` above `text/html` in its `Accept` header.
+fn prefers_plain(headers: &HeaderMap<HeaderValue>) -> bool {
+    let Some(accept) = headers.get("accept").and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let mut plain = 0.0;
+    let mut html = 0.0;
+    for (value, quality) in parse_quality_values(accept) {
+        match value.as_str() {
+            "text/plain" => plain = quality,
+            "text/html" => html = quality,
+            _ => {}
+        }
+    }
+    plain > html
+}
+
+/// Whether the client ranks `application/json` above `text/html` in its `Accept` header.
+fn prefers_json(headers: &HeaderMap<HeaderValue>) -> bool {
+    let Some(accept) = headers.get("accept").and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let mut json = 0.0;
+    let mut html = 0.0;
+    for (value, quality) in parse_quality_values(accept) {
+        match value.as_str() {
+            "application/json" => json = quality,
+            "text/html" => html = quality,
+            _ => {}
+        }
+    }
+    json > html
+}
+
+/// Replace the body of an error response with `{"error":"...","status":404}`.
+async fn json_error_body(res: &mut Response) {
+    let status = res.status();
+    let body = std::mem::replace(res.body_mut(), body_full(""));
+    let text = match body.collect().await {
+        Ok(v) => String::from_utf8_lossy(&v.to_bytes()).trim().to_string(),
+        Err(_) => String::new(),
+    };
+    let error = match text.is_empty() {
+        true => status.canonical_reason().unwrap_or_default().to_string(),
+        false => text,
+    };
+    let output = serde_json::json!({ "error": error, "status": status.as_u16() });
+    res.headers_mut().remove(CONTENT_LENGTH);
+    res.headers_mut()
+        .typed_insert(ContentType::from(mime_guess::mime::APPLICATION_JSON));
+    *res.body_mut() = body_full(output.to_string());
+}
+
+/// Map an error escaping from `handle` to a response status.
+///
+/// The filesystem can change between the metadata lookup that routes a request and the
+/// handler that serves it, so io errors raised afterwards are reported by their kind
+/// rather than as internal errors.
+fn error_status(err: &anyhow::Error) -> StatusCode {
+    let kind = err
+        .chain()
+        .find_map(|v| v.downcast_ref::<io::Error>())
+        .map(|v| v.kind());
+    match kind {
+        Some(io::ErrorKind::NotFound) => StatusCode::NOT_FOUND,
+        Some(io::ErrorKind::PermissionDenied) => StatusCode::FORBIDDEN,
+        Some(
+            io::ErrorKind::AlreadyExists
+            | io::ErrorKind::NotADirectory
+            | io::ErrorKind::IsADirectory
+            | io::ErrorKind::DirectoryNotEmpty,
+        ) => StatusCode::CONFLICT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
 fn status_forbid(res: &mut Response) {
     *res.status_mut() = StatusCode::FORBIDDEN;
     *res.body_mut() = body_full("Forbidden");
@@ -1676,6 +4773,101 @@ fn status_no_content(res: &mut Response) {
     *res.status_mut() = StatusCode::NO_CONTENT;
 }
 
+fn status_locked(res: &mut Response) {
+    *res.status_mut() = StatusCode::LOCKED;
+    *res.body_mut() = body_full("Locked");
+}
+
+fn status_payload_too_large(res: &mut Response) {
+    *res.status_mut() = StatusCode::PAYLOAD_TOO_LARGE;
+    *res.body_mut() = body_full("Payload Too Large");
+}
+
+/// Assets compiled into the binary are tiny and sent whole, so ranges are declined up front.
+fn send_embedded_asset(res: &mut Response, body: &'static [u8], content_type: &'static str) {
+    *res.body_mut() = body_full(body);
+    res.headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    res.headers_mut()
+        .insert(ACCEPT_RANGES, HeaderValue::from_static("none"));
+}
+
+/// RFC 4918 tells a new COPY or MOVE destination (201) from a replaced one (204).
+fn status_created_or_replaced(res: &mut Response, replaced: bool) {
+    if replaced {
+        status_no_content(res);
+    } else {
+        *res.status_mut() = StatusCode::CREATED;
+    }
+}
+
+/// The first line of a text file matching `needle`.
+///
+/// Files over `GREP_MAX_FILE_SIZE` and files with a NUL byte are skipped.
+fn grep_file(path: &Path, needle: &Regex) -> Option<String> {
+    if std::fs::metadata(path).ok()?.len() > GREP_MAX_FILE_SIZE {
+        return None;
+    }
+    let data = std::fs::read(path).ok()?;
+    if data.contains(&0) {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&data);
+    let line = text.lines().find(|v| needle.is_match(v))?;
+    Some(line.trim().chars().take(GREP_SNIPPET_MAX_CHARS).collect())
+}
+
+/// Reads `?page=N&per_page=M`, pages start at 1 and `per_page` is capped
+fn parse_pagination(query_params: &HashMap<String, String>) -> Option<(usize, usize)> {
+    let parse = |name: &str| match query_params.get(name) {
+        Some(v) => v.parse::<usize>().ok().filter(|v| *v > 0).map(Some),
+        None => Some(None),
+    };
+    let page = parse("page")?.unwrap_or(1);
+    let per_page = parse("per_page")?
+        .unwrap_or(LIST_PER_PAGE)
+        .min(LIST_MAX_PER_PAGE);
+    Some((page, per_page))
+}
+
+/// RFC 8288 links to the first, previous, next and last pages, `None` when one page holds all.
+fn pagination_link(
+    href: &str,
+    query_params: &HashMap<String, String>,
+    page: usize,
+    per_page: usize,
+    total_count: usize,
+) -> Option<String> {
+    let last = total_count.div_ceil(per_page).max(1);
+    if last == 1 {
+        return None;
+    }
+    let mut params: Vec<_> = query_params
+        .iter()
+        .filter(|(k, _)| *k != "page" && *k != "per_page")
+        .collect();
+    params.sort();
+    let query: String = params
+        .into_iter()
+        .map(|(k, v)| match v.is_empty() {
+            true => format!("{}&", encode_uri(k)),
+            false => format!("{}={}&", encode_uri(k), encode_uri(v)),
+        })
+        .collect();
+    let link = |page: usize, rel: &str| {
+        format!(r#"<{href}?{query}page={page}&per_page={per_page}>; rel="{rel}""#)
+    };
+    let mut links = vec![link(1, "first")];
+    if page > 1 {
+        links.push(link((page - 1).min(last), "prev"));
+    }
+    if page < last {
+        links.push(link(page + 1, "next"));
+    }
+    links.push(link(last, "last"));
+    Some(links.join(", "))
+}
+
 fn status_bad_request(res: &mut Response, body: &str) {
     *res.status_mut() = StatusCode::BAD_REQUEST;
     if !body.is_empty() {
@@ -1708,26 +4900,91 @@ fn set_content_disposition(res: &mut Response, inline: bool, filename: &str) ->
     Ok(())
 }
 
-fn is_hidden(hidden: &[String], file_name: &str, is_dir: bool) -> bool {
-    hidden.iter().any(|v| {
-        if is_dir {
-            if let Some(x) = v.strip_suffix('/') {
-                return glob(x, file_name);
-            }
-        }
-        glob(v, file_name)
-    })
+/// Walks skip subtrees guarded by their own `.dufs-auth`, they need separate credentials.
+fn is_dir_auth_protected(dir: &Path) -> bool {
+    dir.join(DIR_AUTH_FILE).is_file()
 }
 
-fn set_webdav_headers(res: &mut Response) {
-    res.headers_mut().insert(
-        "Allow",
-        HeaderValue::from_static(
-            "GET,HEAD,PUT,OPTIONS,DELETE,PATCH,PROPFIND,COPY,MOVE,CHECKAUTH,LOGOUT",
-        ),
-    );
+/// Whether the last segment of `path` is `name`, ignoring ASCII case since case-insensitive
+/// filesystems such as macOS and Windows resolve both to the same file.
+fn is_file_name(path: &str, name: &str) -> bool {
+    path.rsplit('/')
+        .next()
+        .is_some_and(|v| v.eq_ignore_ascii_case(name))
+}
+
+fn is_hidden(hidden: &HiddenMatcher, path: &Path, is_dir: bool) -> bool {
+    let name = get_file_name(path);
+    is_file_name(name, DAV_PROPS_FILE) || is_upload_temp_name(name) || hidden.is_match(path, is_dir)
+}
+
+/// The methods this server's flags let through, for `Allow` on OPTIONS and on 405 alike,
+/// and for the methods granted to credentialed CORS requests.
+fn allowed_methods(args: &Args) -> String {
+    let (upload, delete) = (args.allow_upload, args.allow_delete);
+    [
+        ("GET", true),
+        ("HEAD", true),
+        ("PUT", upload),
+        ("OPTIONS", true),
+        ("DELETE", delete),
+        ("PATCH", upload),
+        ("PROPFIND", true),
+        ("PROPPATCH", upload),
+        ("COPY", upload),
+        ("MOVE", upload && delete),
+        ("MKCOL", upload),
+        ("LOCK", upload),
+        ("UNLOCK", upload),
+        ("CHECKAUTH", true),
+        ("LOGOUT", true),
+    ]
+    .iter()
+    .filter(|(_, allowed)| *allowed)
+    .map(|(method, _)| *method)
+    .collect::<Vec<_>>()
+    .join(",")
+}
+
+fn set_webdav_headers(res: &mut Response, args: &Args) -> Result<()> {
+    res.headers_mut()
+        .insert(ALLOW, HeaderValue::from_str(&allowed_methods(args))?);
     res.headers_mut()
         .insert("DAV", HeaderValue::from_static("1, 2, 3"));
+    // lets the Windows redirector and Office author over WebDAV
+    res.headers_mut()
+        .insert("MS-Author-Via", HeaderValue::from_static("DAV"));
+    Ok(())
+}
+
+/// `Translate: f` asks for a resource's source rather than its rendered output.
+///
+/// Files are always sent as stored, so this only bypasses the edit/view pages and the SPA fallback.
+fn wants_source(headers: &HeaderMap<HeaderValue>) -> bool {
+    headers
+        .get("translate")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.trim().eq_ignore_ascii_case("f"))
+}
+
+fn is_compressible(content_type: &str) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    essence.starts_with("text/")
+        || essence.ends_with("+json")
+        || essence.ends_with("+xml")
+        || matches!(
+            essence.as_str(),
+            "application/json"
+                | "application/javascript"
+                | "application/xml"
+                | "application/x-ndjson"
+                | "image/svg+xml"
+        )
 }
 
 async fn get_content_type(path: &Path) -> Result<String> {
@@ -1761,18 +5018,229 @@ async fn get_content_type(path: &Path) -> Result<String> {
     Ok(content_type)
 }
 
-fn parse_upload_offset(headers: &HeaderMap<HeaderValue>, size: u64) -> Result<Option<u64>> {
+/// The first `Timeout` value we can honor in seconds, capped at `LOCK_MAX_TIMEOUT`.
+fn parse_lock_timeout(headers: &HeaderMap<HeaderValue>) -> u64 {
+    headers
+        .get("timeout")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| {
+            v.split(',').find_map(|v| {
+                let v = v.trim();
+                if v.eq_ignore_ascii_case("infinite") {
+                    return Some(LOCK_MAX_TIMEOUT);
+                }
+                let (unit, secs) = v.split_once('-')?;
+                if !unit.eq_ignore_ascii_case("second") {
+                    return None;
+                }
+                secs.parse::<u64>().ok().filter(|v| *v > 0)
+            })
+        })
+        .unwrap_or(LOCK_MAX_TIMEOUT)
+        .min(LOCK_MAX_TIMEOUT)
+}
+
+/// Lock tokens the `If` header submits, e.g. `(<opaquelocktoken:...>)`.
+fn submitted_lock_tokens(headers: &HeaderMap<HeaderValue>) -> Vec<String> {
+    headers
+        .get("if")
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_if_header)
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|list| list.conditions)
+        .filter_map(|(not, condition)| match condition {
+            IfCondition::Token(token) if !not => Some(token),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Methods that change a resource, and so honor the WebDAV `If` header.
+fn is_dav_write(method: &Method) -> bool {
+    matches!(
+        method.as_str(),
+        "PUT" | "PATCH" | "DELETE" | "MKCOL" | "COPY" | "MOVE" | "LOCK" | "PROPPATCH"
+    )
+}
+
+/// One parenthesized list of an `If` header, for the tagged resource or the request URI.
+#[derive(Debug, Clone, PartialEq)]
+struct IfList {
+    resource: Option<String>,
+    /// `(negated, condition)` pairs.
+    conditions: Vec<(bool, IfCondition)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum IfCondition {
+    Token(String),
+    ETag(String),
+}
+
+/// Parses the `If` header, RFC 4918 section 10.4, `None` when it is malformed.
+fn parse_if_header(value: &str) -> Option<Vec<IfList>> {
+    let mut lists = vec![];
+    let mut resource: Option<String> = None;
+    let mut tagged = None;
+    let mut rest = value.trim();
+    while !rest.is_empty() {
+        if let Some(v) = rest.strip_prefix('<') {
+            // a header is either all tagged lists or all untagged ones
+            if tagged == Some(false) {
+                return None;
+            }
+            tagged = Some(true);
+            let (tag, v) = v.split_once('>')?;
+            resource = Some(tag.to_string());
+            rest = v.trim_start();
+            if !rest.starts_with('(') {
+                return None;
+            }
+            continue;
+        }
+        let mut v = rest.strip_prefix('(')?;
+        tagged.get_or_insert(false);
+        let mut conditions = vec![];
+        loop {
+            v = v.trim_start();
+            if let Some(after) = v.strip_prefix(')') {
+                v = after;
+                break;
+            }
+            let not = v.get(..3).is_some_and(|p| p.eq_ignore_ascii_case("not"));
+            if not {
+                v = v[3..].trim_start();
+            }
+            let condition = if let Some(after) = v.strip_prefix('<') {
+                let (token, after) = after.split_once('>')?;
+                v = after;
+                IfCondition::Token(token.to_string())
+            } else if let Some(after) = v.strip_prefix('[') {
+                let (etag, after) = after.split_once(']')?;
+                v = after;
+                IfCondition::ETag(etag.to_string())
+            } else {
+                return None;
+            };
+            conditions.push((not, condition));
+        }
+        if conditions.is_empty() {
+            return None;
+        }
+        lists.push(IfList {
+            resource: resource.clone(),
+            conditions,
+        });
+        rest = v.trim_start();
+    }
+    (!lists.is_empty()).then_some(lists)
+}
+
+/// Whether a lock on `lock_path` stands in the way of writing `path`.
+fn is_lock_covered(lock_path: &str, path: &str, deep: bool) -> bool {
+    lock_path == path
+        || (deep
+            && (path.is_empty()
+                || lock_path
+                    .strip_prefix(path)
+                    .is_some_and(|v| v.starts_with('/'))))
+}
+
+/// Target of a partial `PATCH`, as given by SabreDAV's `X-Update-Range` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct UpdateRange {
+    start: u64,
+    /// `None` when the range is open ended and the whole body is written.
+    len: Option<u64>,
+    /// Drop whatever follows `start` before writing, as a resumed PUT does.
+    truncate: bool,
+}
+
+/// The `bytes start-end/total` range of a resumed PUT, `total` may be `*`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct UploadContentRange {
+    start: u64,
+    end: u64,
+    total: Option<u64>,
+}
+
+fn parse_upload_content_range(
+    headers: &HeaderMap<HeaderValue>,
+) -> Result<Option<UploadContentRange>> {
+    let value = match headers.get(CONTENT_RANGE) {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    let err = || anyhow!("Invalid Content-Range Header");
+    let value = value.to_str().map_err(|_| err())?.trim();
+    let (unit, range) = value.split_once(' ').ok_or_else(err)?;
+    if !unit.eq_ignore_ascii_case("bytes") {
+        return Err(err());
+    }
+    let (range, total) = range.trim().split_once('/').ok_or_else(err)?;
+    let (start, end) = range.split_once('-').ok_or_else(err)?;
+    let start = start.trim().parse::<u64>().map_err(|_| err())?;
+    let end = end.trim().parse::<u64>().map_err(|_| err())?;
+    let total = match total.trim() {
+        "*" => None,
+        v => Some(v.parse::<u64>().map_err(|_| err())?),
+    };
+    if end < start || total.is_some_and(|v| end >= v) {
+        return Err(err());
+    }
+    Ok(Some(UploadContentRange { start, end, total }))
+}
+
+fn parse_update_range(headers: &HeaderMap<HeaderValue>, size: u64) -> Result<Option<UpdateRange>> {
     let value = match headers.get("x-update-range") {
         Some(v) => v,
         None => return Ok(None),
     };
     let err = || anyhow!("Invalid X-Update-Range Header");
-    let value = value.to_str().map_err(|_| err())?;
-    if value == "append" {
-        return Ok(Some(size));
+    let value = value.to_str().map_err(|_| err())?.trim();
+    if value.eq_ignore_ascii_case("append") {
+        return Ok(Some(UpdateRange {
+            start: size,
+            len: None,
+            truncate: false,
+        }));
     }
-    let (start, _) = parse_range(value, size).ok_or_else(err)?;
-    Ok(Some(start))
+    let (unit, range) = value.split_once('=').ok_or_else(err)?;
+    if !unit.trim().eq_ignore_ascii_case("bytes") {
+        return Err(err());
+    }
+    let (start, end) = range.split_once('-').ok_or_else(err)?;
+    let (start, end) = (start.trim(), end.trim());
+    let range = if start.is_empty() {
+        // the last N bytes of the file
+        let suffix = end.parse::<u64>().map_err(|_| err())?;
+        if suffix == 0 || suffix > size {
+            return Err(err());
+        }
+        UpdateRange {
+            start: size - suffix,
+            len: Some(suffix),
+            truncate: false,
+        }
+    } else {
+        let start = start.parse::<u64>().map_err(|_| err())?;
+        let len = if end.is_empty() {
+            None
+        } else {
+            let end = end.parse::<u64>().map_err(|_| err())?;
+            if end < start {
+                return Err(err());
+            }
+            Some(end - start + 1)
+        };
+        UpdateRange {
+            start,
+            len,
+            truncate: false,
+        }
+    };
+    Ok(Some(range))
 }
 
 async fn sha256_file(path: &Path) -> Result<String> {
@@ -1792,6 +5260,37 @@ async fn sha256_file(path: &Path) -> Result<String> {
     Ok(format!("{:x}", result))
 }
 
+/// The repeated `path` params of `?zip`, `None` when one isn't a plain path inside the directory.
+fn parse_zip_selection(query: &str) -> Option<Vec<PathBuf>> {
+    let mut selection = vec![];
+    for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+        if key != "path" {
+            continue;
+        }
+        let path = PathBuf::from(value.trim_matches('/'));
+        if path.as_os_str().is_empty()
+            || !path.components().all(|v| matches!(v, Component::Normal(_)))
+        {
+            return None;
+        }
+        selection.push(path);
+    }
+    selection.sort();
+    selection.dedup();
+    Some(selection)
+}
+
+/// `Some(false)` for `?tar`, `Some(true)` for `?tar.gz`.
+fn tar_query(query_params: &HashMap<String, String>) -> Option<bool> {
+    if has_query_flag(query_params, "tar") {
+        Some(false)
+    } else if has_query_flag(query_params, "tar.gz") {
+        Some(true)
+    } else {
+        None
+    }
+}
+
 fn has_query_flag(query_params: &HashMap<String, String>, name: &str) -> bool {
     query_params
         .get(name)
@@ -19,7 +19,7 @@ pub fn encode_uri(v: &str) -> String {
     parts.join("/")
 }
 
-pub fn decode_uri(v: &str) -> Option<Cow<str>> {
+pub fn decode_uri(v: &str) -> Option<Cow<'_, str>> {
     percent_encoding::percent_decode(v.as_bytes())
         .decode_utf8()
         .ok()
@@ -52,14 +52,6 @@ pub fn try_get_file_name(path: &Path) -> Result<&str> {
         .ok_or_else(|| anyhow!("Failed to get file name of `{}`", path.display()))
 }
 
-pub fn glob(pattern: &str, target: &str) -> bool {
-    let pat = match ::glob::Pattern::new(pattern) {
-        Ok(pat) => pat,
-        Err(_) => return false,
-    };
-    pat.matches(target)
-}
-
 // Load public certificate from file.
 #[cfg(feature = "tls")]
 pub fn load_certs<T: AsRef<Path>>(filename: T) -> Result<Vec<CertificateDer<'static>>> {
@@ -100,34 +92,204 @@ pub fn load_private_key<T: AsRef<Path>>(filename: T) -> Result<PrivateKeyDer<'st
     anyhow::bail!("No supported private key in file");
 }
 
-pub fn parse_range(range: &str, size: u64) -> Option<(u64, u64)> {
-    let (unit, range) = range.split_once('=')?;
-    if unit != "bytes" || range.contains(',') {
+/// Parse a header like `Accept` into `(value, quality)` pairs, dropping `q=0` entries.
+pub fn parse_quality_values(header: &str) -> Vec<(String, f32)> {
+    parse_all_quality_values(header)
+        .into_iter()
+        .filter(|(_, quality)| *quality > 0.0)
+        .collect()
+}
+
+/// Like [`parse_quality_values`] but keeps `q=0` entries, which explicitly refuse a value.
+fn parse_all_quality_values(header: &str) -> Vec<(String, f32)> {
+    header
+        .split(',')
+        .filter_map(|part| {
+            let mut params = part.split(';');
+            let value = params.next()?.trim().to_ascii_lowercase();
+            if value.is_empty() {
+                return None;
+            }
+            let mut quality = 1.0;
+            for param in params {
+                if let Some((key, q)) = param.split_once('=') {
+                    if key.trim().eq_ignore_ascii_case("q") {
+                        quality = q.trim().parse::<f32>().ok()?.clamp(0.0, 1.0);
+                    }
+                }
+            }
+            Some((value, quality))
+        })
+        .collect()
+}
+
+/// Languages the web UI has labels for, the first one is the fallback.
+pub const UI_LANGS: &[&str] = &["en", "de", "es", "fr", "ja", "zh"];
+
+/// Pick the web UI language from `Accept-Language`, matching on the primary subtag.
+pub fn select_lang(accept_language: Option<&str>) -> &'static str {
+    let mut selected: Option<(&'static str, f32)> = None;
+    for (value, quality) in parse_quality_values(accept_language.unwrap_or_default()) {
+        let primary = value.split('-').next().unwrap_or_default();
+        let Some(lang) = UI_LANGS.iter().find(|v| **v == primary) else {
+            continue;
+        };
+        if selected.is_none_or(|(_, v)| quality > v) {
+            selected = Some((lang, quality));
+        }
+    }
+    selected.map(|(v, _)| v).unwrap_or(UI_LANGS[0])
+}
+
+/// Pick the content coding to respond with from `candidates` (in server preference order,
+/// `identity` included when uncompressed is an option) according to `Accept-Encoding`.
+///
+/// Returns `None` when the client refuses every candidate, which calls for a 406.
+pub fn select_encoding<'a>(
+    accept_encoding: Option<&str>,
+    candidates: &[&'a str],
+) -> Option<&'a str> {
+    const IDENTITY: &str = "identity";
+    let Some(accept_encoding) = accept_encoding else {
+        // no preference stated, don't risk a coding the client may not understand
+        return candidates
+            .iter()
+            .find(|v| **v == IDENTITY)
+            .or(candidates.first())
+            .copied();
+    };
+    let values: Vec<(String, f32)> = parse_all_quality_values(accept_encoding)
+        .into_iter()
+        .map(|(value, quality)| match value.as_str() {
+            "x-gzip" => ("gzip".to_string(), quality),
+            "x-compress" => ("compress".to_string(), quality),
+            _ => (value, quality),
+        })
+        .collect();
+    let quality_of = |name: &str| {
+        values
+            .iter()
+            .find(|(value, _)| value == name)
+            .or_else(|| values.iter().find(|(value, _)| value == "*"))
+            .map(|(_, quality)| *quality)
+            // identity stays acceptable unless refused, but any listed coding beats it
+            .unwrap_or(if name == IDENTITY { 0.001 } else { 0.0 })
+    };
+    let mut selected: Option<(&str, f32)> = None;
+    for candidate in candidates {
+        let quality = quality_of(candidate);
+        if quality > 0.0 && selected.map(|(_, v)| quality > v).unwrap_or(true) {
+            selected = Some((candidate, quality));
+        }
+    }
+    selected.map(|(v, _)| v)
+}
+
+/// Names Windows can't create or maps to devices: `CON`, `NUL`, `COM1`... (even with an
+/// extension) and names ending with a dot or space.
+pub fn is_windows_reserved_name(name: &str) -> bool {
+    const RESERVED: [&str; 4] = ["CON", "PRN", "AUX", "NUL"];
+    if name.ends_with(['.', ' ']) {
+        return true;
+    }
+    let stem = name.split('.').next().unwrap_or_default().trim_end();
+    let stem = stem.to_ascii_uppercase();
+    if RESERVED.contains(&stem.as_str()) {
+        return true;
+    }
+    match stem
+        .strip_prefix("COM")
+        .or_else(|| stem.strip_prefix("LPT"))
+    {
+        Some(n) => n.len() == 1 && matches!(n.as_bytes()[0], b'1'..=b'9'),
+        None => false,
+    }
+}
+
+/// Media and archives that barely shrink, going by the file extension.
+pub fn is_precompressed(path: &Path) -> bool {
+    let Some(mime) = mime_guess::from_path(path).first() else {
+        return false;
+    };
+    match (mime.type_().as_str(), mime.subtype().as_str()) {
+        ("image", subtype) => !matches!(subtype, "svg" | "bmp" | "tiff"),
+        ("audio", subtype) => !matches!(subtype, "wav" | "x-wav"),
+        ("video", _) => true,
+        ("application", subtype) => matches!(
+            subtype,
+            "zip"
+                | "gzip"
+                | "x-gzip"
+                | "x-bzip2"
+                | "x-xz"
+                | "zstd"
+                | "x-7z-compressed"
+                | "vnd.rar"
+                | "x-rar-compressed"
+                | "java-archive"
+                | "epub+zip"
+        ),
+        _ => false,
+    }
+}
+
+/// The ranges of a `Range` header, sorted, with overlapping and adjacent ones merged.
+///
+/// Unsatisfiable ranges are dropped, `None` when the header is malformed or none is left.
+pub fn parse_ranges(range: &str, size: u64) -> Option<Vec<(u64, u64)>> {
+    let (unit, specs) = range.split_once('=')?;
+    if !unit.trim().eq_ignore_ascii_case("bytes") {
         return None;
     }
-    let (start, end) = range.split_once('-')?;
+    let mut ranges = vec![];
+    for spec in specs.split(',') {
+        let spec = spec.trim();
+        if spec.is_empty() {
+            continue;
+        }
+        ranges.extend(parse_range_spec(spec, size)?);
+    }
+    ranges.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    if merged.is_empty() {
+        None
+    } else {
+        Some(merged)
+    }
+}
+
+/// One `start-end`, `start-` or `-suffix` spec: `None` when malformed, `Some(None)` when unsatisfiable.
+fn parse_range_spec(spec: &str, size: u64) -> Option<Option<(u64, u64)>> {
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
     if start.is_empty() {
         let offset = end.parse::<u64>().ok()?;
-        if offset <= size {
-            Some((size - offset, size - 1))
+        // a suffix longer than the file asks for all of it
+        if offset > 0 && size > 0 {
+            Some(Some((size.saturating_sub(offset), size - 1)))
         } else {
-            None
+            Some(None)
         }
     } else {
         let start = start.parse::<u64>().ok()?;
-        if start < size {
-            if end.is_empty() {
-                Some((start, size - 1))
-            } else {
-                let end = end.parse::<u64>().ok()?;
-                if end < size {
-                    Some((start, end))
-                } else {
-                    None
-                }
-            }
-        } else {
-            None
+        let end = match end {
+            "" => None,
+            v => Some(v.parse::<u64>().ok()?),
+        };
+        if end.is_some_and(|end| end < start) {
+            return None;
+        }
+        // a last byte past the end stands for the end, see RFC 9110 section 14.1.2
+        match end {
+            _ if start >= size => Some(None),
+            Some(end) => Some(Some((start, end.min(size - 1)))),
+            None => Some(Some((start, size - 1))),
         }
     }
 }
@@ -137,38 +299,144 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_glob_key() {
-        assert!(glob("", ""));
-        assert!(glob(".*", ".git"));
-        assert!(glob("abc", "abc"));
-        assert!(glob("a*c", "abc"));
-        assert!(glob("a?c", "abc"));
-        assert!(glob("a*c", "abbc"));
-        assert!(glob("*c", "abc"));
-        assert!(glob("a*", "abc"));
-        assert!(glob("?c", "bc"));
-        assert!(glob("a?", "ab"));
-        assert!(!glob("abc", "adc"));
-        assert!(!glob("abc", "abcd"));
-        assert!(!glob("a?c", "abbc"));
-        assert!(!glob("*.log", "log"));
-        assert!(glob("*.abc-cba", "xyz.abc-cba"));
-        assert!(glob("*.abc-cba", "123.xyz.abc-cba"));
-        assert!(glob("*.log", ".log"));
-        assert!(glob("*.log", "a.log"));
-        assert!(glob("*/", "abc/"));
-        assert!(!glob("*/", "abc"));
+    fn test_parse_quality_values() {
+        assert_eq!(
+            parse_quality_values("text/html, application/json;q=0.9, */*;q=0"),
+            vec![
+                ("text/html".to_string(), 1.0),
+                ("application/json".to_string(), 0.9)
+            ]
+        );
+        assert_eq!(
+            parse_quality_values("Application/JSON ; q=0.5,,"),
+            vec![("application/json".to_string(), 0.5)]
+        );
+        assert_eq!(
+            parse_quality_values("gzip;q=abc, br"),
+            vec![("br".to_string(), 1.0)]
+        );
+    }
+
+    #[test]
+    fn test_select_encoding() {
+        let all = ["br", "gzip", "identity"];
+        assert_eq!(select_encoding(None, &all), Some("identity"));
+        assert_eq!(select_encoding(None, &["gzip"]), Some("gzip"));
+        assert_eq!(select_encoding(Some(""), &all), Some("identity"));
+        assert_eq!(select_encoding(Some("gzip"), &all), Some("gzip"));
+        assert_eq!(select_encoding(Some("GZIP, BR"), &all), Some("br"));
+        assert_eq!(select_encoding(Some("x-gzip"), &all), Some("gzip"));
+        assert_eq!(select_encoding(Some("gzip, br;q=0.8"), &all), Some("gzip"));
+        assert_eq!(
+            select_encoding(Some("gzip;q=0, identity"), &all),
+            Some("identity")
+        );
+        assert_eq!(select_encoding(Some("gzip;q=0"), &all), Some("identity"));
+        assert_eq!(select_encoding(Some("*"), &all), Some("br"));
+        assert_eq!(
+            select_encoding(Some("gzip;q=0.5, *;q=0.8"), &all),
+            Some("br")
+        );
+        assert_eq!(select_encoding(Some("*;q=0"), &all), None);
+        assert_eq!(
+            select_encoding(Some("*;q=0, identity"), &all),
+            Some("identity")
+        );
+        assert_eq!(
+            select_encoding(Some("*;q=0, gzip;q=0.1"), &all),
+            Some("gzip")
+        );
+        assert_eq!(select_encoding(Some("identity;q=0"), &all), None);
+        assert_eq!(
+            select_encoding(Some("identity;q=0, gzip"), &all),
+            Some("gzip")
+        );
+        assert_eq!(select_encoding(Some("br;q=abc, gzip"), &all), Some("gzip"));
+        assert_eq!(
+            select_encoding(Some("gzip;q=1.5, br;q=0.9"), &all),
+            Some("gzip")
+        );
+        assert_eq!(select_encoding(Some("deflate"), &["gzip"]), None);
+    }
+
+    #[test]
+    fn test_select_lang() {
+        assert_eq!(select_lang(None), "en");
+        assert_eq!(select_lang(Some("ko-KR")), "en");
+        assert_eq!(select_lang(Some("zh-CN,zh;q=0.9,en;q=0.8")), "zh");
+        assert_eq!(select_lang(Some("en;q=0.5, DE-at")), "de");
+        assert_eq!(select_lang(Some("fr;q=0, ja;q=0.3")), "ja");
+    }
+
+    #[test]
+    fn test_is_windows_reserved_name() {
+        assert!(is_windows_reserved_name("CON"));
+        assert!(is_windows_reserved_name("nul.txt"));
+        assert!(is_windows_reserved_name("Com1"));
+        assert!(is_windows_reserved_name("LPT9.tar.gz"));
+        assert!(is_windows_reserved_name("file."));
+        assert!(is_windows_reserved_name("file "));
+        assert!(!is_windows_reserved_name("CONSOLE"));
+        assert!(!is_windows_reserved_name("COM0"));
+        assert!(!is_windows_reserved_name("LPT10"));
+        assert!(!is_windows_reserved_name(".hidden"));
+        assert!(!is_windows_reserved_name("file.txt"));
+    }
+
+    #[test]
+    fn test_is_precompressed() {
+        assert!(is_precompressed(Path::new("photo.JPG")));
+        assert!(is_precompressed(Path::new("dir/movie.mp4")));
+        assert!(is_precompressed(Path::new("song.mp3")));
+        assert!(is_precompressed(Path::new("backup.tar.gz")));
+        assert!(is_precompressed(Path::new("archive.zip")));
+        assert!(!is_precompressed(Path::new("logo.svg")));
+        assert!(!is_precompressed(Path::new("sound.wav")));
+        assert!(!is_precompressed(Path::new("notes.txt")));
+        assert!(!is_precompressed(Path::new("Makefile")));
     }
 
     #[test]
-    fn test_parse_range() {
-        assert_eq!(parse_range("bytes=0-499", 500), Some((0, 499)));
-        assert_eq!(parse_range("bytes=0-", 500), Some((0, 499)));
-        assert_eq!(parse_range("bytes=299-", 500), Some((299, 499)));
-        assert_eq!(parse_range("bytes=-500", 500), Some((0, 499)));
-        assert_eq!(parse_range("bytes=-300", 500), Some((200, 499)));
-        assert_eq!(parse_range("bytes=500-", 500), None);
-        assert_eq!(parse_range("bytes=-501", 500), None);
-        assert_eq!(parse_range("bytes=0-500", 500), None);
+    fn test_parse_ranges() {
+        assert_eq!(parse_ranges("bytes=0-499", 500), Some(vec![(0, 499)]));
+        assert_eq!(parse_ranges("bytes=0-", 500), Some(vec![(0, 499)]));
+        assert_eq!(parse_ranges("bytes=299-", 500), Some(vec![(299, 499)]));
+        assert_eq!(parse_ranges("bytes=-500", 500), Some(vec![(0, 499)]));
+        assert_eq!(parse_ranges("bytes=-300", 500), Some(vec![(200, 499)]));
+        assert_eq!(parse_ranges("bytes=500-", 500), None);
+        assert_eq!(parse_ranges("bytes=-501", 500), Some(vec![(0, 499)]));
+        assert_eq!(parse_ranges("bytes=0-500", 500), Some(vec![(0, 499)]));
+        assert_eq!(parse_ranges("bytes=400-999", 500), Some(vec![(400, 499)]));
+        assert_eq!(parse_ranges("bytes=0-0", 500), Some(vec![(0, 0)]));
+        assert_eq!(parse_ranges("bytes=499-499", 500), Some(vec![(499, 499)]));
+        assert_eq!(parse_ranges("bytes=-0", 500), None);
+        assert_eq!(parse_ranges("bytes=0-", 0), None);
+        assert_eq!(parse_ranges("bytes=-0", 0), None);
+        assert_eq!(parse_ranges("bytes=0-0", 0), None);
+        assert_eq!(parse_ranges("bytes= 0-100", 500), Some(vec![(0, 100)]));
+        assert_eq!(parse_ranges(" bytes=0-100 ", 500), Some(vec![(0, 100)]));
+        assert_eq!(parse_ranges("Bytes=0 - 100", 500), Some(vec![(0, 100)]));
+        assert_eq!(parse_ranges("BYTES= -100", 500), Some(vec![(400, 499)]));
+        assert_eq!(parse_ranges("items=0-100", 500), None);
+        assert_eq!(parse_ranges("bytes=5-1", 500), None);
+        assert_eq!(parse_ranges("bytes=0-1,x", 500), None);
+        assert_eq!(
+            parse_ranges("bytes=0-1, 10-19", 500),
+            Some(vec![(0, 1), (10, 19)])
+        );
+        assert_eq!(
+            parse_ranges("bytes=10-19,0-1,,-5", 500),
+            Some(vec![(0, 1), (10, 19), (495, 499)])
+        );
+        assert_eq!(
+            parse_ranges("bytes=0-9,5-14,15-20", 500),
+            Some(vec![(0, 20)])
+        );
+        assert_eq!(parse_ranges("bytes=0-9,600-700", 500), Some(vec![(0, 9)]));
+        assert_eq!(
+            parse_ranges("bytes=0-9,400-999", 500),
+            Some(vec![(0, 9), (400, 499)])
+        );
+        assert_eq!(parse_ranges("bytes=600-700,800-", 500), None);
     }
 }
@@ -79,6 +79,15 @@ fn allow_search(#[with(&["--allow-search"])] server: TestServer) -> Result<(), E
     Ok(())
 }
 
+#[rstest]
+fn search_disabled_by_default(server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}?q={}", server.url(), "test.html"))?;
+    assert_eq!(resp.status(), 403);
+    let resp = reqwest::blocking::get(format!("{}dir1?q={}&simple", server.url(), "test.html"))?;
+    assert_eq!(resp.status(), 403);
+    Ok(())
+}
+
 #[rstest]
 fn allow_archive(#[with(&["--allow-archive"])] server: TestServer) -> Result<(), Error> {
     let resp = reqwest::blocking::get(format!("{}?zip", server.url()))?;
@@ -30,3 +30,103 @@ fn path_prefix_propfind(
     assert!(text.contains("<D:href>/xyz/</D:href>"));
     Ok(())
 }
+
+#[rstest]
+fn max_path_length(#[with(&["--max-path-length", "10"])] server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}{}", server.url(), "index.html"))?;
+    assert_eq!(resp.status(), 200);
+    let resp = reqwest::blocking::get(format!("{}{}", server.url(), "dir1/index.html"))?;
+    assert_eq!(resp.status(), 414);
+    Ok(())
+}
+
+#[rstest]
+fn max_path_depth(#[with(&["--max-path-depth", "1"])] server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}{}", server.url(), "index.html"))?;
+    assert_eq!(resp.status(), 200);
+    let resp = reqwest::blocking::get(format!("{}{}", server.url(), "dir1/index.html"))?;
+    assert_eq!(resp.status(), 400);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[rstest]
+fn file_mode_dir_mode(
+    #[with(&["-A", "--file-mode", "600", "--dir-mode", "700"])] server: TestServer,
+) -> Result<(), Error> {
+    use std::os::unix::fs::PermissionsExt;
+    let url = format!("{}newdir/sub/file1", server.url());
+    let resp = fetch!(b"PUT", &url).body(b"abc".to_vec()).send()?;
+    assert_eq!(resp.status(), 201);
+    let mode = |path: &str| {
+        std::fs::metadata(server.path().join(path))
+            .unwrap()
+            .permissions()
+            .mode()
+            & 0o7777
+    };
+    assert_eq!(mode("newdir"), 0o700);
+    assert_eq!(mode("newdir/sub"), 0o700);
+    assert_eq!(mode("newdir/sub/file1"), 0o600);
+    let resp = fetch!(b"MKCOL", format!("{}newdir2", server.url())).send()?;
+    assert_eq!(resp.status(), 201);
+    assert_eq!(mode("newdir2"), 0o700);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[rstest]
+fn invalid_file_mode() -> Result<(), Error> {
+    use assert_cmd::prelude::*;
+    std::process::Command::cargo_bin("dufs")?
+        .args(["--file-mode", "999"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("Invalid octal mode `999`"));
+    Ok(())
+}
+
+#[rstest]
+fn alias_path(
+    #[with(&["--alias", "/latest=/dir1", "--alias", "latest/two=dir2/"])] server: TestServer,
+) -> Result<(), Error> {
+    std::fs::write(server.path().join("dir1/only1"), "1")?;
+    std::fs::write(server.path().join("dir2/only2"), "2")?;
+    let resp = reqwest::blocking::get(format!("{}latest/only1", server.url()))?;
+    assert_eq!(resp.text()?, "1");
+    let resp = reqwest::blocking::get(format!("{}latest/two/only2", server.url()))?;
+    assert_eq!(resp.text()?, "2");
+    let resp = reqwest::blocking::get(format!("{}latest?simple", server.url()))?;
+    assert!(resp.text()?.contains("only1"));
+    let resp = reqwest::blocking::get(format!("{}latestx/only1", server.url()))?;
+    assert_eq!(resp.status(), 404);
+    Ok(())
+}
+
+#[rstest]
+fn alias_parent_href(
+    #[with(&["--alias", "latest/two=dir2/", "--path-prefix", "xyz"])] server: TestServer,
+) -> Result<(), Error> {
+    std::fs::create_dir(server.path().join("dir2/sub"))?;
+    let resp = reqwest::blocking::get(format!("{}xyz/latest/two/sub/?json", server.url()))?;
+    let json: serde_json::Value = serde_json::from_str(&resp.text()?)?;
+    assert_eq!(json["href"], "/latest/two/sub");
+    assert_eq!(json["parent_href"], "/xyz/latest/two/");
+    Ok(())
+}
+
+#[rstest]
+#[case("/latest=../outside")]
+#[case("/=dir1")]
+#[case("latest")]
+fn alias_invalid(#[case] alias: &str) -> Result<(), Error> {
+    use assert_cmd::prelude::*;
+    std::process::Command::cargo_bin("dufs")?
+        .args(["--alias", alias])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(format!(
+            "Invalid alias `{alias}`"
+        )));
+    Ok(())
+}
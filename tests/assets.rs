@@ -67,6 +67,25 @@ fn asset_ico(server: TestServer) -> Result<(), Error> {
     Ok(())
 }
 
+#[rstest]
+#[case("index.js")]
+#[case("index.css")]
+#[case("favicon.ico")]
+fn asset_range_declined(server: TestServer, #[case] name: &str) -> Result<(), Error> {
+    let url = format!(
+        "{}__dufs_v{}__/{name}",
+        server.url(),
+        env!("CARGO_PKG_VERSION")
+    );
+    let full = reqwest::blocking::get(&url)?.bytes()?;
+    let resp = fetch!(b"GET", &url).header("range", "bytes=0-3").send()?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers().get("accept-ranges").unwrap(), "none");
+    assert!(resp.headers().get("content-range").is_none());
+    assert_eq!(resp.bytes()?, full);
+    Ok(())
+}
+
 #[rstest]
 fn assets_with_prefix(#[with(&["--path-prefix", "xyz"])] server: TestServer) -> Result<(), Error> {
     let ver = env!("CARGO_PKG_VERSION");
@@ -257,6 +257,32 @@ fn auth_webdav_copy(
     Ok(())
 }
 
+#[rstest]
+fn auth_trust_localhost(
+    #[with(&["--auth", "user:pass@/:rw", "--trust-localhost", "-A"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = fetch!(b"GET", server.url()).send()?;
+    assert_eq!(resp.status(), 200);
+    let url = format!("{}file1", server.url());
+    let resp = fetch!(b"PUT", &url).body(b"abc".to_vec()).send()?;
+    assert_eq!(resp.status(), 201);
+    let resp = fetch!(b"MOVE", &url)
+        .header("Destination", format!("{}dir1/file1", server.url()))
+        .send()?;
+    assert_eq!(resp.status(), 201);
+    Ok(())
+}
+
+#[rstest]
+fn auth_trust_localhost_readonly_server(
+    #[with(&["--auth", "user:pass@/:rw", "--trust-localhost"])] server: TestServer,
+) -> Result<(), Error> {
+    let url = format!("{}file1", server.url());
+    let resp = fetch!(b"PUT", &url).body(b"abc".to_vec()).send()?;
+    assert_eq!(resp.status(), 403);
+    Ok(())
+}
+
 #[rstest]
 fn auth_path_prefix(
     #[with(&["--auth", "user:pass@/:rw", "--path-prefix", "xyz", "-A"])] server: TestServer,
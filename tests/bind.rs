@@ -82,3 +82,95 @@ fn validate_printed_urls(tmpdir: TempDir, port: u16, #[case] args: &[&str]) -> R
 
     Ok(())
 }
+
+#[rstest]
+#[case(&["-a", "user:pass@/:rw"], true)]
+#[case(&["-a", "user:pass@/:rw", "--quiet"], false)]
+fn print_startup_summary(
+    tmpdir: TempDir,
+    port: u16,
+    #[case] args: &[&str],
+    #[case] printed: bool,
+) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("dufs")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .args(args)
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    wait_for_port(port);
+
+    let stdout = child.stdout.as_mut().expect("Failed to get stdout");
+    let mut buf = [0; 1000];
+    let buf_len = stdout.read(&mut buf)?;
+    let output = std::str::from_utf8(&buf[0..buf_len])?;
+
+    assert_eq!(output.contains("auth: digest,basic (users: user;"), printed);
+    assert!(!output.contains("pass"));
+    assert!(output.contains("Listening on"));
+
+    child.kill()?;
+
+    Ok(())
+}
+
+/// Wait up to `limit` for the server to close an idle connection, returns whether it did.
+fn closed_within<S: Read>(stream: &mut S, limit: std::time::Duration) -> bool {
+    let start = std::time::Instant::now();
+    let mut buf = [0; 1024];
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) => return start.elapsed() < limit,
+            Ok(_) => continue,
+            Err(_) => return false,
+        }
+    }
+}
+
+#[rstest]
+#[case(server(&["--idle-timeout", "1"]), true)]
+#[case(server(&[] as &[&str]), false)]
+fn idle_timeout_tcp(#[case] server: TestServer, #[case] closed: bool) -> Result<(), Error> {
+    use std::io::Write;
+    let limit = std::time::Duration::from_secs(3);
+    let mut stream = std::net::TcpStream::connect(("127.0.0.1", server.port()))?;
+    stream.set_read_timeout(Some(limit))?;
+    stream.write_all(b"HEAD / HTTP/1.1\r\nHost: localhost\r\n\r\n")?;
+    assert_eq!(closed_within(&mut stream, limit), closed);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[rstest]
+fn idle_timeout_unix_socket(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    use std::io::Write;
+    let socket_path = tmpdir.path().join("dufs.sock");
+    let mut child = Command::cargo_bin("dufs")?
+        .arg(tmpdir.path())
+        .arg("-b")
+        .arg(&socket_path)
+        .arg("-b")
+        .arg("127.0.0.1")
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("--idle-timeout")
+        .arg("1")
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    wait_for_port(port);
+
+    let limit = std::time::Duration::from_secs(3);
+    let closed = std::os::unix::net::UnixStream::connect(&socket_path).and_then(|mut stream| {
+        stream.set_read_timeout(Some(limit))?;
+        stream.write_all(b"HEAD / HTTP/1.1\r\nHost: localhost\r\n\r\n")?;
+        Ok(closed_within(&mut stream, limit))
+    });
+
+    child.kill()?;
+
+    assert!(closed?);
+    Ok(())
+}
@@ -78,3 +78,173 @@ fn get_file_with_etag_match(
     assert_eq!(resp.status(), expected_code);
     Ok(())
 }
+
+fn any_etag(_etag: &str) -> String {
+    "*".to_owned()
+}
+
+fn listed_etag(etag: &str) -> String {
+    format!(r#""other", {etag}"#)
+}
+
+#[rstest]
+#[case(any_etag, StatusCode::OK)]
+#[case(listed_etag, StatusCode::OK)]
+#[case(different_etag, StatusCode::PRECONDITION_FAILED)]
+fn head_file_with_if_match(
+    #[case] etag_modifier: fn(&str) -> String,
+    #[case] expected_code: StatusCode,
+    server: TestServer,
+) -> Result<(), Error> {
+    let url = format!("{}index.html", server.url());
+    let resp = fetch!(b"HEAD", &url).send()?;
+    let etag = resp.headers().get(ETAG).unwrap().to_str()?.to_string();
+
+    let resp = fetch!(b"HEAD", &url)
+        .header(IF_MATCH, etag_modifier(&etag))
+        .send()?;
+    assert_eq!(resp.status(), expected_code);
+    Ok(())
+}
+
+#[rstest]
+fn if_match_takes_precedence_over_if_unmodified_since(server: TestServer) -> Result<(), Error> {
+    let url = format!("{}index.html", server.url());
+    let resp = fetch!(b"HEAD", &url).send()?;
+    let etag = resp.headers().get(ETAG).unwrap().to_str()?.to_string();
+
+    let resp = fetch!(b"GET", &url)
+        .header(IF_MATCH, &etag)
+        .header(IF_UNMODIFIED_SINCE, "Thu, 01 Jan 1970 00:00:00 GMT")
+        .send()?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.text()?, "This is index.html");
+    Ok(())
+}
+
+#[rstest]
+#[case(same_etag, StatusCode::NOT_MODIFIED)]
+#[case(different_etag, StatusCode::OK)]
+fn head_file_with_if_none_match(
+    #[case] etag_modifier: fn(&str) -> String,
+    #[case] expected_code: StatusCode,
+    server: TestServer,
+) -> Result<(), Error> {
+    let url = format!("{}index.html", server.url());
+    let resp = fetch!(b"HEAD", &url).send()?;
+    let etag = resp.headers().get(ETAG).unwrap().to_str()?.to_string();
+
+    let resp = fetch!(b"HEAD", &url)
+        .header(IF_NONE_MATCH, etag_modifier(&etag))
+        .send()?;
+    assert_eq!(resp.status(), expected_code);
+    assert_eq!(resp.headers().get(ETAG).unwrap().to_str()?, etag);
+    if expected_code == StatusCode::NOT_MODIFIED {
+        assert!(resp.headers().get("content-length").is_none());
+        assert_eq!(resp.text()?, "");
+    }
+    Ok(())
+}
+
+#[rstest]
+fn if_none_match_takes_precedence_over_if_modified_since(server: TestServer) -> Result<(), Error> {
+    let url = format!("{}index.html", server.url());
+    let resp = fetch!(b"HEAD", &url).send()?;
+    let etag = resp.headers().get(ETAG).unwrap().to_str()?.to_string();
+    let last_modified = resp
+        .headers()
+        .get(LAST_MODIFIED)
+        .unwrap()
+        .to_str()?
+        .to_string();
+
+    let resp = fetch!(b"GET", &url)
+        .header(IF_NONE_MATCH, different_etag(&etag))
+        .header(IF_MODIFIED_SINCE, last_modified)
+        .send()?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    Ok(())
+}
+
+#[rstest]
+#[case("cache-control", "no-cache", StatusCode::OK)]
+#[case("cache-control", "max-age=0, No-Cache", StatusCode::OK)]
+#[case("cache-control", "max-age=0", StatusCode::NOT_MODIFIED)]
+#[case("pragma", "no-cache", StatusCode::OK)]
+fn get_file_no_cache_skips_not_modified(
+    server: TestServer,
+    #[case] name: &str,
+    #[case] value: &str,
+    #[case] expected_code: StatusCode,
+) -> Result<(), Error> {
+    let url = format!("{}index.html", server.url());
+    let resp = fetch!(b"HEAD", &url).send()?;
+    let etag = resp.headers().get(ETAG).unwrap().to_str()?.to_string();
+
+    let resp = fetch!(b"GET", &url)
+        .header(IF_NONE_MATCH, &etag)
+        .header(name, value)
+        .send()?;
+    assert_eq!(resp.status(), expected_code);
+    assert_eq!(resp.headers().get(ETAG).unwrap().to_str()?, etag);
+    if expected_code == StatusCode::OK {
+        assert_eq!(resp.text()?, "This is index.html");
+    }
+    Ok(())
+}
+
+#[rstest]
+fn get_file_weak_etag(#[with(&["--weak-etags"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}index.html", server.url());
+    let resp = fetch!(b"HEAD", &url).send()?;
+    let etag = resp.headers().get(ETAG).unwrap().to_str()?.to_string();
+    assert!(etag.starts_with("W/\""));
+
+    let resp = fetch!(b"GET", &url).header(IF_NONE_MATCH, &etag).send()?;
+    assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+    // If-Match and If-Range compare strongly, a weak tag never satisfies them
+    let resp = fetch!(b"GET", &url).header(IF_MATCH, &etag).send()?;
+    assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+    let resp = fetch!(b"GET", &url)
+        .header("range", "bytes=0-3")
+        .header("if-range", &etag)
+        .send()?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.text()?, "This is index.html");
+    Ok(())
+}
+
+#[rstest]
+#[case(server(&[] as &[&str]), "no-cache")]
+#[case(server(&["--cache-control", "3600"]), "public, max-age=3600")]
+#[case(server(&["--cache-control", "3600", "--cache-immutable"]), "public, immutable, max-age=3600")]
+fn get_file_cache_control(#[case] server: TestServer, #[case] expected: &str) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}index.html", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers().get("cache-control").unwrap(), expected);
+    let resp = reqwest::blocking::get(server.url())?;
+    assert_eq!(resp.headers().get("cache-control").unwrap(), "no-cache");
+    let resp = fetch!(b"PROPFIND", server.url()).send()?;
+    assert_eq!(resp.headers().get("cache-control").unwrap(), "no-cache");
+    Ok(())
+}
+
+#[rstest]
+fn get_file_hash_etag(#[with(&["--etag", "hash"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}index.html", server.url());
+    let resp = reqwest::blocking::get(&url)?;
+    let etag = resp.headers().get(ETAG).unwrap().clone();
+    assert_eq!(etag.len(), 34);
+    // same content, new mtime
+    let path = server.path().join("index.html");
+    let content = std::fs::read(&path)?;
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    std::fs::write(&path, &content)?;
+    let resp = fetch!(b"GET", &url).header(IF_NONE_MATCH, &etag).send()?;
+    assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+    std::fs::write(&path, b"changed")?;
+    let resp = fetch!(b"GET", &url).header(IF_NONE_MATCH, &etag).send()?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_ne!(resp.headers().get(ETAG).unwrap(), &etag);
+    Ok(())
+}
@@ -30,3 +30,53 @@ fn print_completions() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+/// Print the man page and exit.
+fn print_man() -> Result<(), Error> {
+    let output = Command::cargo_bin("dufs")?.arg("--man").output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.starts_with(".ie \\n(.g .ds Aq \\(aq"));
+    assert!(stdout.contains(".TH dufs 1"));
+    assert!(stdout.contains("\\-\\-allow\\-upload"));
+
+    Ok(())
+}
+
+#[test]
+/// Validate the configuration and exit without serving.
+fn check_config() -> Result<(), Error> {
+    let output = Command::cargo_bin("dufs")?
+        .args(["--check", "-A", "--auth", "user:pass@/:rw", "-p", "5000"])
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("  auth: "));
+    assert!(stdout.ends_with("Configuration OK\n"));
+
+    Command::cargo_bin("dufs")?
+        .args(["--check", "--auth", "user:pass@/:xx"])
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[cfg(feature = "tls")]
+#[test]
+/// Load the tls certificate and key as serving would.
+fn check_config_tls() -> Result<(), Error> {
+    Command::cargo_bin("dufs")?
+        .args(["--check", "--tls-cert", "tests/data/cert.pem"])
+        .args(["--tls-key", "tests/data/key_pkcs8.pem"])
+        .assert()
+        .success();
+    Command::cargo_bin("dufs")?
+        .args(["--check", "--tls-cert", "tests/data/cert.pem"])
+        .args(["--tls-key", "tests/data/cert.pem"])
+        .assert()
+        .failure();
+
+    Ok(())
+}
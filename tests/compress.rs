@@ -0,0 +1,108 @@
+mod fixtures;
+mod utils;
+
+use fixtures::{server, Error, TestServer, BIN_FILE};
+use rstest::rstest;
+use std::io::Read;
+
+fn write_text(server: &TestServer, name: &str) -> String {
+    let content = "The quick brown fox jumps over the lazy dog.\n".repeat(100);
+    std::fs::write(server.path().join(name), &content).unwrap();
+    content
+}
+
+#[rstest]
+fn get_file_gzip(#[with(&["--http-compress", "medium"])] server: TestServer) -> Result<(), Error> {
+    let content = write_text(&server, "big.txt");
+    let resp = fetch!(b"GET", format!("{}big.txt", server.url()))
+        .header("accept-encoding", "gzip")
+        .send()?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers().get("content-encoding").unwrap(), "gzip");
+    assert_eq!(resp.headers().get("vary").unwrap(), "Accept-Encoding");
+    assert!(resp.headers().get("content-length").is_none());
+    let etag = resp.headers().get("etag").unwrap().clone();
+    assert!(etag.to_str()?.ends_with(r#"-gzip""#));
+    let body = resp.bytes()?;
+    assert!(body.len() < content.len());
+    let mut decoded = String::new();
+    flate2::read::GzDecoder::new(&body[..]).read_to_string(&mut decoded)?;
+    assert_eq!(decoded, content);
+
+    let resp = fetch!(b"GET", format!("{}big.txt", server.url()))
+        .header("accept-encoding", "gzip")
+        .header("if-none-match", etag)
+        .send()?;
+    assert_eq!(resp.status(), 304);
+    Ok(())
+}
+
+#[rstest]
+fn get_file_brotli(#[with(&["--http-compress", "low"])] server: TestServer) -> Result<(), Error> {
+    let content = write_text(&server, "big.txt");
+    let resp = fetch!(b"GET", format!("{}big.txt", server.url()))
+        .header("accept-encoding", "gzip, deflate, br")
+        .send()?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers().get("content-encoding").unwrap(), "br");
+    let body = resp.bytes()?;
+    let mut decoded = String::new();
+    brotli::Decompressor::new(&body[..], 4096).read_to_string(&mut decoded)?;
+    assert_eq!(decoded, content);
+    Ok(())
+}
+
+#[rstest]
+fn head_file_gzip(#[with(&["--http-compress", "low"])] server: TestServer) -> Result<(), Error> {
+    write_text(&server, "big.txt");
+    let resp = fetch!(b"HEAD", format!("{}big.txt", server.url()))
+        .header("accept-encoding", "gzip")
+        .send()?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers().get("content-encoding").unwrap(), "gzip");
+    assert_eq!(resp.text()?, "");
+    Ok(())
+}
+
+#[rstest]
+#[case(server(&["--http-compress", "low"] as &[&str]), "big.txt", "identity", None)]
+#[case(server(&["--http-compress", "low"] as &[&str]), "big.txt", "gzip;q=0, br;q=0", None)]
+#[case(server(&["--http-compress", "low"] as &[&str]), "big.txt", "gzip", Some("bytes=0-9"))]
+#[case(server(&["--http-compress", "low"] as &[&str]), "index.html", "gzip", None)]
+#[case(server(&[] as &[&str]), "big.txt", "gzip", None)]
+fn get_file_uncompressed(
+    #[case] server: TestServer,
+    #[case] name: &str,
+    #[case] accept_encoding: &str,
+    #[case] range: Option<&str>,
+) -> Result<(), Error> {
+    write_text(&server, "big.txt");
+    let mut req = fetch!(b"GET", format!("{}{}", server.url(), name))
+        .header("accept-encoding", accept_encoding);
+    if let Some(range) = range {
+        req = req.header("range", range);
+    }
+    let resp = req.send()?;
+    assert!(resp.status().is_success());
+    assert!(resp.headers().get("content-encoding").is_none());
+    assert!(resp.headers().get("content-length").is_some());
+    Ok(())
+}
+
+#[rstest]
+fn get_binary_uncompressed(
+    #[with(&["--http-compress", "high"])] server: TestServer,
+) -> Result<(), Error> {
+    std::fs::write(server.path().join("big.bin"), vec![0u8; 4096])?;
+    let resp = fetch!(b"GET", format!("{}big.bin", server.url()))
+        .header("accept-encoding", "gzip")
+        .send()?;
+    assert_eq!(resp.status(), 200);
+    assert!(resp.headers().get("content-encoding").is_none());
+    assert!(resp.headers().get("vary").is_none());
+    let resp = fetch!(b"GET", format!("{}{}", server.url(), BIN_FILE))
+        .header("accept-encoding", "gzip")
+        .send()?;
+    assert!(resp.headers().get("content-encoding").is_none());
+    Ok(())
+}
@@ -11,6 +11,38 @@ fn cors(#[with(&["--enable-cors"])] server: TestServer) -> Result<(), Error> {
         resp.headers().get("access-control-allow-origin").unwrap(),
         "*"
     );
+    assert!(resp
+        .headers()
+        .get("access-control-allow-credentials")
+        .is_none());
+    assert_eq!(
+        resp.headers().get("access-control-allow-methods").unwrap(),
+        "*"
+    );
+    assert_eq!(
+        resp.headers().get("access-control-allow-headers").unwrap(),
+        "Authorization,*"
+    );
+    assert_eq!(
+        resp.headers().get("access-control-expose-headers").unwrap(),
+        "Authorization,*"
+    );
+    Ok(())
+}
+
+#[rstest]
+fn cors_origin_allowed(
+    #[with(&["--enable-cors", "--cors-origin", "https://a.com,https://b.com", "-A"])]
+    server: TestServer,
+) -> Result<(), Error> {
+    let resp = fetch!(b"GET", server.url())
+        .header("origin", "https://b.com")
+        .send()?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get("access-control-allow-origin").unwrap(),
+        "https://b.com"
+    );
     assert_eq!(
         resp.headers()
             .get("access-control-allow-credentials")
@@ -19,15 +51,67 @@ fn cors(#[with(&["--enable-cors"])] server: TestServer) -> Result<(), Error> {
     );
     assert_eq!(
         resp.headers().get("access-control-allow-methods").unwrap(),
-        "*"
+        "GET,HEAD,PUT,OPTIONS,DELETE,PATCH,PROPFIND,PROPPATCH,COPY,MOVE,MKCOL,LOCK,UNLOCK,CHECKAUTH,LOGOUT"
+    );
+    assert!(resp.headers().get_all("vary").iter().any(|v| v == "Origin"));
+    Ok(())
+}
+
+#[rstest]
+fn cors_origin_rejected(
+    #[with(&["--enable-cors", "--cors-origin", "https://a.com"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = fetch!(b"GET", server.url())
+        .header("origin", "https://evil.com")
+        .send()?;
+    assert_eq!(resp.status(), 200);
+    assert!(resp.headers().get("access-control-allow-origin").is_none());
+    assert!(resp
+        .headers()
+        .get("access-control-allow-credentials")
+        .is_none());
+    assert!(resp.headers().get_all("vary").iter().any(|v| v == "Origin"));
+    Ok(())
+}
+
+#[rstest]
+fn cors_preflight(
+    #[with(&[
+        "--enable-cors",
+        "--cors-origin",
+        "https://a.com",
+        "--cors-methods",
+        "GET,PUT",
+        "--cors-max-age",
+        "600",
+        "--auth",
+        "user:pass@/:rw",
+    ])]
+    server: TestServer,
+) -> Result<(), Error> {
+    let resp = fetch!(b"OPTIONS", format!("{}index.html", server.url()))
+        .header("origin", "https://a.com")
+        .header("access-control-request-method", "PUT")
+        .header(
+            "access-control-request-headers",
+            "authorization,content-type",
+        )
+        .send()?;
+    assert_eq!(resp.status(), 204);
+    assert_eq!(
+        resp.headers().get("access-control-allow-origin").unwrap(),
+        "https://a.com"
     );
     assert_eq!(
-        resp.headers().get("access-control-allow-headers").unwrap(),
-        "Authorization,*"
+        resp.headers().get("access-control-allow-methods").unwrap(),
+        "GET,PUT"
     );
     assert_eq!(
-        resp.headers().get("access-control-expose-headers").unwrap(),
-        "Authorization,*"
+        resp.headers().get("access-control-allow-headers").unwrap(),
+        "authorization,content-type"
     );
+    assert_eq!(resp.headers().get("access-control-max-age").unwrap(), "600");
+    assert!(resp.headers().get("allow").is_none());
+    assert!(resp.headers().get("dav").is_none());
     Ok(())
 }
@@ -0,0 +1,174 @@
+mod digest_auth_util;
+mod fixtures;
+mod utils;
+
+use digest_auth_util::send_with_digest_auth;
+use fixtures::{server, Error, TestServer};
+use rstest::rstest;
+
+fn protect_dir1(server: &TestServer) -> Result<(), Error> {
+    std::fs::write(
+        server.path().join("dir1/.dufs-auth"),
+        "# accounts\nuser:pass\n",
+    )?;
+    Ok(())
+}
+
+#[rstest]
+fn dir_auth(#[with(&["--dir-auth", "-A"])] server: TestServer) -> Result<(), Error> {
+    protect_dir1(&server)?;
+    let resp = reqwest::blocking::get(server.url())?;
+    assert_eq!(resp.status(), 200);
+    let url = format!("{}dir1/test.html", server.url());
+    let resp = reqwest::blocking::get(&url)?;
+    assert_eq!(resp.status(), 401);
+    let resp = fetch!(b"GET", &url).basic_auth("user", Some("-")).send()?;
+    assert_eq!(resp.status(), 401);
+    let resp = fetch!(b"GET", &url)
+        .basic_auth("user", Some("pass"))
+        .send()?;
+    assert_eq!(resp.status(), 200);
+    let resp = send_with_digest_auth(fetch!(b"GET", &url), "user", "pass")?;
+    assert_eq!(resp.status(), 200);
+    Ok(())
+}
+
+#[rstest]
+fn dir_auth_disabled(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    protect_dir1(&server)?;
+    let resp = reqwest::blocking::get(format!("{}dir1/test.html", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    Ok(())
+}
+
+#[rstest]
+fn dir_auth_reload(#[with(&["--dir-auth", "-A"])] server: TestServer) -> Result<(), Error> {
+    protect_dir1(&server)?;
+    let url = format!("{}dir1/", server.url());
+    let resp = fetch!(b"GET", &url)
+        .basic_auth("user", Some("pass"))
+        .send()?;
+    assert_eq!(resp.status(), 200);
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    std::fs::write(server.path().join("dir1/.dufs-auth"), "user:pass2\n")?;
+    let resp = fetch!(b"GET", &url)
+        .basic_auth("user", Some("pass"))
+        .send()?;
+    assert_eq!(resp.status(), 401);
+    let resp = fetch!(b"GET", &url)
+        .basic_auth("user", Some("pass2"))
+        .send()?;
+    assert_eq!(resp.status(), 200);
+    Ok(())
+}
+
+#[rstest]
+fn dir_auth_file_not_exposed(
+    #[with(&["--dir-auth", "-A"])] server: TestServer,
+) -> Result<(), Error> {
+    protect_dir1(&server)?;
+    let url = format!("{}dir1/.dufs-auth", server.url());
+    let resp = fetch!(b"GET", &url)
+        .basic_auth("user", Some("pass"))
+        .send()?;
+    assert_eq!(resp.status(), 404);
+    let resp = fetch!(b"PUT", format!("{}dir2/.dufs-auth", server.url()))
+        .body(b"evil:evil".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 404);
+    // case-insensitive filesystems resolve any casing to the credentials file
+    let resp = fetch!(b"GET", format!("{}dir1/.DUFS-Auth", server.url()))
+        .basic_auth("user", Some("pass"))
+        .send()?;
+    assert_eq!(resp.status(), 404);
+    let resp = fetch!(b"PUT", format!("{}dir2/.DUFS-AUTH", server.url()))
+        .body(b"evil:evil".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 404);
+    let resp = fetch!(b"GET", format!("{}dir1/?json", server.url()))
+        .basic_auth("user", Some("pass"))
+        .send()?;
+    assert!(!resp.text()?.contains(".dufs-auth"));
+    Ok(())
+}
+
+#[rstest]
+fn dir_auth_skipped_in_search_and_move(
+    #[with(&["--dir-auth", "-A"])] server: TestServer,
+) -> Result<(), Error> {
+    protect_dir1(&server)?;
+    let resp = reqwest::blocking::get(format!("{}?q=test.html&simple", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    assert!(!resp.text()?.contains("dir1"));
+
+    let resp = fetch!(b"MOVE", format!("{}test.html", server.url()))
+        .header("Destination", format!("{}dir1/moved.html", server.url()))
+        .send()?;
+    assert_eq!(resp.status(), 403);
+    Ok(())
+}
+
+#[rstest]
+fn dir_auth_replace_protected_dir(
+    #[with(&["--dir-auth", "-A"])] server: TestServer,
+) -> Result<(), Error> {
+    std::fs::create_dir_all(server.path().join("dir1/dir2"))?;
+    std::fs::write(
+        server.path().join("dir1/dir2/.dufs-auth"),
+        "# accounts\nuser:pass\n",
+    )?;
+    let resp = fetch!(b"MOVE", format!("{}test.html", server.url()))
+        .header("Destination", format!("{}dir1", server.url()))
+        .send()?;
+    assert_eq!(resp.status(), 403);
+    assert!(server.path().join("dir1/dir2/.dufs-auth").exists());
+
+    let resp = fetch!(b"MOVE", format!("{}test.html", server.url()))
+        .header("Destination", format!("{}dir1", server.url()))
+        .basic_auth("user", Some("pass"))
+        .send()?;
+    assert_eq!(resp.status(), 204);
+    assert!(server.path().join("dir1").is_file());
+    Ok(())
+}
+
+#[rstest]
+fn dir_auth_delete_parent_of_protected_dir(
+    #[with(&["--dir-auth", "-A"])] server: TestServer,
+) -> Result<(), Error> {
+    std::fs::create_dir_all(server.path().join("dir1/dir2"))?;
+    std::fs::write(
+        server.path().join("dir1/dir2/.dufs-auth"),
+        "# accounts\nuser:pass\n",
+    )?;
+    let resp = fetch!(b"DELETE", format!("{}dir1", server.url())).send()?;
+    assert_eq!(resp.status(), 403);
+    let resp = fetch!(b"MOVE", format!("{}dir1", server.url()))
+        .header("Destination", format!("{}dir5", server.url()))
+        .send()?;
+    assert_eq!(resp.status(), 403);
+    assert!(server.path().join("dir1/dir2/.dufs-auth").exists());
+
+    let resp = fetch!(b"DELETE", format!("{}dir1", server.url()))
+        .basic_auth("user", Some("pass"))
+        .send()?;
+    assert_eq!(resp.status(), 204);
+    assert!(!server.path().join("dir1").exists());
+    Ok(())
+}
+
+#[rstest]
+fn dir_auth_skipped_in_infinite_propfind(
+    #[with(&["--dir-auth", "-A", "--dav-infinite-depth", "allow"])] server: TestServer,
+) -> Result<(), Error> {
+    protect_dir1(&server)?;
+    let resp = fetch!(b"PROPFIND", server.url())
+        .header("depth", "infinity")
+        .send()?;
+    assert_eq!(resp.status(), 207);
+    let body = resp.text()?;
+    assert!(body.contains("<D:href>/dir1/</D:href>"));
+    assert!(!body.contains("<D:href>/dir1/test.html</D:href>"));
+    assert!(body.contains("<D:href>/dir2/test.html</D:href>"));
+    Ok(())
+}
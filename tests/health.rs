@@ -29,3 +29,45 @@ fn path_prefix_health(#[with(&["--path-prefix", "xyz"])] server: TestServer) ->
     assert_eq!(resp.text()?, HEALTH_CHECK_RESPONSE);
     Ok(())
 }
+
+#[rstest]
+fn version_info(
+    #[with(&["--auth", "user:pass@/:rw", "-A"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}__dufs__/version", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    let json: serde_json::Value = serde_json::from_str(&resp.text()?)?;
+    assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+    assert!(json["features"].is_array());
+    assert!(json.get("git_hash").is_some());
+    Ok(())
+}
+
+#[rstest]
+fn metrics(
+    #[with(&["--auth", "user:pass@/:rw", "--metrics"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}missing", server.url()))?;
+    assert_eq!(resp.status(), 401);
+    let resp = reqwest::blocking::get(format!("{}__dufs__/metrics", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    let text = resp.text()?;
+    assert!(text.contains("dufs_requests_total{method=\"GET\",status=\"4xx\"} 1\n"));
+    assert!(text.contains("dufs_response_bytes_total "));
+    assert!(text.contains("dufs_active_uploads 0\n"));
+    Ok(())
+}
+
+#[rstest]
+fn metrics_disabled(server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}__dufs__/metrics", server.url()))?;
+    assert_eq!(resp.status(), 404);
+    Ok(())
+}
+
+#[rstest]
+fn no_health(#[with(&["--no-health"])] server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}{HEALTH_CHECK_PATH}", server.url()))?;
+    assert_eq!(resp.status(), 404);
+    Ok(())
+}
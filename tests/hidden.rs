@@ -30,6 +30,31 @@ fn hidden_get_dir2(#[case] server: TestServer, #[case] exist: bool) -> Result<()
     Ok(())
 }
 
+#[rstest]
+#[case(server(&[] as &[&str]), true)]
+#[case(server(&["--hidden", "**/*.html"]), false)]
+fn hidden_get_dir_globstar(#[case] server: TestServer, #[case] exist: bool) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}dir1/", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    let paths = utils::retrieve_index_paths(&resp.text()?);
+    assert_eq!(paths.contains("index.html"), exist);
+    assert_eq!(paths.contains("test.html"), exist);
+    Ok(())
+}
+
+#[rstest]
+fn hidden_path_pattern(
+    #[with(&["--hidden", "dir1/*.html"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}dir1/", server.url()))?;
+    let paths = utils::retrieve_index_paths(&resp.text()?);
+    assert!(!paths.contains("index.html"));
+    let resp = reqwest::blocking::get(server.url())?;
+    let paths = utils::retrieve_index_paths(&resp.text()?);
+    assert!(paths.contains("index.html"));
+    Ok(())
+}
+
 #[rstest]
 #[case(server(&[] as &[&str]), true)]
 #[case(server(&["--hidden", ".git,index.html"]), false)]
@@ -59,6 +84,8 @@ fn hidden_search_dir(#[case] server: TestServer, #[case] exist: bool) -> Result<
 #[rstest]
 #[case(server(&["--hidden", "hidden/"]), "dir4/", 1)]
 #[case(server(&["--hidden", "hidden"]), "dir4/", 0)]
+#[case(server(&["--hidden", "hidden/**"]), "dir4/", 1)]
+#[case(server(&["--hidden", "**/hidden/"]), "dir4/", 1)]
 fn hidden_dir_only(
     #[case] server: TestServer,
     #[case] dir: &str,
@@ -70,3 +97,28 @@ fn hidden_dir_only(
     assert_eq!(paths.len(), count);
     Ok(())
 }
+
+#[rstest]
+fn hidden_still_reachable(
+    #[with(&["--hidden", ".git,test.html"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}.git/test.html", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.text()?, "This is .git/test.html");
+    let resp = reqwest::blocking::get(format!("{}test.html", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    let resp = reqwest::blocking::get(format!("{}.git/", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    Ok(())
+}
+
+#[rstest]
+fn hidden_upload_temp_files(server: TestServer) -> Result<(), Error> {
+    std::fs::write(server.path().join(".test.txt.dufs-upload-0123abcd"), "abc")?;
+    let resp = reqwest::blocking::get(server.url())?;
+    assert_eq!(resp.status(), 200);
+    let paths = utils::retrieve_index_paths(&resp.text()?);
+    assert!(paths.contains("test.txt"));
+    assert!(!paths.iter().any(|v| v.contains(".dufs-upload-")));
+    Ok(())
+}
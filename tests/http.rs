@@ -56,6 +56,150 @@ fn get_dir_zip(#[case] server: TestServer) -> Result<(), Error> {
     Ok(())
 }
 
+#[rstest]
+#[case("tar", "application/x-tar")]
+#[case("tar.gz", "application/gzip")]
+fn get_dir_tar(
+    #[with(&["--allow-archive"])] server: TestServer,
+    #[case] ext: &str,
+    #[case] content_type: &str,
+) -> Result<(), Error> {
+    use std::io::Read;
+    let resp = reqwest::blocking::get(format!("{}dir1?{ext}", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers().get("content-type").unwrap(), content_type);
+    assert_eq!(
+        resp.headers().get("content-disposition").unwrap(),
+        &format!(r#"attachment; filename="dir1.{ext}""#)
+    );
+    let body = resp.bytes()?;
+    let reader: Box<dyn Read> = match ext {
+        "tar" => Box::new(&body[..]),
+        _ => Box::new(flate2::read::GzDecoder::new(&body[..])),
+    };
+    let mut archive = tar::Archive::new(reader);
+    let mut names = vec![];
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().to_string();
+        if name == "test.txt" {
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+            assert_eq!(content, "This is dir1/test.txt");
+        }
+        names.push(name);
+    }
+    assert!(names.iter().any(|v| v == "test.txt"));
+    Ok(())
+}
+
+#[rstest]
+fn get_dir_tar_not_allowed(server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}dir1?tar", server.url()))?;
+    assert_eq!(resp.status(), 404);
+    Ok(())
+}
+
+#[rstest]
+fn get_dir_zip_selection(#[with(&["--allow-archive"])] server: TestServer) -> Result<(), Error> {
+    let has = |body: &[u8], name: &str| body.windows(name.len()).any(|v| v == name.as_bytes());
+    let resp = reqwest::blocking::get(format!("{}?zip&path=test.txt&path=dir2/", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    let body = resp.bytes()?;
+    assert!(has(&body, "test.txt"));
+    assert!(has(&body, "dir2/index.html"));
+    assert!(!has(&body, "dir1/"));
+    assert!(!has(&body, "dir3/"));
+    Ok(())
+}
+
+#[rstest]
+#[case("path=../dir1")]
+#[case("path=/")]
+#[case("path=dir1/../..")]
+fn get_dir_zip_selection_invalid(
+    #[with(&["--allow-archive"])] server: TestServer,
+    #[case] param: &str,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}dir1?zip&{param}", server.url()))?;
+    assert_eq!(resp.status(), 400);
+    Ok(())
+}
+
+#[rstest]
+fn get_dir_zip_concurrent(#[with(&["--allow-archive"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}?zip", server.url());
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let url = url.clone();
+            std::thread::spawn(move || -> Result<Vec<u8>, reqwest::Error> {
+                let resp = reqwest::blocking::get(url)?.error_for_status()?;
+                Ok(resp.bytes()?.to_vec())
+            })
+        })
+        .collect();
+    let bodies: Vec<_> = handles
+        .into_iter()
+        .map(|v| v.join().unwrap())
+        .collect::<Result<_, _>>()?;
+    let body = &bodies[0];
+    // end of central directory record
+    assert_eq!(&body[body.len() - 22..body.len() - 18], b"PK\x05\x06");
+    assert!(bodies.iter().all(|v| v == body));
+    Ok(())
+}
+
+#[rstest]
+fn get_dir_zip_cache(
+    #[with(&["--allow-archive", "--zip-cache", "60"])] server: TestServer,
+) -> Result<(), Error> {
+    let url = format!("{}dir1?zip", server.url());
+    let resp = fetch!(b"HEAD", &url).send()?;
+    assert_eq!(resp.status(), 200);
+    assert!(resp.headers().get("content-length").is_none());
+    let resp = reqwest::blocking::get(&url)?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers().get("accept-ranges").unwrap(), "bytes");
+    assert_eq!(
+        resp.headers().get("content-disposition").unwrap(),
+        r#"attachment; filename="dir1.zip""#
+    );
+    let etag = resp.headers().get("etag").unwrap().clone();
+    let body = resp.bytes()?;
+    assert_eq!(&body[body.len() - 22..body.len() - 18], b"PK\x05\x06");
+    let resp = fetch!(b"HEAD", &url).send()?;
+    assert_eq!(
+        resp.headers().get("content-length").unwrap(),
+        &body.len().to_string()
+    );
+    let resp = fetch!(b"GET", &url)
+        .header("range", "bytes=10-")
+        .header("if-range", etag)
+        .send()?;
+    assert_eq!(resp.status(), 206);
+    assert_eq!(resp.bytes()?, body[10..]);
+    Ok(())
+}
+
+#[rstest]
+fn get_dir_zip_cache_nested_change(
+    #[with(&["--allow-archive", "--zip-cache", "60"])] server: TestServer,
+) -> Result<(), Error> {
+    let nested = server.path().join("dir1/sub/file.txt");
+    std::fs::create_dir_all(nested.parent().unwrap())?;
+    std::fs::write(&nested, "old")?;
+    let url = format!("{}dir1?zip", server.url());
+    let resp = reqwest::blocking::get(&url)?;
+    let etag = resp.headers().get("etag").unwrap().clone();
+    resp.bytes()?;
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    std::fs::write(&nested, "new content")?;
+    let resp = reqwest::blocking::get(&url)?;
+    assert_eq!(resp.status(), 200);
+    assert_ne!(resp.headers().get("etag").unwrap(), &etag);
+    Ok(())
+}
+
 #[rstest]
 fn get_dir_json(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     let resp = reqwest::blocking::get(format!("{}?json", server.url()))?;
@@ -69,6 +213,189 @@ fn get_dir_json(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     Ok(())
 }
 
+#[rstest]
+fn get_dir_json_totals(server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}?json", server.url()))?;
+    let json: Value = serde_json::from_str(&resp.text()?)?;
+    let paths = json["paths"].as_array().unwrap();
+    let size: u64 = paths
+        .iter()
+        .filter(|v| v["path_type"] == "File")
+        .filter_map(|v| v["size"].as_u64())
+        .sum();
+    assert_eq!(json["total_count"], paths.len());
+    assert_eq!(json["total_size"], size);
+    assert!(size > 0);
+    Ok(())
+}
+
+#[rstest]
+fn get_dir_max_list_entries(
+    #[with(&["--allow-search", "--max-list-entries", "3"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}?json", server.url()))?;
+    let json: Value = serde_json::from_str(&resp.text()?)?;
+    assert_eq!(json["paths"].as_array().unwrap().len(), 3);
+    assert_eq!(json["truncated"], true);
+    let resp = reqwest::blocking::get(format!("{}?json&q=test", server.url()))?;
+    let json: Value = serde_json::from_str(&resp.text()?)?;
+    assert_eq!(json["paths"].as_array().unwrap().len(), 3);
+    assert_eq!(json["truncated"], true);
+    let resp = reqwest::blocking::get(format!("{}dir4/?json", server.url()))?;
+    let json: Value = serde_json::from_str(&resp.text()?)?;
+    assert_eq!(json["truncated"], false);
+    Ok(())
+}
+
+#[rstest]
+fn get_dir_max_list_entries_hidden_rest(
+    #[with(&["--max-list-entries", "3", "--hidden", "*.txt"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}dir1/?json", server.url()))?;
+    let json: Value = serde_json::from_str(&resp.text()?)?;
+    assert_eq!(json["paths"].as_array().unwrap().len(), 3);
+    assert_eq!(json["truncated"], false);
+    Ok(())
+}
+
+#[rstest]
+fn get_dir_json_paginated(server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}?json", server.url()))?;
+    let json: Value = serde_json::from_str(&resp.text()?)?;
+    let all = json["paths"].as_array().unwrap().clone();
+    assert_eq!(json["total_count"], all.len());
+    let resp = reqwest::blocking::get(format!("{}?json&page=2&per_page=3", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    let json: Value = serde_json::from_str(&resp.text()?)?;
+    assert_eq!(json["page"], 2);
+    assert_eq!(json["per_page"], 3);
+    assert_eq!(json["total_count"], all.len());
+    assert_eq!(json["paths"].as_array().unwrap(), &all[3..6]);
+    Ok(())
+}
+
+#[rstest]
+fn get_dir_paginated_link(server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}?json", server.url()))?;
+    let json: Value = serde_json::from_str(&resp.text()?)?;
+    let last = json["total_count"].as_u64().unwrap().div_ceil(3);
+    let resp = reqwest::blocking::get(format!("{}?json&page=2&per_page=3", server.url()))?;
+    assert_eq!(
+        resp.headers().get("link").unwrap(),
+        &format!(
+            r#"</?json&page=1&per_page=3>; rel="first", </?json&page=1&per_page=3>; rel="prev", </?json&page=3&per_page=3>; rel="next", </?json&page={last}&per_page=3>; rel="last""#
+        )
+    );
+    let resp = reqwest::blocking::get(format!("{}?json", server.url()))?;
+    assert!(resp.headers().get("link").is_none());
+    Ok(())
+}
+
+#[rstest]
+#[case("page=0")]
+#[case("page=x")]
+#[case("per_page=0")]
+fn get_dir_invalid_page(server: TestServer, #[case] query: &str) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}?json&{query}", server.url()))?;
+    assert_eq!(resp.status(), 400);
+    Ok(())
+}
+
+#[rstest]
+fn get_dir_json_by_accept(server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"GET", server.url())
+        .header("accept", "application/json")
+        .send()?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/json"
+    );
+    let json: Value = serde_json::from_str(&resp.text()?)?;
+    let paths = json["paths"].as_array().unwrap();
+    let item = paths.iter().find(|v| v["name"] == "test.txt").unwrap();
+    assert_eq!(item["path_type"], "File");
+    assert!(item["mtime"].is_u64());
+    assert!(item["size"].is_u64());
+    Ok(())
+}
+
+#[rstest]
+#[case(server(&["-A"] as &[&str]), "", "", None)]
+#[case(server(&["-A"] as &[&str]), "", "dir1/", Some("/"))]
+#[case(server(&["-A"] as &[&str]), "", "dir%201/sub/", Some("/dir%201/"))]
+#[case(server(&["-A", "--path-prefix", "xyz"] as &[&str]), "xyz/", "", None)]
+#[case(server(&["-A", "--path-prefix", "xyz"] as &[&str]), "xyz/", "dir1/", Some("/xyz/"))]
+fn get_dir_parent_href(
+    #[case] server: TestServer,
+    #[case] prefix: &str,
+    #[case] path: &str,
+    #[case] expect: Option<&str>,
+) -> Result<(), Error> {
+    let base = format!("{}{prefix}", server.url());
+    let resp = fetch!(b"MKCOL", format!("{base}dir%201/sub")).send()?;
+    assert_eq!(resp.status(), 201);
+    let resp = reqwest::blocking::get(format!("{base}{path}?json"))?;
+    assert_eq!(resp.status(), 200);
+    let json: Value = serde_json::from_str(&resp.text()?)?;
+    assert_eq!(json["parent_href"].as_str(), expect);
+    Ok(())
+}
+
+#[rstest]
+#[case(server(&["-A"] as &[&str]), None)]
+#[case(server(&["-A", "--size-units", "iec"] as &[&str]), Some("1.5 KiB"))]
+#[case(server(&["-A", "--size-units", "si"] as &[&str]), Some("1.5 kB"))]
+fn get_dir_human_size(
+    #[case] server: TestServer,
+    #[case] expect: Option<&str>,
+) -> Result<(), Error> {
+    let resp = fetch!(b"PUT", format!("{}sized/file1", server.url()))
+        .body(vec![b'a'; 1536])
+        .send()?;
+    assert_eq!(resp.status(), 201);
+    let resp = reqwest::blocking::get(format!("{}?json", server.url()))?;
+    let json: Value = serde_json::from_str(&resp.text()?)?;
+    let dir = json["paths"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|v| v["name"] == "sized")
+        .unwrap();
+    assert!(dir.get("human_size").is_none());
+    let resp = reqwest::blocking::get(format!("{}sized/?json", server.url()))?;
+    let json: Value = serde_json::from_str(&resp.text()?)?;
+    assert_eq!(json["paths"][0]["size"], 1536);
+    assert_eq!(json["paths"][0]["human_size"].as_str(), expect);
+    Ok(())
+}
+
+#[rstest]
+#[case(server(&["-A"] as &[&str]), false)]
+#[case(server(&["-A", "--recent-window", "3600"] as &[&str]), true)]
+fn get_dir_recent(#[case] server: TestServer, #[case] flagged: bool) -> Result<(), Error> {
+    let resp = fetch!(b"PUT", format!("{}recent/new.txt", server.url()))
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 201);
+    let old = std::fs::File::create(server.path().join("recent/old.txt"))?;
+    old.set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(7200))?;
+    let resp = reqwest::blocking::get(format!("{}recent/?json", server.url()))?;
+    let json: Value = serde_json::from_str(&resp.text()?)?;
+    let is_recent = |name: &str| {
+        let item = json["paths"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|v| v["name"] == name)
+            .unwrap();
+        item.get("is_recent").and_then(|v| v.as_bool())
+    };
+    assert_eq!(is_recent("new.txt"), flagged.then_some(true));
+    assert_eq!(is_recent("old.txt"), None);
+    Ok(())
+}
+
 #[rstest]
 fn get_dir_simple(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     let resp = reqwest::blocking::get(format!("{}?simple", server.url()))?;
@@ -82,6 +409,147 @@ fn get_dir_simple(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     Ok(())
 }
 
+#[rstest]
+fn get_dir_plain(#[with(&["-A", "--hidden", ".git"])] server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}?plain&sort=name&order=desc", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/plain; charset=utf-8"
+    );
+    let text = resp.text()?;
+    let lines: Vec<&str> = text.lines().collect();
+    let line = lines
+        .iter()
+        .find(|v| v.starts_with("index.html\t"))
+        .unwrap();
+    let fields: Vec<&str> = line.split('\t').collect();
+    assert_eq!(fields[1], "18");
+    assert!(fields[2].ends_with('Z'));
+    assert!(lines.iter().any(|v| v.starts_with("dir1/\t")));
+    assert!(!lines.iter().any(|v| v.starts_with(".git/")));
+    #[cfg(not(target_os = "windows"))]
+    assert!(lines.iter().any(|v| v.starts_with("file\\n1.txt\t")));
+    let resp = reqwest::blocking::get(format!("{}?simple&sort=name&order=desc", server.url()))?;
+    let first = resp.text()?.lines().next().unwrap().to_string();
+    assert!(lines[0].starts_with(&format!("{first}\t")));
+    Ok(())
+}
+
+#[rstest]
+fn get_dir_plain_by_accept(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"GET", server.url())
+        .header("accept", "text/plain")
+        .send()?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/plain; charset=utf-8"
+    );
+    assert!(resp.text()?.lines().any(|v| v.starts_with("index.html\t")));
+    let resp = fetch!(b"GET", server.url())
+        .header("accept", "text/html,text/plain;q=0.9")
+        .send()?;
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/html; charset=utf-8"
+    );
+    Ok(())
+}
+
+#[rstest]
+#[case(server(&[] as &[&str]), None, "en")]
+#[case(server(&[] as &[&str]), Some("zh-CN,zh;q=0.9,en;q=0.8"), "zh")]
+#[case(server(&[] as &[&str]), Some("ko, fr;q=0.5"), "fr")]
+#[case(server(&["--lang", "de"] as &[&str]), Some("zh-CN"), "de")]
+fn get_dir_lang(
+    #[case] server: TestServer,
+    #[case] accept_language: Option<&str>,
+    #[case] lang: &str,
+) -> Result<(), Error> {
+    let mut req = fetch!(b"GET", server.url());
+    if let Some(accept_language) = accept_language {
+        req = req.header("accept-language", accept_language);
+    }
+    let resp = req.send()?;
+    assert_eq!(resp.status(), 200);
+    let varies_by_lang = resp
+        .headers()
+        .get_all("vary")
+        .iter()
+        .any(|v| v == "Accept-Language");
+    assert_eq!(varies_by_lang, lang != "de");
+    let data = utils::retrieve_json(&resp.text()?).unwrap();
+    assert_eq!(data["lang"], lang);
+    Ok(())
+}
+
+#[rstest]
+fn get_dir_tree(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    for name in ["tree/a.txt", "tree/sub/b.txt"] {
+        let resp = fetch!(b"PUT", format!("{}{name}", server.url()))
+            .body(b"abc".to_vec())
+            .send()?;
+        assert_eq!(resp.status(), 201);
+    }
+    let resp = reqwest::blocking::get(format!("{}tree/?tree", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/json"
+    );
+    assert_eq!(
+        resp.text()?,
+        r#"[{"name":"sub","is_dir":true,"size":1},{"name":"a.txt","is_dir":false,"size":3}]"#
+    );
+    let resp = fetch!(b"HEAD", format!("{}tree/?tree", server.url())).send()?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.text()?, "");
+    Ok(())
+}
+
+#[rstest]
+#[case("?ndjson", None)]
+#[case("", Some("application/x-ndjson"))]
+fn get_dir_ndjson(
+    #[with(&["--hidden", "dir2"])] server: TestServer,
+    #[case] query: &str,
+    #[case] accept: Option<&str>,
+) -> Result<(), Error> {
+    let mut req = fetch!(b"GET", format!("{}{query}", server.url()));
+    if let Some(accept) = accept {
+        req = req.header("accept", accept);
+    }
+    let resp = req.send()?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/x-ndjson"
+    );
+    assert_eq!(resp.headers().get("vary").unwrap(), "Accept");
+    let text = resp.text()?;
+    let mut names: Vec<String> = text
+        .lines()
+        .map(|line| {
+            let item: Value = serde_json::from_str(line).unwrap();
+            item["name"].as_str().unwrap().to_string()
+        })
+        .collect();
+    names.sort();
+    let resp = reqwest::blocking::get(format!("{}?json", server.url()))?;
+    let json: Value = serde_json::from_str(&resp.text()?)?;
+    let mut expect: Vec<String> = json["paths"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v["name"].as_str().unwrap().to_string())
+        .collect();
+    expect.sort();
+    assert_eq!(names, expect);
+    assert!(!names.contains(&"dir2".to_string()));
+    Ok(())
+}
+
 #[rstest]
 fn head_dir_zip(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     let resp = fetch!(b"HEAD", format!("{}?zip", server.url())).send()?;
@@ -137,6 +605,42 @@ fn get_dir_search4(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     Ok(())
 }
 
+#[rstest]
+fn get_dir_search_regex(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!(
+        "{}?q={}&regex=1&simple",
+        server.url(),
+        urlencoding::encode(r"^test\.(txt|html)$")
+    ))?;
+    assert_eq!(resp.status(), 200);
+    let text = resp.text()?;
+    let names: Vec<_> = text.lines().collect();
+    assert!(names.contains(&"test.txt"));
+    assert!(names.contains(&"dir1/test.html"));
+    assert!(names
+        .iter()
+        .all(|v| v.ends_with("test.txt") || v.ends_with("test.html")));
+    Ok(())
+}
+
+#[rstest]
+fn get_dir_search_case(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}?q=TEST.html&simple", server.url()))?;
+    assert!(!resp.text()?.is_empty());
+    let resp = reqwest::blocking::get(format!("{}?q=TEST.html&case=1&simple", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    assert!(resp.text()?.is_empty());
+    Ok(())
+}
+
+#[rstest]
+fn get_dir_search_invalid_regex(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}?q=(&regex=1", server.url()))?;
+    assert_eq!(resp.status(), 400);
+    assert!(resp.text()?.contains("regex parse error"));
+    Ok(())
+}
+
 #[rstest]
 fn head_dir_search(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     let resp = fetch!(b"HEAD", format!("{}?q={}", server.url(), "test.html")).send()?;
@@ -149,6 +653,34 @@ fn head_dir_search(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     Ok(())
 }
 
+#[rstest]
+fn get_dir_grep(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}?json&grep=DIR1/test.TXT", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    let json: Value = serde_json::from_str(&resp.text()?)?;
+    let paths = json["paths"].as_array().unwrap();
+    assert_eq!(paths.len(), 1);
+    assert_eq!(paths[0]["name"], "dir1/test.txt");
+    assert_eq!(paths[0]["snippet"], "This is dir1/test.txt");
+    Ok(())
+}
+
+#[rstest]
+fn get_dir_grep_skips_binary(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}?json&grep=123", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    let json: Value = serde_json::from_str(&resp.text()?)?;
+    assert!(json["paths"].as_array().unwrap().is_empty());
+    Ok(())
+}
+
+#[rstest]
+fn get_dir_grep_not_allowed(server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}?grep=test", server.url()))?;
+    assert_eq!(resp.status(), 403);
+    Ok(())
+}
+
 #[rstest]
 fn empty_search(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     let resp = reqwest::blocking::get(format!("{}?q=", server.url()))?;
@@ -204,6 +736,65 @@ fn hash_file(server: TestServer) -> Result<(), Error> {
     Ok(())
 }
 
+/// How much the server's memory grows while a client requests a large file and reads nothing.
+#[cfg(target_os = "linux")]
+fn stalled_download_rss_growth(server: &TestServer) -> Result<u64, Error> {
+    use std::io::{Read, Write};
+    let size = 64 * 1024 * 1024;
+    std::fs::write(server.path().join("big.bin"), vec![1u8; size])?;
+    let rss_kb = || -> Result<u64, Error> {
+        let status = std::fs::read_to_string(format!("/proc/{}/status", server.pid()))?;
+        let line = status.lines().find(|v| v.starts_with("VmRSS:")).unwrap();
+        Ok(line.split_whitespace().nth(1).unwrap().parse()?)
+    };
+    let before = rss_kb()?;
+    let mut stream = std::net::TcpStream::connect(("127.0.0.1", server.port()))?;
+    stream.write_all(b"GET /big.bin HTTP/1.1\r\nHost: localhost\r\n\r\n")?;
+    std::thread::sleep(std::time::Duration::from_secs(1));
+    let stalled = rss_kb()?;
+    let mut received = 0;
+    let mut buf = vec![0; 65536];
+    while received < size {
+        let n = stream.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        received += n;
+    }
+    assert!(received >= size);
+    Ok(stalled.saturating_sub(before))
+}
+
+#[cfg(target_os = "linux")]
+#[rstest]
+fn get_file_slow_client(
+    #[with(&["--stream-buffer", "8192"])] server: TestServer,
+) -> Result<(), Error> {
+    // the client reads nothing, neither server may pull the file into memory meanwhile,
+    // and smaller chunks must not hold more than the default ones
+    let limited = stalled_download_rss_growth(&server)?;
+    let unlimited = stalled_download_rss_growth(&fixtures::server(&[] as &[&str]))?;
+    assert!(limited < 16 * 1024, "rss grew by {limited}kB");
+    assert!(unlimited < 16 * 1024, "rss grew by {unlimited}kB");
+    assert!(
+        limited <= unlimited + 1024,
+        "rss grew by {limited}kB, {unlimited}kB without --stream-buffer"
+    );
+    Ok(())
+}
+
+#[rstest]
+fn get_file_stream_buffer_large_headers(
+    #[with(&["--stream-buffer", "4096"])] server: TestServer,
+) -> Result<(), Error> {
+    // the chunk size only bounds response bodies, request headers keep hyper's own limit
+    let resp = fetch!(b"GET", format!("{}index.html", server.url()))
+        .header("x-padding", "a".repeat(32 * 1024))
+        .send()?;
+    assert_eq!(resp.status(), 200);
+    Ok(())
+}
+
 #[rstest]
 fn get_file_404(server: TestServer) -> Result<(), Error> {
     let resp = reqwest::blocking::get(format!("{}404", server.url()))?;
@@ -222,6 +813,27 @@ fn get_file_emoji_path(server: TestServer) -> Result<(), Error> {
     Ok(())
 }
 
+#[rstest]
+#[case(&[], "test.txt", "inline")]
+#[case(&["--attachment-extensions", "TXT,.bin"], "test.txt", "attachment")]
+#[case(&["--attachment-extensions", "txt"], "test.html", "inline")]
+#[case(&["--attachment-extensions", "*", "--inline-extensions", "html"], "test.txt", "attachment")]
+#[case(&["--attachment-extensions", "*", "--inline-extensions", "html"], "test.html", "inline")]
+fn get_file_disposition(
+    #[case] args: &[&str],
+    #[case] file: &str,
+    #[case] kind: &str,
+) -> Result<(), Error> {
+    let server = server(args);
+    let resp = reqwest::blocking::get(format!("{}{file}", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get("content-disposition").unwrap(),
+        &format!("{kind}; filename=\"{file}\"")
+    );
+    Ok(())
+}
+
 #[cfg(not(target_os = "windows"))]
 #[rstest]
 fn get_file_newline_path(server: TestServer) -> Result<(), Error> {
@@ -243,6 +855,18 @@ fn get_file_edit(server: TestServer) -> Result<(), Error> {
     Ok(())
 }
 
+#[rstest]
+#[case("index.html?edit")]
+#[case("index.html?view")]
+fn get_file_translate_f(server: TestServer, #[case] path: &str) -> Result<(), Error> {
+    let resp = fetch!(b"GET", format!("{}{path}", server.url()))
+        .header("translate", "f")
+        .send()?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.text()?, "This is index.html");
+    Ok(())
+}
+
 #[rstest]
 fn get_file_edit_bin(server: TestServer) -> Result<(), Error> {
     let resp = fetch!(b"GET", format!("{}{BIN_FILE}?edit", server.url())).send()?;
@@ -265,9 +889,79 @@ fn options_dir(server: TestServer) -> Result<(), Error> {
     assert_eq!(resp.status(), 200);
     assert_eq!(
         resp.headers().get("allow").unwrap(),
-        "GET,HEAD,PUT,OPTIONS,DELETE,PATCH,PROPFIND,COPY,MOVE,CHECKAUTH,LOGOUT"
+        "GET,HEAD,OPTIONS,PROPFIND,CHECKAUTH,LOGOUT"
     );
     assert_eq!(resp.headers().get("dav").unwrap(), "1, 2, 3");
+    assert_eq!(resp.headers().get("ms-author-via").unwrap(), "DAV");
+    Ok(())
+}
+
+#[rstest]
+#[case(&[], "GET,HEAD,OPTIONS,PROPFIND,CHECKAUTH,LOGOUT")]
+#[case(&["--allow-upload"], "GET,HEAD,PUT,OPTIONS,PATCH,PROPFIND,PROPPATCH,COPY,MKCOL,LOCK,UNLOCK,CHECKAUTH,LOGOUT")]
+#[case(&["-A"], "GET,HEAD,PUT,OPTIONS,DELETE,PATCH,PROPFIND,PROPPATCH,COPY,MOVE,MKCOL,LOCK,UNLOCK,CHECKAUTH,LOGOUT")]
+fn unknown_method_allow(#[case] args: &[&str], #[case] allow: &str) -> Result<(), Error> {
+    let server = server(args);
+    let resp = fetch!(b"FOO", format!("{}index.html", server.url())).send()?;
+    assert_eq!(resp.status(), 405);
+    assert_eq!(resp.headers().get("allow").unwrap(), allow);
+    let resp = fetch!(b"OPTIONS", format!("{}index.html", server.url())).send()?;
+    assert_eq!(resp.headers().get("allow").unwrap(), allow);
+    Ok(())
+}
+
+#[rstest]
+fn options_asterisk(
+    #[with(&["--auth", "user:pass@/:rw", "-A"])] server: TestServer,
+) -> Result<(), Error> {
+    use std::io::{Read, Write};
+    let mut stream = std::net::TcpStream::connect(("127.0.0.1", server.port()))?;
+    stream.write_all(b"OPTIONS * HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")?;
+    let mut output = String::new();
+    stream.read_to_string(&mut output)?;
+    let output = output.to_lowercase();
+    assert!(output.starts_with("http/1.1 200 ok\r\n"));
+    assert!(output.contains("\r\nallow: get,head,put,options,"));
+    assert!(output.contains("\r\ndav: 1, 2, 3\r\n"));
+    assert!(output.ends_with("\r\n\r\n"));
+    Ok(())
+}
+
+#[rstest]
+fn max_connections(
+    #[with(&["-A", "--max-connections", "1"])] server: TestServer,
+) -> Result<(), Error> {
+    use std::io::{Read, Write};
+    // an upload whose body is still on its way holds the only slot
+    let mut stream = std::net::TcpStream::connect(("127.0.0.1", server.port()))?;
+    stream.write_all(
+        b"PUT /file1 HTTP/1.1\r\nHost: localhost\r\nContent-Length: 6\r\nConnection: close\r\n\r\nabc",
+    )?;
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    let resp = reqwest::blocking::get(server.url())?;
+    assert_eq!(resp.status(), 503);
+    assert_eq!(resp.headers().get("retry-after").unwrap(), "1");
+    stream.write_all(b"def")?;
+    let mut output = String::new();
+    stream.read_to_string(&mut output)?;
+    assert!(output.starts_with("HTTP/1.1 201"));
+    let resp = reqwest::blocking::get(server.url())?;
+    assert_eq!(resp.status(), 200);
+    Ok(())
+}
+
+#[rstest]
+fn custom_headers(
+    #[with(&["--header", "X-Frame-Options: DENY", "--header", "Cache-Control: no-store"])]
+    server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}index.html", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers().get("x-frame-options").unwrap(), "DENY");
+    assert_eq!(resp.headers().get("cache-control").unwrap(), "no-store");
+    let resp = reqwest::blocking::get(format!("{}missing", server.url()))?;
+    assert_eq!(resp.status(), 404);
+    assert_eq!(resp.headers().get("x-frame-options").unwrap(), "DENY");
     Ok(())
 }
 
@@ -281,6 +975,125 @@ fn put_file(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     Ok(())
 }
 
+#[rstest]
+fn put_file_max_upload_size(
+    #[with(&["-A", "--max-upload-size", "4"])] server: TestServer,
+) -> Result<(), Error> {
+    let url = format!("{}file1", server.url());
+    let resp = fetch!(b"PUT", &url).body(b"abcd".to_vec()).send()?;
+    assert_eq!(resp.status(), 201);
+    let resp = fetch!(b"PUT", &url).body(b"abcde".to_vec()).send()?;
+    assert_eq!(resp.status(), 413);
+    assert_eq!(std::fs::read(server.path().join("file1"))?, b"abcd");
+    Ok(())
+}
+
+#[rstest]
+fn put_file_max_upload_size_chunked(
+    #[with(&["-A", "--max-upload-size", "4"])] server: TestServer,
+) -> Result<(), Error> {
+    use std::io::{Read, Write};
+    let mut stream = std::net::TcpStream::connect(("127.0.0.1", server.port()))?;
+    stream.set_read_timeout(Some(std::time::Duration::from_secs(5)))?;
+    stream.write_all(
+        b"PUT /chunked.txt HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n3\r\nabc\r\n3\r\ndef\r\n0\r\n\r\n",
+    )?;
+    let mut output = String::new();
+    let _ = stream.read_to_string(&mut output);
+    assert!(output.starts_with("HTTP/1.1 413"), "{output}");
+    assert!(!server.path().join("chunked.txt").exists());
+    Ok(())
+}
+
+#[rstest]
+fn put_file_max_upload_size_keeps_file(
+    #[with(&["-A", "--max-upload-size", "4"])] server: TestServer,
+) -> Result<(), Error> {
+    use std::io::{Read, Write};
+    std::fs::write(server.path().join("file1"), b"abcd")?;
+    let mut stream = std::net::TcpStream::connect(("127.0.0.1", server.port()))?;
+    stream.set_read_timeout(Some(std::time::Duration::from_secs(5)))?;
+    stream.write_all(
+        b"PUT /file1 HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n3\r\nxyz\r\n3\r\nxyz\r\n0\r\n\r\n",
+    )?;
+    let mut output = String::new();
+    let _ = stream.read_to_string(&mut output);
+    assert!(output.starts_with("HTTP/1.1 413"), "{output}");
+    assert_eq!(std::fs::read(server.path().join("file1"))?, b"abcd");
+    Ok(())
+}
+
+#[rstest]
+fn patch_file_max_upload_size(
+    #[with(&["-A", "--max-upload-size", "4"])] server: TestServer,
+) -> Result<(), Error> {
+    let url = format!("{}file1", server.url());
+    std::fs::write(server.path().join("file1"), b"abc")?;
+    let resp = fetch!(b"PATCH", &url)
+        .header("X-Update-Range", "append")
+        .body(b"d".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 204);
+    let resp = fetch!(b"PATCH", &url)
+        .header("X-Update-Range", "append")
+        .body(b"e".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 413);
+    let resp = fetch!(b"PUT", &url)
+        .header("Content-Range", "bytes 4-5/6")
+        .body(b"ef".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 413);
+    assert_eq!(std::fs::read(server.path().join("file1"))?, b"abcd");
+    Ok(())
+}
+
+#[rstest]
+#[case("abc", "3\r\nabc\r\n0\r\n\r\n")]
+#[case("abcdef", "3\r\nabc\r\n3;ext=1\r\ndef\r\n0\r\n\r\n")]
+#[case("", "0\r\n\r\n")]
+#[case("abc", "3\r\nabc\r\n0\r\nX-Checksum: 900150983cd24fb0\r\n\r\n")]
+fn put_file_chunked(
+    #[with(&["-A"])] server: TestServer,
+    #[case] expect: &str,
+    #[case] body: &str,
+) -> Result<(), Error> {
+    use std::io::{Read, Write};
+    let mut stream = std::net::TcpStream::connect(("127.0.0.1", server.port()))?;
+    stream.set_read_timeout(Some(std::time::Duration::from_secs(5)))?;
+    stream.write_all(
+        format!(
+            "PUT /chunked.txt HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n{body}"
+        )
+        .as_bytes(),
+    )?;
+    let mut output = String::new();
+    stream.read_to_string(&mut output)?;
+    assert!(output.starts_with("HTTP/1.1 201 Created\r\n"), "{output}");
+    let resp = reqwest::blocking::get(format!("{}chunked.txt", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.text()?, expect);
+    Ok(())
+}
+
+#[rstest]
+fn put_file_chunked_malformed(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    use std::io::{Read, Write};
+    let mut stream = std::net::TcpStream::connect(("127.0.0.1", server.port()))?;
+    stream.set_read_timeout(Some(std::time::Duration::from_secs(5)))?;
+    stream.write_all(
+        b"PUT /chunked.txt HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\nzz\r\n",
+    )?;
+    let mut output = String::new();
+    let _ = stream.read_to_string(&mut output);
+    assert!(
+        output.starts_with("HTTP/1.1 400 Bad Request\r\n"),
+        "{output}"
+    );
+    assert!(!server.path().join("chunked.txt").exists());
+    Ok(())
+}
+
 #[rstest]
 fn put_file_create_dir(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     let url = format!("{}xyz/file1", server.url());
@@ -292,10 +1105,277 @@ fn put_file_create_dir(#[with(&["-A"])] server: TestServer) -> Result<(), Error>
 }
 
 #[rstest]
-fn put_file_conflict_dir(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+#[case("content-md5", "kAFQmDzST7DWlj99KOF/cg==", 201)]
+#[case("digest", "md5=kAFQmDzST7DWlj99KOF/cg==", 201)]
+#[case("digest", "SHA-256=ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=", 201)]
+#[case("digest", "unixsum=123, md5=kAFQmDzST7DWlj99KOF/cg==", 201)]
+#[case("digest", "crc32c=abc", 201)]
+#[case("content-md5", "AAAAAAAAAAAAAAAAAAAAAA==", 400)]
+#[case("digest", "sha-256=AAAAAAAAAAAAAAAAAAAAAA==", 400)]
+#[case("content-md5", "not base64!", 400)]
+fn put_file_with_digest(
+    #[with(&["-A"])] server: TestServer,
+    #[case] header: &str,
+    #[case] value: &str,
+    #[case] status: u16,
+) -> Result<(), Error> {
+    let url = format!("{}file1", server.url());
+    let resp = fetch!(b"PUT", &url)
+        .header(header, value)
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), status);
+    let resp = reqwest::blocking::get(url)?;
+    assert_eq!(resp.status(), if status == 201 { 200 } else { 404 });
+    Ok(())
+}
+
+#[rstest]
+fn put_file_digest_mismatch_message(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"PUT", format!("{}file1", server.url()))
+        .header("content-md5", "AAAAAAAAAAAAAAAAAAAAAA==")
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 400);
+    assert_eq!(
+        resp.text()?,
+        "Digest Mismatch: expected AAAAAAAAAAAAAAAAAAAAAA==, got kAFQmDzST7DWlj99KOF/cg=="
+    );
+    Ok(())
+}
+
+#[rstest]
+fn put_file_digest_mismatch_keeps_file(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}test.txt", server.url());
+    let origin = reqwest::blocking::get(&url)?.text()?;
+    let resp = fetch!(b"PUT", &url)
+        .header("content-md5", "AAAAAAAAAAAAAAAAAAAAAA==")
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 400);
+    assert_eq!(reqwest::blocking::get(&url)?.text()?, origin);
+    let leftovers = std::fs::read_dir(server.path())?
+        .filter_map(|v| v.ok())
+        .filter(|v| v.file_name().to_string_lossy().contains(".dufs-upload-"))
+        .count();
+    assert_eq!(leftovers, 0);
+    Ok(())
+}
+
+/// Yields `size` bytes, then fails as a client that drops mid-upload.
+struct BrokenReader {
+    size: usize,
+}
+
+impl std::io::Read for BrokenReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.size == 0 {
+            return Err(std::io::Error::other("interrupted"));
+        }
+        let len = buf.len().min(self.size);
+        buf[..len].fill(b'a');
+        self.size -= len;
+        Ok(len)
+    }
+}
+
+#[rstest]
+fn put_file_interrupted_keeps_file(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}test.txt", server.url());
+    let origin = reqwest::blocking::get(&url)?.text()?;
+    let size = 24 * 1024 * 1024;
+    let resp = reqwest::blocking::Client::new()
+        .put(&url)
+        .body(reqwest::blocking::Body::sized(
+            BrokenReader { size },
+            size as u64 * 2,
+        ))
+        .send();
+    assert!(resp.is_err());
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    assert_eq!(reqwest::blocking::get(&url)?.text()?, origin);
+    let leftovers = std::fs::read_dir(server.path())?
+        .filter_map(|v| v.ok())
+        .filter(|v| v.file_name().to_string_lossy().contains(".dufs-upload-"))
+        .count();
+    assert_eq!(leftovers, 0);
+    Ok(())
+}
+
+#[rstest]
+fn put_file_nested_dirs(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    for name in ["folder/a.txt", "folder/sub/b.txt", "folder/sub/deep/c.txt"] {
+        let resp = fetch!(b"PUT", format!("{}{name}", server.url()))
+            .body(b"abc".to_vec())
+            .send()?;
+        assert_eq!(resp.status(), 201);
+    }
+    assert!(server.path().join("folder/sub/deep/c.txt").is_file());
+    let resp = fetch!(
+        b"PUT",
+        format!("{}folder%2F..%2F..%2Fescape.txt", server.url())
+    )
+    .body(b"abc".to_vec())
+    .send()?;
+    assert_eq!(resp.status(), 400);
+    assert!(!server.path().join("../escape.txt").exists());
+    Ok(())
+}
+
+#[rstest]
+#[case(server(&["-A"]))]
+#[case(server(&["--allow-upload"]))]
+fn put_file_conflict_dir(#[case] server: TestServer) -> Result<(), Error> {
     let url = format!("{}dir1", server.url());
     let resp = fetch!(b"PUT", &url).body(b"abc".to_vec()).send()?;
+    assert_eq!(resp.status(), 409);
+    assert_eq!(resp.text()?, "Target is a directory");
+    assert!(server.path().join("dir1").is_dir());
+    let resp = fetch!(b"PATCH", &url)
+        .header("X-Update-Range", "append")
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 409);
+    let resp = fetch!(b"PATCH", &url)
+        .header("X-Update-Range", "bytes=0-1")
+        .body(b"ab".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 409);
+    Ok(())
+}
+
+#[rstest]
+fn put_file_if_none_match_star(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}file1", server.url());
+    let resp = fetch!(b"PUT", &url)
+        .header("if-none-match", "*")
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 201);
+    let resp = fetch!(b"PUT", &url)
+        .header("if-none-match", "*")
+        .body(b"def".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 412);
+    assert_eq!(std::fs::read(server.path().join("file1"))?, b"abc");
+    Ok(())
+}
+
+#[rstest]
+fn put_file_if_none_match_star_race(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    use std::io::{Read, Write};
+    let mut stream = std::net::TcpStream::connect(("127.0.0.1", server.port()))?;
+    stream.set_read_timeout(Some(std::time::Duration::from_secs(5)))?;
+    stream.write_all(
+        b"PUT /file1 HTTP/1.1\r\nHost: localhost\r\nIf-None-Match: *\r\nContent-Length: 6\r\nConnection: close\r\n\r\nabc",
+    )?;
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    let resp = fetch!(b"PUT", format!("{}file1", server.url()))
+        .header("if-none-match", "*")
+        .body(b"xyz".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 412);
+    stream.write_all(b"def")?;
+    let mut output = String::new();
+    let _ = stream.read_to_string(&mut output);
+    assert!(output.starts_with("HTTP/1.1 201"), "{output}");
+    assert_eq!(std::fs::read(server.path().join("file1"))?, b"abcdef");
+    Ok(())
+}
+
+#[rstest]
+fn put_file_if_none_match_star_rejected(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"PUT", format!("{}file1", server.url()))
+        .header("if-none-match", "*")
+        .header("content-md5", "AAAAAAAAAAAAAAAAAAAAAA==")
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 400);
+    assert!(!server.path().join("file1").exists());
+    Ok(())
+}
+
+#[rstest]
+fn put_file_if_match(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}index.html", server.url());
+    let etag = reqwest::blocking::get(&url)?
+        .headers()
+        .get("etag")
+        .unwrap()
+        .clone();
+    let resp = fetch!(b"PUT", &url)
+        .header("if-match", r#""0-0""#)
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 412);
+    let resp = fetch!(b"PUT", &url)
+        .header("if-match", etag)
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 201);
+    let resp = fetch!(b"PUT", format!("{}missing", server.url()))
+        .header("if-match", "*")
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 412);
+    Ok(())
+}
+
+#[rstest]
+fn put_file_resumable(#[with(&["--allow-upload"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}file1", server.url());
+    let resp = fetch!(b"PUT", &url)
+        .header("Content-Range", "bytes 0-2/6")
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 204);
+    let resp = fetch!(b"HEAD", &url).send()?;
+    assert_eq!(resp.headers().get("content-length").unwrap(), "3");
+    let resp = fetch!(b"PUT", &url)
+        .header("Content-Range", "bytes 5-5/6")
+        .body(b"f".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 416);
+    assert_eq!(resp.headers().get("content-range").unwrap(), "bytes */3");
+    let resp = fetch!(b"PUT", &url)
+        .header("Content-Range", "bytes 0-2/6")
+        .body(b"abc".to_vec())
+        .send()?;
     assert_eq!(resp.status(), 403);
+    let resp = fetch!(b"PUT", &url)
+        .header("Content-Range", "bytes 3-5/6")
+        .body(b"def".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 201);
+    assert_eq!(std::fs::read(server.path().join("file1"))?, b"abcdef");
+    Ok(())
+}
+
+#[rstest]
+#[case("bytes 2-1/6")]
+#[case("bytes 0-6/6")]
+#[case("items 0-2/6")]
+#[case("bytes 0-2")]
+fn put_file_invalid_content_range(
+    #[with(&["-A"])] server: TestServer,
+    #[case] range: &str,
+) -> Result<(), Error> {
+    let resp = fetch!(b"PUT", format!("{}file1", server.url()))
+        .header("Content-Range", range)
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 400);
+    Ok(())
+}
+
+#[rstest]
+fn put_file_under_file(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}index.html/sub.txt", server.url());
+    let resp = fetch!(b"PUT", &url).body(b"abc".to_vec()).send()?;
+    assert_eq!(resp.status(), 409);
+    assert_eq!(resp.text()?, "Conflict");
+    let resp = fetch!(b"MKCOL", format!("{}index.html/sub", server.url())).send()?;
+    assert_eq!(resp.status(), 409);
     Ok(())
 }
 
@@ -361,3 +1441,103 @@ fn resumable_upload(#[with(&["--allow-upload"])] server: TestServer) -> Result<(
     assert_eq!(resp.text().unwrap(), "abc123");
     Ok(())
 }
+
+#[rstest]
+fn patch_update_range(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}file1", server.url());
+    let resp = fetch!(b"PUT", &url).body(b"abcdef".to_vec()).send()?;
+    assert_eq!(resp.status(), 201);
+    for (range, body, expect) in [
+        ("bytes=1-2", "XY", "aXYdef"),
+        ("bytes=6-", "gh", "aXYdefgh"),
+        ("bytes=-2", "ZZ", "aXYdefZZ"),
+    ] {
+        let resp = fetch!(b"PATCH", &url)
+            .header("X-Update-Range", range)
+            .body(body)
+            .send()?;
+        assert_eq!(resp.status(), 204);
+        assert_eq!(reqwest::blocking::get(&url)?.text()?, expect);
+    }
+    let resp = fetch!(b"PATCH", &url)
+        .header("X-Update-Range", "bytes=20-21")
+        .body("12")
+        .send()?;
+    assert_eq!(resp.status(), 416);
+    assert_eq!(resp.headers().get("content-range").unwrap(), "bytes */8");
+    let resp = fetch!(b"PATCH", &url)
+        .header("X-Update-Range", "bytes=0-1")
+        .body("123")
+        .send()?;
+    assert_eq!(resp.status(), 400);
+    let resp = fetch!(b"PATCH", &url)
+        .header("X-Update-Range", "bytes=3-1")
+        .body("12")
+        .send()?;
+    assert_eq!(resp.status(), 400);
+    assert_eq!(reqwest::blocking::get(&url)?.text()?, "aXYdefZZ");
+    Ok(())
+}
+
+#[rstest]
+fn patch_update_range_needs_delete(
+    #[with(&["--allow-upload"])] server: TestServer,
+) -> Result<(), Error> {
+    let url = format!("{}file1", server.url());
+    let resp = fetch!(b"PUT", &url).body(b"abc".to_vec()).send()?;
+    assert_eq!(resp.status(), 201);
+    let resp = fetch!(b"PATCH", &url)
+        .header("X-Update-Range", "bytes=0-1")
+        .body("XY")
+        .send()?;
+    assert_eq!(resp.status(), 403);
+    assert_eq!(reqwest::blocking::get(&url)?.text()?, "abc");
+    Ok(())
+}
+
+#[rstest]
+#[case("application/json", true)]
+#[case("text/html,application/json;q=0.9", false)]
+#[case("*/*", false)]
+fn json_error_body(
+    server: TestServer,
+    #[case] accept: &str,
+    #[case] is_json: bool,
+) -> Result<(), Error> {
+    let resp = fetch!(b"GET", format!("{}404", server.url()))
+        .header("accept", accept)
+        .send()?;
+    assert_eq!(resp.status(), 404);
+    assert_eq!(resp.headers().get("vary").unwrap(), "Accept");
+    let text = resp.text()?;
+    if is_json {
+        let json: Value = serde_json::from_str(&text)?;
+        assert_eq!(json["error"], "Not Found");
+        assert_eq!(json["status"], 404);
+    } else {
+        assert_eq!(text, "Not Found");
+    }
+    Ok(())
+}
+
+#[rstest]
+fn get_file_no_vary(server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}index.html", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    assert!(resp.headers().get("vary").is_none());
+    Ok(())
+}
+
+#[rstest]
+fn json_error_body_forbidden(server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"GET", format!("{}?q=test", server.url()))
+        .header("accept", "application/json")
+        .send()?;
+    assert_eq!(resp.status(), 403);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/json"
+    );
+    assert_eq!(resp.text()?, r#"{"error":"Forbidden","status":403}"#);
+    Ok(())
+}
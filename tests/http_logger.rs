@@ -8,7 +8,7 @@ use fixtures::{port, tmpdir, wait_for_port, Error};
 use assert_cmd::prelude::*;
 use assert_fs::fixture::TempDir;
 use rstest::rstest;
-use std::io::Read;
+use std::io::{BufRead, BufReader, Read};
 use std::process::{Command, Stdio};
 
 #[rstest]
@@ -52,6 +52,36 @@ fn log_remote_user(
     Ok(())
 }
 
+#[rstest]
+fn log_redacts_share_token(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("dufs")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .args(["--log-format", "$request"])
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    wait_for_port(port);
+
+    let stdout = child.stdout.as_mut().expect("Failed to get stdout");
+
+    let url = format!("http://localhost:{port}/?expires=1&token=s3cr3t&json");
+    let resp = fetch!(b"GET", &url).send()?;
+    assert_eq!(resp.status(), 200);
+
+    let mut buf = [0; 2048];
+    let buf_len = stdout.read(&mut buf)?;
+    let output = std::str::from_utf8(&buf[0..buf_len])?;
+
+    let line = output.lines().last().unwrap();
+    assert!(line.ends_with("GET /?expires=1&token=REDACTED&json"));
+    assert!(!output.contains("s3cr3t"));
+
+    child.kill()?;
+    Ok(())
+}
+
 #[rstest]
 #[case(&["--log-format", ""])]
 fn no_log(tmpdir: TempDir, port: u16, #[case] args: &[&str]) -> Result<(), Error> {
@@ -79,3 +109,64 @@ fn no_log(tmpdir: TempDir, port: u16, #[case] args: &[&str]) -> Result<(), Error
     child.kill()?;
     Ok(())
 }
+
+#[rstest]
+fn log_auth_rule(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("dufs")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .args([
+            "-A",
+            "-a",
+            "user:pass@/:ro,/dir1:rw",
+            "-a",
+            "@/dir2",
+            "--log-format",
+            "$auth_result $auth_rule",
+        ])
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    wait_for_port(port);
+
+    let mut stdout = BufReader::new(child.stdout.take().expect("Failed to get stdout"));
+    let url = format!("http://localhost:{port}");
+    // skips the startup banner, returns what follows the log level
+    let mut last_line = || -> Result<String, Error> {
+        loop {
+            let mut line = String::new();
+            stdout.read_line(&mut line)?;
+            if let Some((_, log)) = line.trim_end().split_once(" INFO - ") {
+                return Ok(log.to_string());
+            }
+        }
+    };
+
+    let resp = fetch!(b"PUT", format!("{url}/dir1/a.txt"))
+        .basic_auth("user", Some("pass"))
+        .body("abc")
+        .send()?;
+    assert_eq!(resp.status(), 201);
+    assert_eq!(last_line()?, "granted /dir1:rw");
+
+    let resp = fetch!(b"PUT", format!("{url}/a.txt"))
+        .basic_auth("user", Some("pass"))
+        .body("abc")
+        .send()?;
+    assert_eq!(resp.status(), 403);
+    assert_eq!(last_line()?, "denied /:ro");
+
+    let resp = fetch!(b"GET", format!("{url}/dir2/")).send()?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(last_line()?, "granted /dir2:ro");
+
+    let resp = fetch!(b"GET", format!("{url}/dir1/"))
+        .basic_auth("user", Some("wrong"))
+        .send()?;
+    assert_eq!(resp.status(), 401);
+    assert_eq!(last_line()?, "rejected -");
+
+    child.kill()?;
+    Ok(())
+}
@@ -18,15 +18,43 @@ fn get_file_range(server: TestServer) -> Result<(), Error> {
     Ok(())
 }
 
+#[rstest]
+fn get_file_range_loose_syntax(server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"GET", format!("{}index.html", server.url()))
+        .header("range", HeaderValue::from_static("Bytes= 0-6"))
+        .send()?;
+    assert_eq!(resp.status(), 206);
+    assert_eq!(resp.headers().get("content-range").unwrap(), "bytes 0-6/18");
+    assert_eq!(resp.text()?, "This is");
+    Ok(())
+}
+
+#[rstest]
+fn head_file_range(server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"HEAD", format!("{}index.html", server.url()))
+        .header("range", HeaderValue::from_static("bytes=0-6"))
+        .send()?;
+    assert_eq!(resp.status(), 206);
+    assert_eq!(resp.headers().get("content-range").unwrap(), "bytes 0-6/18");
+    assert_eq!(resp.headers().get("accept-ranges").unwrap(), "bytes");
+    assert_eq!(resp.headers().get("content-length").unwrap(), "7");
+    assert_eq!(resp.text()?, "");
+    Ok(())
+}
+
 #[rstest]
 fn get_file_range_beyond(server: TestServer) -> Result<(), Error> {
     let resp = fetch!(b"GET", format!("{}index.html", server.url()))
         .header("range", HeaderValue::from_static("bytes=12-20"))
         .send()?;
-    assert_eq!(resp.status(), 416);
-    assert_eq!(resp.headers().get("content-range").unwrap(), "bytes */18");
+    assert_eq!(resp.status(), 206);
+    assert_eq!(
+        resp.headers().get("content-range").unwrap(),
+        "bytes 12-17/18"
+    );
     assert_eq!(resp.headers().get("accept-ranges").unwrap(), "bytes");
-    assert_eq!(resp.headers().get("content-length").unwrap(), "0");
+    assert_eq!(resp.headers().get("content-length").unwrap(), "6");
+    assert_eq!(resp.text()?, "x.html");
     Ok(())
 }
 
@@ -39,3 +67,181 @@ fn get_file_range_invalid(server: TestServer) -> Result<(), Error> {
     assert_eq!(resp.headers().get("content-range").unwrap(), "bytes */18");
     Ok(())
 }
+
+#[rstest]
+fn get_file_range_full(server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"GET", format!("{}index.html", server.url()))
+        .header("range", HeaderValue::from_static("bytes=0-"))
+        .send()?;
+    assert_eq!(resp.status(), 206);
+    assert_eq!(
+        resp.headers().get("content-range").unwrap(),
+        "bytes 0-17/18"
+    );
+    assert_eq!(resp.headers().get("content-length").unwrap(), "18");
+    assert_eq!(resp.text()?, "This is index.html");
+    Ok(())
+}
+
+#[rstest]
+fn get_file_range_full_with_if_range(server: TestServer) -> Result<(), Error> {
+    let url = format!("{}index.html", server.url());
+    let resp = reqwest::blocking::get(&url)?;
+    let etag = resp.headers().get("etag").unwrap().clone();
+    let resp = fetch!(b"GET", &url)
+        .header("range", HeaderValue::from_static("bytes=0-"))
+        .header("if-range", etag)
+        .send()?;
+    assert_eq!(resp.status(), 206);
+    assert_eq!(
+        resp.headers().get("content-range").unwrap(),
+        "bytes 0-17/18"
+    );
+    Ok(())
+}
+
+#[rstest]
+fn get_file_range_first_byte(server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"GET", format!("{}index.html", server.url()))
+        .header("range", HeaderValue::from_static("bytes=0-0"))
+        .send()?;
+    assert_eq!(resp.status(), 206);
+    assert_eq!(resp.headers().get("content-range").unwrap(), "bytes 0-0/18");
+    assert_eq!(resp.headers().get("content-length").unwrap(), "1");
+    assert_eq!(resp.text()?, "T");
+    Ok(())
+}
+
+#[rstest]
+fn get_file_range_last_byte(server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"GET", format!("{}index.html", server.url()))
+        .header("range", HeaderValue::from_static("bytes=17-17"))
+        .send()?;
+    assert_eq!(resp.status(), 206);
+    assert_eq!(
+        resp.headers().get("content-range").unwrap(),
+        "bytes 17-17/18"
+    );
+    assert_eq!(resp.text()?, "l");
+    Ok(())
+}
+
+#[rstest]
+#[case("bytes=-4", "bytes 14-17/18", "html")]
+#[case("bytes=-18", "bytes 0-17/18", "This is index.html")]
+#[case("bytes=-500", "bytes 0-17/18", "This is index.html")]
+fn get_file_suffix_range(
+    server: TestServer,
+    #[case] range: &str,
+    #[case] content_range: &str,
+    #[case] expect: &str,
+) -> Result<(), Error> {
+    let resp = fetch!(b"GET", format!("{}index.html", server.url()))
+        .header("range", HeaderValue::from_str(range)?)
+        .send()?;
+    assert_eq!(resp.status(), 206);
+    assert_eq!(resp.headers().get("content-range").unwrap(), content_range);
+    assert_eq!(resp.text()?, expect);
+    Ok(())
+}
+
+#[rstest]
+#[case("bytes=18-")]
+#[case("bytes=18-18")]
+#[case("bytes=-0")]
+fn get_file_range_unsatisfiable(server: TestServer, #[case] range: &str) -> Result<(), Error> {
+    let resp = fetch!(b"GET", format!("{}index.html", server.url()))
+        .header("range", HeaderValue::from_str(range)?)
+        .send()?;
+    assert_eq!(resp.status(), 416);
+    assert_eq!(resp.headers().get("content-range").unwrap(), "bytes */18");
+    Ok(())
+}
+
+#[rstest]
+#[case("bytes=0-")]
+#[case("bytes=0-0")]
+#[case("bytes=-1")]
+fn get_empty_file_range(
+    #[with(&["-A"])] server: TestServer,
+    #[case] range: &str,
+) -> Result<(), Error> {
+    let url = format!("{}empty.txt", server.url());
+    let resp = fetch!(b"PUT", &url).body(vec![]).send()?;
+    assert_eq!(resp.status(), 201);
+    let resp = fetch!(b"GET", &url)
+        .header("range", HeaderValue::from_str(range)?)
+        .send()?;
+    assert_eq!(resp.status(), 200);
+    assert!(resp.headers().get("content-range").is_none());
+    assert_eq!(resp.headers().get("content-length").unwrap(), "0");
+    assert_eq!(resp.text()?, "");
+    Ok(())
+}
+
+#[rstest]
+fn get_file_multiple_ranges(server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"GET", format!("{}index.html", server.url()))
+        .header("range", HeaderValue::from_static("bytes=8-9, 0-3"))
+        .send()?;
+    assert_eq!(resp.status(), 206);
+    assert!(resp.headers().get("content-range").is_none());
+    let content_type = resp.headers().get("content-type").unwrap().to_str()?;
+    let boundary = content_type
+        .strip_prefix("multipart/byteranges; boundary=")
+        .unwrap()
+        .to_string();
+    let content_length: usize = resp
+        .headers()
+        .get("content-length")
+        .unwrap()
+        .to_str()?
+        .parse()?;
+    let body = resp.text()?;
+    assert_eq!(body.len(), content_length);
+    assert_eq!(
+        body,
+        format!(
+            "--{boundary}\r\nContent-Type: text/html; charset=UTF-8\r\nContent-Range: bytes 0-3/18\r\n\r\nThis\r\n\
+             --{boundary}\r\nContent-Type: text/html; charset=UTF-8\r\nContent-Range: bytes 8-9/18\r\n\r\nin\r\n\
+             --{boundary}--\r\n"
+        )
+    );
+    Ok(())
+}
+
+#[rstest]
+#[case("bytes=0-3,2-6", "bytes 0-6/18", "This is")]
+#[case("bytes=0-3,40-50", "bytes 0-3/18", "This")]
+#[case("bytes=0-3,2-40", "bytes 0-17/18", "This is index.html")]
+fn get_file_multiple_ranges_coalesced(
+    server: TestServer,
+    #[case] range: &'static str,
+    #[case] content_range: &str,
+    #[case] expect: &str,
+) -> Result<(), Error> {
+    let resp = fetch!(b"GET", format!("{}index.html", server.url()))
+        .header("range", HeaderValue::from_static(range))
+        .send()?;
+    assert_eq!(resp.status(), 206);
+    assert_eq!(resp.headers().get("content-range").unwrap(), content_range);
+    assert_eq!(resp.text()?, expect);
+    Ok(())
+}
+
+#[rstest]
+fn head_file_multiple_ranges(server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"HEAD", format!("{}index.html", server.url()))
+        .header("range", HeaderValue::from_static("bytes=0-1,4-5"))
+        .send()?;
+    assert_eq!(resp.status(), 206);
+    assert!(resp
+        .headers()
+        .get("content-type")
+        .unwrap()
+        .to_str()?
+        .starts_with("multipart/byteranges; boundary="));
+    assert!(resp.headers().get("content-length").is_some());
+    assert_eq!(resp.text()?, "");
+    Ok(())
+}
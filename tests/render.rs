@@ -53,13 +53,33 @@ fn render_try_index3(
 }
 
 #[rstest]
-#[case(server(&["--render-try-index"] as &[&str]), false)]
-#[case(server(&["--render-try-index", "--allow-search"] as &[&str]), true)]
-fn render_try_index4(#[case] server: TestServer, #[case] searched: bool) -> Result<(), Error> {
+fn render_try_index_archive_disabled(
+    #[with(&["--render-try-index"])] server: TestServer,
+) -> Result<(), Error> {
+    for query in ["zip", "tar", "tar.gz"] {
+        let resp = reqwest::blocking::get(format!("{}{}?{query}", server.url(), DIR_NO_INDEX))?;
+        assert_eq!(resp.status(), 404);
+    }
+    Ok(())
+}
+
+#[rstest]
+fn render_try_index4(
+    #[with(&["--render-try-index", "--allow-search"])] server: TestServer,
+) -> Result<(), Error> {
     let resp = reqwest::blocking::get(format!("{}{}?q={}", server.url(), DIR_NO_INDEX, BIN_FILE))?;
     assert_eq!(resp.status(), 200);
     let paths = utils::retrieve_index_paths(&resp.text()?);
-    assert_eq!(paths.iter().all(|v| v.contains(BIN_FILE)), searched);
+    assert!(paths.iter().all(|v| v.contains(BIN_FILE)));
+    Ok(())
+}
+
+#[rstest]
+fn render_try_index_search_disabled(
+    #[with(&["--render-try-index"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}{}?q={}", server.url(), DIR_NO_INDEX, BIN_FILE))?;
+    assert_eq!(resp.status(), 403);
     Ok(())
 }
 
@@ -78,3 +98,94 @@ fn render_spa2(#[with(&["--render-spa"])] server: TestServer) -> Result<(), Erro
     assert_eq!(text, "This is index.html");
     Ok(())
 }
+
+#[rstest]
+fn render_spa_translate_f(#[with(&["--render-spa"])] server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"GET", format!("{}{}", server.url(), DIR_NO_FOUND))
+        .header("translate", "f")
+        .send()?;
+    assert_eq!(resp.status(), 404);
+    Ok(())
+}
+
+#[rstest]
+#[case("test", 200, "This is test.html")]
+#[case("dir1/test", 200, "This is dir1/test.html")]
+#[case("test.txt", 200, "This is test.txt")]
+#[case("dir1/missing", 404, "Not Found")]
+fn render_try_files(
+    #[with(&["--try-files", "$uri.html"])] server: TestServer,
+    #[case] path: &str,
+    #[case] status: u16,
+    #[case] text: &str,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}{path}", server.url()))?;
+    assert_eq!(resp.status(), status);
+    assert_eq!(resp.text()?, text);
+    Ok(())
+}
+
+#[rstest]
+fn render_try_files_attachment(
+    #[with(&["--try-files", "$uri.html", "--attachment-extensions", "html"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}test", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get("content-disposition").unwrap(),
+        r#"attachment; filename="test.html""#
+    );
+    Ok(())
+}
+
+#[rstest]
+fn render_try_files_fallback(
+    #[with(&["--try-files", "$uri.html,/index.html"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}{}", server.url(), DIR_NO_FOUND))?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.text()?, "This is index.html");
+    Ok(())
+}
+
+#[rstest]
+fn render_try_files_respects_auth(
+    #[with(&["--try-files", "/test.html", "--auth", "user:pass@/:rw", "--auth", "@/dir1"])]
+    server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}dir1/missing", server.url()))?;
+    assert_eq!(resp.status(), 404);
+    Ok(())
+}
+
+#[rstest]
+#[case(&["--index-redirect", "/dir1/"], "", "/dir1/")]
+#[case(&["--index-redirect", "dir1"], "?q=1", "/dir1?q=1")]
+#[case(&["--index-redirect", "/dir1/", "--path-prefix", "xyz"], "xyz/", "/xyz/dir1/")]
+fn render_index_redirect(
+    #[case] args: &[&str],
+    #[case] path: &str,
+    #[case] location: &str,
+) -> Result<(), Error> {
+    let server = server(args);
+    let client = reqwest::blocking::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?;
+    let resp = client.get(format!("{}{path}", server.url())).send()?;
+    assert_eq!(resp.status(), 302);
+    assert_eq!(resp.headers().get("location").unwrap(), location);
+    let resp = client.head(format!("{}{path}", server.url())).send()?;
+    assert_eq!(resp.status(), 302);
+    Ok(())
+}
+
+#[rstest]
+fn render_index_redirect_only_root(
+    #[with(&["--index-redirect", "/dir1/"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}index.html", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    let resp = fetch!(b"PROPFIND", server.url()).send()?;
+    assert_eq!(resp.status(), 207);
+    Ok(())
+}
@@ -0,0 +1,111 @@
+mod fixtures;
+mod utils;
+
+use assert_cmd::prelude::*;
+use fixtures::{server, Error, TestServer};
+use rstest::rstest;
+use std::process::Command;
+
+const SECRET: &str = "s3cret";
+
+/// The query string of a link printed by `dufs --share`.
+fn mint(path: &str, extra_args: &[&str]) -> Result<String, Error> {
+    let output = Command::cargo_bin("dufs")?
+        .args(["--share-secret", SECRET, "--share", path])
+        .args(extra_args)
+        .output()?;
+    assert!(output.status.success());
+    let link = String::from_utf8(output.stdout)?;
+    Ok(link.trim().split_once('?').unwrap().1.to_string())
+}
+
+#[test]
+fn share_needs_secret() -> Result<(), Error> {
+    Command::cargo_bin("dufs")?
+        .args(["--share", "dir1"])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+#[test]
+fn share_prints_link() -> Result<(), Error> {
+    let output = Command::cargo_bin("dufs")?
+        .args([
+            "--share-secret",
+            SECRET,
+            "--share",
+            "/dir 1/",
+            "-b",
+            "127.0.0.1",
+            "-p",
+            "5000",
+        ])
+        .output()?;
+    let link = String::from_utf8(output.stdout)?;
+    assert!(link.starts_with("http://127.0.0.1:5000/dir%201/?expires="));
+    assert!(link.contains("&token="));
+    Ok(())
+}
+
+#[rstest]
+fn share_grants_read(
+    #[with(&["--share-secret", SECRET, "--auth", "user:pass@/:rw", "-A"])] server: TestServer,
+) -> Result<(), Error> {
+    let query = mint("dir1/", &[])?;
+    let resp = reqwest::blocking::get(format!("{}dir1/?{query}", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    let resp = reqwest::blocking::get(format!("{}dir1/test.html?{query}", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.text()?, "This is dir1/test.html");
+    let resp = reqwest::blocking::get(format!("{}dir2/?{query}", server.url()))?;
+    assert_eq!(resp.status(), 401);
+    let resp = reqwest::blocking::get(format!("{}dir1/", server.url()))?;
+    assert_eq!(resp.status(), 401);
+    let resp = fetch!(b"PUT", format!("{}dir1/file1?{query}", server.url()))
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 401);
+    let tampered = query.replace("expires=", "expires=1");
+    let resp = reqwest::blocking::get(format!("{}dir1/?{tampered}", server.url()))?;
+    assert_eq!(resp.status(), 401);
+    Ok(())
+}
+
+#[rstest]
+fn share_expired(
+    #[with(&["--share-secret", SECRET, "--auth", "user:pass@/:rw"])] server: TestServer,
+) -> Result<(), Error> {
+    let query = mint("index.html", &["--share-ttl", "1"])?;
+    let url = format!("{}index.html?{query}", server.url());
+    assert_eq!(reqwest::blocking::get(&url)?.status(), 200);
+    std::thread::sleep(std::time::Duration::from_millis(2100));
+    assert_eq!(reqwest::blocking::get(&url)?.status(), 401);
+    Ok(())
+}
+
+#[rstest]
+fn share_under_alias_and_prefix(
+    #[with(&[
+        "--share-secret",
+        SECRET,
+        "--auth",
+        "user:pass@/:rw",
+        "--alias",
+        "latest=dir1",
+        "--path-prefix",
+        "xyz",
+    ])]
+    server: TestServer,
+) -> Result<(), Error> {
+    let query = mint(
+        "latest/",
+        &["--alias", "latest=dir1", "--path-prefix", "xyz"],
+    )?;
+    let resp = reqwest::blocking::get(format!("{}xyz/latest/test.html?{query}", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.text()?, "This is dir1/test.html");
+    let resp = reqwest::blocking::get(format!("{}xyz/dir2/?{query}", server.url()))?;
+    assert_eq!(resp.status(), 401);
+    Ok(())
+}
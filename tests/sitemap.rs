@@ -0,0 +1,72 @@
+mod fixtures;
+mod utils;
+
+use fixtures::{server, Error, TestServer};
+use rstest::rstest;
+
+#[rstest]
+fn sitemap_disabled(server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}sitemap.xml", server.url()))?;
+    assert_eq!(resp.status(), 404);
+    Ok(())
+}
+
+#[rstest]
+fn sitemap(#[with(&["--sitemap", "--hidden", "dir2"])] server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}sitemap.xml", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/xml; charset=utf-8"
+    );
+    let etag = resp.headers().get("etag").unwrap().clone();
+    let text = resp.text()?;
+    assert!(text.contains(r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#));
+    assert!(text.contains(&format!(
+        "<url><loc>{}index.html</loc><lastmod>",
+        server.url()
+    )));
+    assert!(text.contains(&format!("<loc>{}dir1/test.html</loc>", server.url())));
+    assert!(!text.contains("/dir2/"));
+    let resp = fetch!(b"GET", format!("{}sitemap.xml", server.url()))
+        .header("if-none-match", etag)
+        .send()?;
+    assert_eq!(resp.status(), 304);
+    Ok(())
+}
+
+#[rstest]
+fn sitemap_cached_per_host(#[with(&["--sitemap"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}sitemap.xml", server.url());
+    let get = |host: &str| -> Result<String, Error> {
+        Ok(fetch!(b"GET", &url).header("host", host).send()?.text()?)
+    };
+    assert!(get("a.example")?.contains("<loc>http://a.example/dir1/test.html</loc>"));
+    assert!(get("b.example")?.contains("<loc>http://b.example/dir1/test.html</loc>"));
+    // below the root, so the root mtime is unchanged and the cached sitemaps are reused
+    std::fs::write(server.path().join("dir1/new.html"), "new")?;
+    assert!(!get("a.example")?.contains("new.html"));
+    assert!(!get("b.example")?.contains("new.html"));
+    Ok(())
+}
+
+#[rstest]
+fn sitemap_anonymous_paths(
+    #[with(&["--sitemap", "--auth", "user:pass@/:rw", "--auth", "@/dir1"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}sitemap.xml", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    let text = resp.text()?;
+    assert!(text.contains(&format!("<loc>{}dir1/test.html</loc>", server.url())));
+    assert!(!text.contains(&format!("<loc>{}index.html</loc>", server.url())));
+    Ok(())
+}
+
+#[rstest]
+fn sitemap_no_anonymous(
+    #[with(&["--sitemap", "--auth", "user:pass@/:rw"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}sitemap.xml", server.url()))?;
+    assert_eq!(resp.status(), 404);
+    Ok(())
+}
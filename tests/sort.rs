@@ -17,7 +17,7 @@ fn ls_dir_sort_by_name(server: TestServer) -> Result<(), Error> {
 }
 
 #[rstest]
-fn search_dir_sort_by_name(server: TestServer) -> Result<(), Error> {
+fn search_dir_sort_by_name(#[with(&["--allow-search"])] server: TestServer) -> Result<(), Error> {
     let url = server.url();
     let resp = reqwest::blocking::get(format!("{url}?q=test.html&sort=name&order=asc"))?;
     let paths1 = self::utils::retrieve_index_paths(&resp.text()?);
@@ -27,3 +27,50 @@ fn search_dir_sort_by_name(server: TestServer) -> Result<(), Error> {
     assert_eq!(paths1, paths2);
     Ok(())
 }
+
+#[rstest]
+#[case(server(&["-A"] as &[&str]), "a2\nA10\nb1\nB3\n")]
+#[case(server(&["-A", "--sort-case-sensitive"] as &[&str]), "A10\nB3\na2\nb1\n")]
+fn ls_dir_natural_sort(#[case] server: TestServer, #[case] expect: &str) -> Result<(), Error> {
+    for name in ["A10", "a2", "B3", "b1"] {
+        let url = format!("{}sorted/{name}", server.url());
+        let resp = fetch!(b"PUT", &url).body(b"abc".to_vec()).send()?;
+        assert_eq!(resp.status(), 201);
+    }
+    let resp = reqwest::blocking::get(format!("{}sorted/?simple", server.url()))?;
+    assert_eq!(resp.text()?, expect);
+    Ok(())
+}
+
+#[rstest]
+#[case(server(&["-A"] as &[&str]), "a/\nc/\nb1\nd1\n")]
+#[case(server(&["-A", "--group-dirs", "last"] as &[&str]), "b1\nd1\na/\nc/\n")]
+#[case(server(&["-A", "--group-dirs", "none"] as &[&str]), "a/\nb1\nc/\nd1\n")]
+fn ls_dir_group_dirs(#[case] server: TestServer, #[case] expect: &str) -> Result<(), Error> {
+    for name in ["b1", "d1"] {
+        let url = format!("{}sorted/{name}", server.url());
+        let resp = fetch!(b"PUT", &url).body(b"abc".to_vec()).send()?;
+        assert_eq!(resp.status(), 201);
+    }
+    for name in ["a", "c"] {
+        let resp = fetch!(b"MKCOL", format!("{}sorted/{name}", server.url())).send()?;
+        assert_eq!(resp.status(), 201);
+    }
+    let resp = reqwest::blocking::get(format!("{}sorted/?simple", server.url()))?;
+    assert_eq!(resp.text()?, expect);
+    Ok(())
+}
+
+#[rstest]
+fn ls_dir_sort_in_json(server: TestServer) -> Result<(), Error> {
+    let url = server.url();
+    let resp = reqwest::blocking::get(format!("{url}?json&sort=size&order=desc"))?;
+    let json: serde_json::Value = serde_json::from_str(&resp.text()?)?;
+    assert_eq!(json["sort"], "size");
+    assert_eq!(json["order"], "desc");
+    let resp = reqwest::blocking::get(format!("{url}?json"))?;
+    let json: serde_json::Value = serde_json::from_str(&resp.text()?)?;
+    assert_eq!(json["sort"], "name");
+    assert_eq!(json["order"], "asc");
+    Ok(())
+}
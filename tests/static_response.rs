@@ -0,0 +1,77 @@
+mod fixtures;
+mod utils;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, server, tmpdir, Error, TestServer};
+use rstest::rstest;
+use std::process::{Command, Stdio};
+
+#[rstest]
+fn static_response_inline(
+    #[with(&["--static-response", "/.well-known/token.txt=abc123", "--auth", "user:pass@/"])]
+    server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}.well-known/token.txt", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/plain; charset=utf-8"
+    );
+    assert_eq!(resp.text()?, "abc123");
+    let resp = reqwest::blocking::get(format!("{}index.html", server.url()))?;
+    assert_eq!(resp.status(), 401);
+    Ok(())
+}
+
+#[rstest]
+fn static_response_file(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let file = tmpdir.path().join("dir1/test.html");
+    let mapping = format!(
+        "/.well-known/webfinger:application/jrd+json=@{}",
+        file.display()
+    );
+    let mut child = Command::cargo_bin("dufs")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .args(["--static-response", &mapping])
+        .stdout(Stdio::piped())
+        .spawn()?;
+    fixtures::wait_for_port(port);
+
+    let url = format!("http://localhost:{port}/.well-known/webfinger");
+    let resp = reqwest::blocking::get(&url)?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/jrd+json"
+    );
+    assert_eq!(resp.text()?, "This is dir1/test.html");
+
+    let resp = fetch!(b"HEAD", &url).send()?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers().get("content-length").unwrap(), "22");
+    assert_eq!(resp.text()?, "");
+
+    let resp = fetch!(b"PUT", &url).body(b"abc".to_vec()).send()?;
+    assert_eq!(resp.status(), 405);
+    assert_eq!(resp.headers().get("allow").unwrap(), "GET, HEAD");
+
+    child.kill()?;
+    Ok(())
+}
+
+#[rstest]
+#[case("/token.txt")]
+#[case("/=abc")]
+#[case("/../x=abc")]
+#[case("/x=@missing-file")]
+#[case("/x:not a type=abc")]
+fn static_response_invalid(#[case] value: &str) -> Result<(), Error> {
+    Command::cargo_bin("dufs")?
+        .args(["--static-response", value])
+        .assert()
+        .failure();
+    Ok(())
+}
@@ -44,3 +44,87 @@ fn allow_symlink(
     assert!(paths.contains(&format!("{dir}/")));
     Ok(())
 }
+
+#[rstest]
+fn show_symlink_target(
+    #[with(&["--allow-symlink", "--show-symlink-target"])] server: TestServer,
+    tmpdir: TempDir,
+) -> Result<(), Error> {
+    let dir = "foo";
+    symlink_dir(tmpdir.path(), server.path().join(dir)).expect("Couldn't create symlink");
+    let resp = reqwest::blocking::get(format!("{}?json", server.url()))?;
+    let json: serde_json::Value = serde_json::from_str(&resp.text()?)?;
+    let item = json["paths"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|v| v["name"] == dir)
+        .unwrap();
+    assert_eq!(item["path_type"], "SymlinkDir");
+    assert_eq!(
+        item["symlink_target"].as_str().unwrap(),
+        tmpdir.path().to_string_lossy()
+    );
+    let item = json["paths"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|v| v["name"] == "index.html")
+        .unwrap();
+    assert!(item.get("symlink_target").is_none());
+    Ok(())
+}
+
+#[rstest]
+fn propfind_symlink(
+    #[with(&["--allow-symlink", "--show-symlink-target"])] server: TestServer,
+    tmpdir: TempDir,
+) -> Result<(), Error> {
+    let dir = "foo";
+    symlink_dir(tmpdir.path(), server.path().join(dir)).expect("Couldn't create symlink");
+    let resp = reqwest::blocking::get(format!("{}?json", server.url()))?;
+    let json: serde_json::Value = serde_json::from_str(&resp.text()?)?;
+    assert_eq!(json["allow_symlink"], true);
+    let resp = fetch!(b"PROPFIND", server.url())
+        .header("depth", "1")
+        .send()?;
+    assert_eq!(resp.status(), 207);
+    let body = resp.text()?;
+    let response = body
+        .split("<D:response>")
+        .find(|v| v.contains(&format!("<D:href>/{dir}/</D:href>")))
+        .unwrap();
+    assert!(response.contains(&format!(
+        r#"<dufs:symlink xmlns:dufs="https://github.com/sigoden/dufs">{}</dufs:symlink>"#,
+        tmpdir.path().display()
+    )));
+    assert_eq!(body.matches("<dufs:symlink ").count(), 1);
+    Ok(())
+}
+
+#[rstest]
+fn tar_keeps_inner_symlinks(
+    #[with(&["--allow-archive"])] server: TestServer,
+    tmpdir: TempDir,
+) -> Result<(), Error> {
+    symlink_dir(server.path().join("dir1"), server.path().join("inner"))
+        .expect("Couldn't create symlink");
+    symlink_dir(tmpdir.path(), server.path().join("outer")).expect("Couldn't create symlink");
+    let resp = reqwest::blocking::get(format!("{}?tar", server.url()))?;
+    assert_eq!(resp.status(), 200);
+    let body = resp.bytes()?;
+    let mut archive = tar::Archive::new(&body[..]);
+    let mut found_inner = false;
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let path = entry.path()?.to_string_lossy().to_string();
+        assert!(!path.starts_with("outer"));
+        if path == "inner" {
+            assert!(entry.header().entry_type().is_symlink());
+            assert_eq!(entry.link_name()?.unwrap().to_string_lossy(), "dir1");
+            found_inner = true;
+        }
+    }
+    assert!(found_inner);
+    Ok(())
+}
@@ -71,3 +71,39 @@ fn wrong_path_key() -> Result<(), Error> {
 
     Ok(())
 }
+
+/// Advertises HTTP/3 to TLS clients when `--http3` is set.
+#[cfg(feature = "http3")]
+#[rstest]
+fn http3_alt_svc(
+    #[with(&[
+        "--tls-cert", "tests/data/cert.pem",
+        "--tls-key", "tests/data/key_pkcs8.pem",
+        "--http3",
+    ])]
+    server: TestServer,
+) -> Result<(), Error> {
+    let client = ClientBuilder::new()
+        .danger_accept_invalid_certs(true)
+        .build()?;
+    let resp = client.get(server.url()).send()?.error_for_status()?;
+    assert_eq!(
+        resp.headers().get("alt-svc").unwrap(),
+        &format!("h3=\":{}\"; ma=86400", server.port())
+    );
+    Ok(())
+}
+
+/// HTTP/3 needs the TLS certificate for QUIC.
+#[cfg(feature = "http3")]
+#[rstest]
+fn http3_requires_tls() -> Result<(), Error> {
+    let port = port().to_string();
+    Command::cargo_bin("dufs")?
+        .args(["--http3", "--port", &port])
+        .assert()
+        .failure()
+        .stderr(contains("HTTP/3 requires tls-cert and tls-key"));
+
+    Ok(())
+}
@@ -0,0 +1,196 @@
+mod fixtures;
+mod utils;
+
+use fixtures::{server, Error, TestServer};
+use rstest::rstest;
+use serde_json::Value;
+use std::io::Read;
+use std::thread::sleep;
+use std::time::Duration;
+
+const UPLOAD_STATUS_PATH: &str = "__dufs__/upload-status";
+
+/// Yields one chunk, then stalls before yielding the rest.
+struct SlowReader {
+    chunks: Vec<Vec<u8>>,
+}
+
+impl Read for SlowReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.chunks.is_empty() {
+            return Ok(0);
+        }
+        let chunk = self.chunks.remove(0);
+        if self.chunks.is_empty() {
+            sleep(Duration::from_millis(1000));
+        }
+        buf[..chunk.len()].copy_from_slice(&chunk);
+        Ok(chunk.len())
+    }
+}
+
+#[rstest]
+fn upload_status(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}file1", server.url());
+    let status_url = format!("{}{UPLOAD_STATUS_PATH}/abc", server.url());
+    let uploader = std::thread::spawn(move || {
+        let reader = SlowReader {
+            chunks: vec![vec![b'a'; 100], vec![b'b'; 100]],
+        };
+        reqwest::blocking::Client::new()
+            .put(&url)
+            .header("X-Upload-Id", "abc")
+            .body(reqwest::blocking::Body::sized(reader, 200))
+            .send()
+            .map(|v| v.status())
+    });
+
+    sleep(Duration::from_millis(500));
+    let resp = reqwest::blocking::get(&status_url)?;
+    assert_eq!(resp.status(), 200);
+    let json: Value = serde_json::from_str(&resp.text()?)?;
+    assert_eq!(json["received"], 100);
+    assert_eq!(json["total"], 200);
+    assert_eq!(json["percent"], 50.0);
+
+    assert_eq!(uploader.join().unwrap()?, 201);
+    let resp = reqwest::blocking::get(&status_url)?;
+    assert_eq!(resp.status(), 404);
+    Ok(())
+}
+
+#[rstest]
+fn upload_status_unknown(server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(format!("{}{UPLOAD_STATUS_PATH}/xyz", server.url()))?;
+    assert_eq!(resp.status(), 404);
+    Ok(())
+}
+
+#[rstest]
+fn upload_status_auth(
+    #[with(&["-A", "--auth", "user:pass@/:rw", "--auth", "user2:pass2@/:rw"])] server: TestServer,
+) -> Result<(), Error> {
+    let url = format!("{}file1", server.url());
+    let status_url = format!("{}{UPLOAD_STATUS_PATH}/abc", server.url());
+    let uploader = std::thread::spawn(move || {
+        let reader = SlowReader {
+            chunks: vec![vec![b'a'; 100], vec![b'b'; 100]],
+        };
+        reqwest::blocking::Client::new()
+            .put(&url)
+            .basic_auth("user", Some("pass"))
+            .header("X-Upload-Id", "abc")
+            .body(reqwest::blocking::Body::sized(reader, 200))
+            .send()
+            .map(|v| v.status())
+    });
+
+    sleep(Duration::from_millis(500));
+    let resp = reqwest::blocking::get(&status_url)?;
+    assert_eq!(resp.status(), 404);
+    let resp = fetch!(b"GET", &status_url)
+        .basic_auth("user2", Some("pass2"))
+        .send()?;
+    assert_eq!(resp.status(), 404);
+    let resp = fetch!(b"GET", &status_url)
+        .basic_auth("user", Some("wrong"))
+        .send()?;
+    assert_eq!(resp.status(), 403);
+    let resp = fetch!(b"GET", &status_url)
+        .basic_auth("user", Some("pass"))
+        .send()?;
+    assert_eq!(resp.status(), 200);
+
+    assert_eq!(uploader.join().unwrap()?, 201);
+    Ok(())
+}
+
+#[rstest]
+fn upload_status_duplicate_id(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}file1", server.url());
+    let uploader = std::thread::spawn(move || {
+        let reader = SlowReader {
+            chunks: vec![vec![b'a'; 100], vec![b'b'; 100]],
+        };
+        reqwest::blocking::Client::new()
+            .put(&url)
+            .header("X-Upload-Id", "abc")
+            .body(reqwest::blocking::Body::sized(reader, 200))
+            .send()
+            .map(|v| v.status())
+    });
+
+    sleep(Duration::from_millis(500));
+    let resp = fetch!(b"PUT", format!("{}file2", server.url()))
+        .header("X-Upload-Id", "abc")
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 409);
+    assert!(!server.path().join("file2").exists());
+
+    assert_eq!(uploader.join().unwrap()?, 201);
+    Ok(())
+}
+
+#[rstest]
+fn max_concurrent_uploads(
+    #[with(&["-A", "--max-concurrent-uploads", "1"])] server: TestServer,
+) -> Result<(), Error> {
+    let url = format!("{}file1", server.url());
+    let uploader = std::thread::spawn(move || {
+        let reader = SlowReader {
+            chunks: vec![vec![b'a'; 100], vec![b'b'; 100]],
+        };
+        reqwest::blocking::Client::new()
+            .put(&url)
+            .body(reqwest::blocking::Body::sized(reader, 200))
+            .send()
+            .map(|v| v.status())
+    });
+
+    sleep(Duration::from_millis(500));
+    let resp = fetch!(b"PUT", format!("{}file2", server.url()))
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 429);
+    assert_eq!(resp.headers().get("retry-after").unwrap(), "1");
+
+    assert_eq!(uploader.join().unwrap()?, 201);
+    let resp = fetch!(b"PUT", format!("{}file2", server.url()))
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 201);
+    Ok(())
+}
+
+#[rstest]
+fn resumed_upload_rejected_keeps_file(
+    #[with(&["-A", "--max-concurrent-uploads", "1"])] server: TestServer,
+) -> Result<(), Error> {
+    let url = format!("{}file2", server.url());
+    let resp = fetch!(b"PUT", &url).body(b"abcdef".to_vec()).send()?;
+    assert_eq!(resp.status(), 201);
+
+    let slow_url = format!("{}file1", server.url());
+    let uploader = std::thread::spawn(move || {
+        let reader = SlowReader {
+            chunks: vec![vec![b'a'; 100], vec![b'b'; 100]],
+        };
+        reqwest::blocking::Client::new()
+            .put(&slow_url)
+            .body(reqwest::blocking::Body::sized(reader, 200))
+            .send()
+            .map(|v| v.status())
+    });
+
+    sleep(Duration::from_millis(500));
+    let resp = fetch!(b"PUT", &url)
+        .header("Content-Range", "bytes 2-3/4")
+        .body(b"XY".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 429);
+    assert_eq!(reqwest::blocking::get(&url)?.text()?, "abcdef");
+
+    assert_eq!(uploader.join().unwrap()?, 201);
+    Ok(())
+}
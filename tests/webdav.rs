@@ -47,7 +47,85 @@ fn propfind_dir_depth2(server: TestServer) -> Result<(), Error> {
         .send()?;
     assert_eq!(resp.status(), 400);
     let body = resp.text()?;
-    assert_eq!(body, "Invalid depth: only 0 and 1 are allowed.");
+    assert_eq!(body, "Invalid depth: only 0, 1 and infinity are allowed.");
+    Ok(())
+}
+
+#[rstest]
+fn propfind_dir_depth_infinity_deny(server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"PROPFIND", format!("{}dir1", server.url()))
+        .header("depth", "infinity")
+        .send()?;
+    assert_eq!(resp.status(), 403);
+    let body = resp.text()?;
+    assert!(body.contains("<D:propfind-finite-depth/>"));
+    Ok(())
+}
+
+#[rstest]
+#[case(server(&["-A", "--dav-infinite-depth", "allow"] as &[&str]), &["a/", "a/b/", "a/b/c/", "a/b/c/d/"])]
+#[case(server(&["-A", "--dav-infinite-depth", "limit=1"] as &[&str]), &["a/"])]
+#[case(server(&["-A", "--dav-infinite-depth", "limit=3"] as &[&str]), &["a/", "a/b/", "a/b/c/"])]
+fn propfind_dir_depth_infinity(
+    #[case] server: TestServer,
+    #[case] expect: &[&str],
+) -> Result<(), Error> {
+    let resp = fetch!(b"MKCOL", format!("{}deep/a/b/c/d", server.url())).send()?;
+    assert_eq!(resp.status(), 201);
+    let resp = fetch!(b"PROPFIND", format!("{}deep", server.url()))
+        .header("depth", "Infinity")
+        .send()?;
+    assert_eq!(resp.status(), 207);
+    let body = resp.text()?;
+    let hrefs: Vec<_> = body
+        .lines()
+        .filter_map(|v| v.strip_prefix("<D:href>/deep/"))
+        .filter_map(|v| v.strip_suffix("</D:href>"))
+        .filter(|v| !v.is_empty())
+        .collect();
+    assert_eq!(hrefs, expect);
+    Ok(())
+}
+
+#[rstest]
+fn propfind_dir_if_none_match(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}dir1", server.url());
+    let resp = fetch!(b"PROPFIND", &url).send()?;
+    assert_eq!(resp.status(), 207);
+    let etag = resp.headers().get("etag").unwrap().clone();
+    assert!(resp.headers().contains_key("last-modified"));
+
+    let resp = fetch!(b"PROPFIND", &url)
+        .header("if-none-match", etag.clone())
+        .send()?;
+    assert_eq!(resp.status(), 304);
+    assert_eq!(resp.headers().get("etag").unwrap(), &etag);
+    assert_eq!(resp.text()?, "");
+
+    // a child changing its content must invalidate the listing
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    let resp = fetch!(b"PUT", format!("{url}/test.html"))
+        .body(b"changed content".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 201);
+    let resp = fetch!(b"PROPFIND", &url)
+        .header("if-none-match", etag.clone())
+        .send()?;
+    assert_eq!(resp.status(), 207);
+    assert_ne!(resp.headers().get("etag").unwrap(), &etag);
+    Ok(())
+}
+
+#[rstest]
+fn propfind_file_if_modified_since(server: TestServer) -> Result<(), Error> {
+    let url = format!("{}test.html", server.url());
+    let resp = fetch!(b"PROPFIND", &url).send()?;
+    assert_eq!(resp.status(), 207);
+    let last_modified = resp.headers().get("last-modified").unwrap().clone();
+    let resp = fetch!(b"PROPFIND", &url)
+        .header("if-modified-since", last_modified)
+        .send()?;
+    assert_eq!(resp.status(), 304);
     Ok(())
 }
 
@@ -58,6 +136,28 @@ fn propfind_404(server: TestServer) -> Result<(), Error> {
     Ok(())
 }
 
+#[rstest]
+fn propfind_body_too_large(server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"PROPFIND", server.url())
+        .body(vec![b' '; 1024 * 1024])
+        .send()?;
+    assert_eq!(resp.status(), 413);
+    Ok(())
+}
+
+#[rstest]
+fn propfind_max_list_entries(
+    #[with(&["--max-list-entries", "3"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = fetch!(b"PROPFIND", format!("{}dir1/", server.url())).send()?;
+    assert_eq!(resp.status(), 207);
+    let body = resp.text()?;
+    assert!(body.contains("507 Insufficient Storage"));
+    let resp = fetch!(b"PROPFIND", format!("{}dir4/", server.url())).send()?;
+    assert!(!resp.text()?.contains("507 Insufficient Storage"));
+    Ok(())
+}
+
 #[rstest]
 fn propfind_double_slash(server: TestServer) -> Result<(), Error> {
     let resp = fetch!(b"PROPFIND", server.url()).send()?;
@@ -81,6 +181,62 @@ fn propfind_file(server: TestServer) -> Result<(), Error> {
     Ok(())
 }
 
+#[rstest]
+fn propfind_content_type_and_supportedlock(server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"PROPFIND", format!("{}test.html", server.url())).send()?;
+    assert_eq!(resp.status(), 207);
+    let body = resp.text()?;
+    assert!(body.contains("<D:getcontenttype>text/html</D:getcontenttype>"));
+    assert!(body.contains("<D:supportedlock><D:lockentry><D:lockscope><D:exclusive/></D:lockscope><D:locktype><D:write/></D:locktype></D:lockentry></D:supportedlock>"));
+    let resp = fetch!(b"PROPFIND", format!("{}dir1", server.url()))
+        .header("depth", "0")
+        .send()?;
+    let body = resp.text()?;
+    assert!(body.contains("<D:supportedlock>"));
+    assert!(!body.contains("<D:getcontenttype>"));
+    Ok(())
+}
+
+#[rstest]
+fn propfind_propname(server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"PROPFIND", format!("{}test.html", server.url()))
+        .body(r#"<D:propfind xmlns:D="DAV:"><D:propname/></D:propfind>"#)
+        .send()?;
+    assert_eq!(resp.status(), 207);
+    let body = resp.text()?;
+    assert!(body.contains("<D:displayname/>"));
+    assert!(body.contains("<D:getcontentlength/>"));
+    assert!(!body.contains("<D:displayname>"));
+    Ok(())
+}
+
+#[rstest]
+fn propfind_named_props(server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"PROPFIND", format!("{}test.html", server.url()))
+        .body(
+            r#"<D:propfind xmlns:D="DAV:"><D:prop xmlns:Z="urn:example">
+<D:getcontentlength/><Z:color/>
+</D:prop></D:propfind>"#,
+        )
+        .send()?;
+    assert_eq!(resp.status(), 207);
+    let body = resp.text()?;
+    assert!(body.contains("<D:getcontentlength>"));
+    assert!(!body.contains("<D:displayname>"));
+    assert!(body.contains(r#"<x:color xmlns:x="urn:example"/>"#));
+    assert!(body.contains("<D:status>HTTP/1.1 404 Not Found</D:status>"));
+    Ok(())
+}
+
+#[rstest]
+fn propfind_invalid_body(server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"PROPFIND", format!("{}dir1", server.url()))
+        .body("<D:propfind")
+        .send()?;
+    assert_eq!(resp.status(), 400);
+    Ok(())
+}
+
 #[rstest]
 fn proppatch_file(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     let resp = fetch!(b"PROPPATCH", format!("{}test.html", server.url())).send()?;
@@ -97,6 +253,141 @@ fn proppatch_404(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     Ok(())
 }
 
+const WIN32_PROPPATCH: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+<D:propertyupdate xmlns:D="DAV:" xmlns:Z="urn:schemas-microsoft-com:">
+<D:set><D:prop>
+<Z:Win32FileAttributes>00000020</Z:Win32FileAttributes>
+<D:getlastmodified>Mon, 01 Jan 2024 00:00:00 GMT</D:getlastmodified>
+</D:prop></D:set>
+</D:propertyupdate>"#;
+
+#[rstest]
+fn proppatch_persists_dead_props(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}test.html", server.url());
+    let resp = fetch!(b"PROPPATCH", &url).body(WIN32_PROPPATCH).send()?;
+    assert_eq!(resp.status(), 207);
+    let body = resp.text()?;
+    assert!(body.contains("<D:status>HTTP/1.1 200 OK</D:status>"));
+    assert!(!body.contains("403 Forbidden"));
+    let body = fetch!(b"PROPFIND", &url).send()?.text()?;
+    assert!(body.contains(
+        r#"<x:Win32FileAttributes xmlns:x="urn:schemas-microsoft-com:">00000020</x:Win32FileAttributes>"#
+    ));
+    assert!(body.contains("<D:getlastmodified>Mon, 01 Jan 2024 00:00:00 GMT</D:getlastmodified>"));
+    let body = fetch!(b"PROPFIND", server.url()).send()?.text()?;
+    assert!(body.contains("Win32FileAttributes"));
+    assert!(!body.contains(".dufs-props.json"));
+    let resp = reqwest::blocking::get(format!("{}.dufs-props.json", server.url()))?;
+    assert_eq!(resp.status(), 404);
+    let resp = fetch!(b"PUT", format!("{}.DUFS-Props.json", server.url()))
+        .body(b"{}".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 404);
+
+    let resp = fetch!(b"PROPPATCH", &url)
+        .body(
+            r#"<D:propertyupdate xmlns:D="DAV:" xmlns:Z="urn:schemas-microsoft-com:">
+<D:remove><D:prop><Z:Win32FileAttributes/></D:prop></D:remove>
+</D:propertyupdate>"#,
+        )
+        .send()?;
+    assert_eq!(resp.status(), 207);
+    let body = fetch!(b"PROPFIND", &url).send()?.text()?;
+    assert!(!body.contains("Win32FileAttributes"));
+    Ok(())
+}
+
+#[rstest]
+fn proppatch_protected_prop(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}test.html", server.url());
+    let resp = fetch!(b"PROPPATCH", &url)
+        .body(
+            r#"<D:propertyupdate xmlns:D="DAV:" xmlns:Z="urn:schemas-microsoft-com:">
+<D:set><D:prop>
+<D:getcontentlength>1</D:getcontentlength>
+<Z:Win32FileAttributes>00000020</Z:Win32FileAttributes>
+</D:prop></D:set>
+</D:propertyupdate>"#,
+        )
+        .send()?;
+    assert_eq!(resp.status(), 207);
+    let body = resp.text()?;
+    assert!(body.contains("<D:status>HTTP/1.1 403 Forbidden</D:status>"));
+    assert!(body.contains("<D:status>HTTP/1.1 424 Failed Dependency</D:status>"));
+    let body = fetch!(b"PROPFIND", &url).send()?.text()?;
+    assert!(!body.contains("Win32FileAttributes"));
+    Ok(())
+}
+
+#[rstest]
+fn proppatch_not_allow_upload(server: TestServer) -> Result<(), Error> {
+    let url = format!("{}test.html", server.url());
+    let resp = fetch!(b"PROPPATCH", &url).body(WIN32_PROPPATCH).send()?;
+    assert_eq!(resp.status(), 403);
+    Ok(())
+}
+
+#[rstest]
+fn proppatch_body_too_large(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}test.html", server.url());
+    let resp = fetch!(b"PROPPATCH", &url)
+        .body(vec![b' '; 1024 * 1024])
+        .send()?;
+    assert_eq!(resp.status(), 413);
+    Ok(())
+}
+
+#[rstest]
+fn proppatch_concurrent(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let url = format!("{}test.html", server.url());
+            std::thread::spawn(move || {
+                let body = format!(
+                    r#"<D:propertyupdate xmlns:D="DAV:" xmlns:Z="urn:x">
+<D:set><D:prop><Z:p{i}>{i}</Z:p{i}></D:prop></D:set>
+</D:propertyupdate>"#
+                );
+                let method = reqwest::Method::from_bytes(b"PROPPATCH").unwrap();
+                reqwest::blocking::Client::new()
+                    .request(method, &url)
+                    .body(body)
+                    .send()
+                    .map(|v| v.status())
+            })
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap()?, 207);
+    }
+    let body = fetch!(b"PROPFIND", format!("{}test.html", server.url()))
+        .send()?
+        .text()?;
+    for i in 0..8 {
+        assert!(body.contains(&format!("p{i}")), "{body}");
+    }
+    Ok(())
+}
+
+#[rstest]
+fn proppatch_props_follow_move(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let origin_url = format!("{}test.html", server.url());
+    let new_url = format!("{}dir1/test2.html", server.url());
+    let resp = fetch!(b"PROPPATCH", &origin_url)
+        .body(WIN32_PROPPATCH)
+        .send()?;
+    assert_eq!(resp.status(), 207);
+    let resp = fetch!(b"MOVE", &origin_url)
+        .header("Destination", &new_url)
+        .send()?;
+    assert_eq!(resp.status(), 201);
+    let body = fetch!(b"PROPFIND", &new_url).send()?.text()?;
+    assert!(body.contains("Win32FileAttributes"));
+    let body = fetch!(b"PROPFIND", server.url()).send()?.text()?;
+    assert!(!body.contains("Win32FileAttributes"));
+    Ok(())
+}
+
 #[rstest]
 fn mkcol_dir(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     let resp = fetch!(b"MKCOL", format!("{}newdir", server.url())).send()?;
@@ -104,6 +395,21 @@ fn mkcol_dir(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     Ok(())
 }
 
+#[rstest]
+fn mkcol_with_body(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"MKCOL", format!("{}newdir", server.url()))
+        .header("content-type", "application/xml")
+        .body(r#"<?xml version="1.0"?><D:mkcol xmlns:D="DAV:"/>"#)
+        .send()?;
+    assert_eq!(resp.status(), 415);
+    assert!(!server.path().join("newdir").exists());
+    let resp = fetch!(b"MKCOL", format!("{}newdir", server.url()))
+        .header("content-length", "0")
+        .send()?;
+    assert_eq!(resp.status(), 201);
+    Ok(())
+}
+
 #[rstest]
 fn mkcol_not_allow_upload(server: TestServer) -> Result<(), Error> {
     let resp = fetch!(b"MKCOL", format!("{}newdir", server.url())).send()?;
@@ -124,12 +430,47 @@ fn copy_file(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     let resp = fetch!(b"COPY", format!("{}test.html", server.url()))
         .header("Destination", &new_url)
         .send()?;
-    assert_eq!(resp.status(), 204);
+    assert_eq!(resp.status(), 201);
     let resp = reqwest::blocking::get(new_url)?;
     assert_eq!(resp.status(), 200);
     Ok(())
 }
 
+#[rstest]
+fn copy_file_replace(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let new_url = format!("{}index.html", server.url());
+    let resp = fetch!(b"COPY", format!("{}test.html", server.url()))
+        .header("Destination", &new_url)
+        .header("Overwrite", "F")
+        .send()?;
+    assert_eq!(resp.status(), 412);
+    assert_eq!(
+        reqwest::blocking::get(&new_url)?.text()?,
+        "This is index.html"
+    );
+    let resp = fetch!(b"COPY", format!("{}test.html", server.url()))
+        .header("Destination", &new_url)
+        .header("Overwrite", "T")
+        .send()?;
+    assert_eq!(resp.status(), 204);
+    assert_eq!(
+        reqwest::blocking::get(&new_url)?.text()?,
+        "This is test.html"
+    );
+    Ok(())
+}
+
+#[rstest]
+fn copy_replace_not_allow_delete(
+    #[with(&["--allow-upload"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = fetch!(b"COPY", format!("{}test.html", server.url()))
+        .header("Destination", format!("{}index.html", server.url()))
+        .send()?;
+    assert_eq!(resp.status(), 403);
+    Ok(())
+}
+
 #[rstest]
 fn copy_not_allow_upload(server: TestServer) -> Result<(), Error> {
     let new_url = format!("{}test2.html", server.url());
@@ -157,7 +498,7 @@ fn move_file(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     let resp = fetch!(b"MOVE", &origin_url)
         .header("Destination", &new_url)
         .send()?;
-    assert_eq!(resp.status(), 204);
+    assert_eq!(resp.status(), 201);
     let resp = reqwest::blocking::get(new_url)?;
     assert_eq!(resp.status(), 200);
     let resp = reqwest::blocking::get(origin_url)?;
@@ -165,6 +506,41 @@ fn move_file(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     Ok(())
 }
 
+#[rstest]
+fn move_file_replace_dir(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let origin_url = format!("{}test.html", server.url());
+    let new_url = format!("{}dir1", server.url());
+    let resp = fetch!(b"MOVE", &origin_url)
+        .header("Destination", &new_url)
+        .send()?;
+    assert_eq!(resp.status(), 204);
+    assert_eq!(
+        reqwest::blocking::get(&new_url)?.text()?,
+        "This is test.html"
+    );
+    let resp = fetch!(b"MOVE", &new_url)
+        .header("Destination", &new_url)
+        .send()?;
+    assert_eq!(resp.status(), 403);
+    Ok(())
+}
+
+#[rstest]
+fn move_nested_in_source_or_dest(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let resp = fetch!(b"MOVE", format!("{}dir1", server.url()))
+        .header("Destination", format!("{}dir1/sub", server.url()))
+        .send()?;
+    assert_eq!(resp.status(), 403);
+    let file_url = format!("{}dir1/test.html", server.url());
+    let resp = fetch!(b"MOVE", &file_url)
+        .header("Destination", format!("{}dir1", server.url()))
+        .send()?;
+    assert_eq!(resp.status(), 403);
+    let resp = reqwest::blocking::get(&file_url)?;
+    assert_eq!(resp.status(), 200);
+    Ok(())
+}
+
 #[rstest]
 fn move_not_allow_upload(#[with(&["--allow-delete"])] server: TestServer) -> Result<(), Error> {
     let origin_url = format!("{}test.html", server.url());
@@ -206,6 +582,186 @@ fn lock_file(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     Ok(())
 }
 
+#[rstest]
+fn lock_not_allow_upload(server: TestServer) -> Result<(), Error> {
+    let url = format!("{}test.html", server.url());
+    let resp = fetch!(b"LOCK", &url).send()?;
+    assert_eq!(resp.status(), 403);
+    let resp = fetch!(b"UNLOCK", &url)
+        .header(
+            "Lock-Token",
+            "<opaquelocktoken:00000000-0000-0000-0000-000000000000>",
+        )
+        .send()?;
+    assert_eq!(resp.status(), 403);
+    Ok(())
+}
+
+#[rstest]
+fn lock_file_exclusive(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}dir1/test.html", server.url());
+    let resp = fetch!(b"LOCK", &url).send()?;
+    assert_eq!(resp.status(), 200);
+    let token = resp
+        .headers()
+        .get("lock-token")
+        .unwrap()
+        .to_str()?
+        .to_string();
+    assert!(token.starts_with("<opaquelocktoken:"));
+
+    let resp = fetch!(b"LOCK", &url).send()?;
+    assert_eq!(resp.status(), 423);
+    let resp = fetch!(b"PUT", &url).body(b"abc".to_vec()).send()?;
+    assert_eq!(resp.status(), 423);
+    let resp = fetch!(b"DELETE", &url).send()?;
+    assert_eq!(resp.status(), 423);
+    let resp = fetch!(b"DELETE", format!("{}dir1", server.url())).send()?;
+    assert_eq!(resp.status(), 423);
+    let resp = fetch!(b"COPY", format!("{}test.html", server.url()))
+        .header("Destination", &url)
+        .send()?;
+    assert_eq!(resp.status(), 423);
+    let resp = fetch!(b"PUT", &url)
+        .header(
+            "If",
+            "(<opaquelocktoken:00000000-0000-0000-0000-000000000000>)",
+        )
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 412);
+
+    let resp = fetch!(b"PUT", &url)
+        .header("If", format!("({token})"))
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 201);
+
+    // refresh
+    let resp = fetch!(b"LOCK", &url)
+        .header("If", format!("({token})"))
+        .header("Timeout", "Second-60")
+        .send()?;
+    assert_eq!(resp.status(), 200);
+    assert!(resp.text()?.contains("<D:timeout>Second-60</D:timeout>"));
+
+    let resp = fetch!(b"UNLOCK", &url)
+        .header("Lock-Token", "<opaquelocktoken:other>")
+        .send()?;
+    assert_eq!(resp.status(), 409);
+    let resp = fetch!(b"UNLOCK", &url)
+        .header("Lock-Token", &token)
+        .send()?;
+    assert_eq!(resp.status(), 204);
+    let resp = fetch!(b"UNLOCK", &url)
+        .header("Lock-Token", &token)
+        .send()?;
+    assert_eq!(resp.status(), 409);
+    let resp = fetch!(b"DELETE", &url).send()?;
+    assert_eq!(resp.status(), 204);
+    Ok(())
+}
+
+#[rstest]
+fn lock_file_timeout(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}test.html", server.url());
+    let resp = fetch!(b"LOCK", &url).header("Timeout", "Second-1").send()?;
+    assert_eq!(resp.status(), 200);
+    let resp = fetch!(b"PUT", &url).body(b"abc".to_vec()).send()?;
+    assert_eq!(resp.status(), 423);
+    std::thread::sleep(std::time::Duration::from_millis(1500));
+    let resp = fetch!(b"PUT", &url).body(b"abc".to_vec()).send()?;
+    assert_eq!(resp.status(), 201);
+    Ok(())
+}
+
+#[rstest]
+fn lock_released_by_move(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}test.html", server.url());
+    let resp = fetch!(b"LOCK", &url).send()?;
+    let token = resp
+        .headers()
+        .get("lock-token")
+        .unwrap()
+        .to_str()?
+        .to_string();
+    let resp = fetch!(b"MOVE", &url)
+        .header("Destination", format!("{}test2.html", server.url()))
+        .send()?;
+    assert_eq!(resp.status(), 423);
+    let resp = fetch!(b"MOVE", &url)
+        .header("If", format!("({token})"))
+        .header("Destination", format!("{}test2.html", server.url()))
+        .send()?;
+    assert_eq!(resp.status(), 201);
+    let resp = fetch!(b"PUT", &url).body(b"abc".to_vec()).send()?;
+    assert_eq!(resp.status(), 201);
+    Ok(())
+}
+
+#[rstest]
+fn if_header(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}test.html", server.url());
+    let resp = fetch!(b"HEAD", &url).send()?;
+    let etag = resp.headers().get("etag").unwrap().to_str()?.to_string();
+
+    let resp = fetch!(b"PUT", &url)
+        .header("If", r#"(["0-0"])"#)
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 412);
+    let resp = fetch!(b"PUT", &url)
+        .header("If", format!(r#"(["0-0"]) ([{etag}])"#))
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 201);
+    let resp = fetch!(b"DELETE", &url)
+        .header("If", "(<opaquelocktoken:unknown>)")
+        .send()?;
+    assert_eq!(resp.status(), 412);
+    let resp = fetch!(b"DELETE", &url)
+        .header("If", "(Not <DAV:no-lock>)")
+        .send()?;
+    assert_eq!(resp.status(), 204);
+    Ok(())
+}
+
+#[rstest]
+fn if_header_tagged(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
+    let url = format!("{}dir1/test.html", server.url());
+    let resp = fetch!(b"LOCK", &url).send()?;
+    let token = resp
+        .headers()
+        .get("lock-token")
+        .unwrap()
+        .to_str()?
+        .to_string();
+    let resp = fetch!(b"DELETE", format!("{}dir1", server.url())).send()?;
+    assert_eq!(resp.status(), 423);
+    let resp = fetch!(b"DELETE", format!("{}dir1", server.url()))
+        .header("If", format!("<{url}> ({token})"))
+        .send()?;
+    assert_eq!(resp.status(), 204);
+    Ok(())
+}
+
+#[rstest]
+#[case("(<a>")]
+#[case("<http://localhost/a>")]
+#[case("(<a>) <http://localhost/a> (<b>)")]
+#[case("()")]
+fn if_header_malformed(
+    #[with(&["-A"])] server: TestServer,
+    #[case] value: &str,
+) -> Result<(), Error> {
+    let resp = fetch!(b"PUT", format!("{}test.html", server.url()))
+        .header("If", value)
+        .body(b"abc".to_vec())
+        .send()?;
+    assert_eq!(resp.status(), 400);
+    Ok(())
+}
+
 #[rstest]
 fn lock_file_404(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     let resp = fetch!(b"LOCK", format!("{}404", server.url())).send()?;
@@ -217,6 +773,11 @@ fn lock_file_404(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
 fn unlock_file(#[with(&["-A"])] server: TestServer) -> Result<(), Error> {
     let resp = fetch!(b"LOCK", format!("{}test.html", server.url())).send()?;
     assert_eq!(resp.status(), 200);
+    let token = resp.headers().get("lock-token").unwrap().clone();
+    let resp = fetch!(b"UNLOCK", format!("{}test.html", server.url()))
+        .header("Lock-Token", token)
+        .send()?;
+    assert_eq!(resp.status(), 204);
     Ok(())
 }
 